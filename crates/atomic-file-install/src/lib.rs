@@ -14,6 +14,12 @@ use std::os::unix::fs::symlink as symlink_file_inner;
 #[cfg(windows)]
 use std::os::windows::fs::symlink_file as symlink_file_inner;
 
+/// Prefix of the files renamed aside by [`persist_after_renaming_aside`].
+const OLD_FILE_PREFIX: &str = ".binstall-old-";
+
+/// Length of the random suffix [`tempfile::Builder`] appends by default.
+const OLD_FILE_RAND_LEN: usize = 6;
+
 fn parent(p: &Path) -> io::Result<&Path> {
     p.parent().ok_or_else(|| {
         io::Error::new(
@@ -111,6 +117,45 @@ pub fn atomic_install(src: &Path, dst: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Create a hard link at `dst` to `src`, this fails if the `dst` already
+/// exists.
+///
+/// This is a blocking function, must be called in `block_in_place` mode.
+pub fn atomic_hard_link_noclobber(src: &Path, dst: &Path) -> io::Result<()> {
+    debug!(
+        "Creating hard link '{}' to file '{}', fail if dst already exists",
+        dst.display(),
+        src.display()
+    );
+
+    fs::hard_link(src, dst)
+}
+
+/// Atomically create a hard link at `dst` to `src`, this atomically replace
+/// `dst` if it already exists.
+///
+/// Fails if `src` and `dst` are not on the same filesystem.
+///
+/// This is a blocking function, must be called in `block_in_place` mode.
+pub fn atomic_hard_link(src: &Path, dst: &Path) -> io::Result<()> {
+    let parent = parent(dst)?;
+
+    debug!("Creating tempPath at '{}'", parent.display());
+    let temp_path = NamedTempFile::new_in(parent)?.into_temp_path();
+    // Remove this file so that we can create a hard link
+    // with the name.
+    fs::remove_file(&temp_path)?;
+
+    debug!(
+        "Creating hard link '{}' to file '{}'",
+        temp_path.display(),
+        src.display()
+    );
+    fs::hard_link(src, &temp_path)?;
+
+    persist(temp_path, dst)
+}
+
 /// Create a symlink at `link` to `dest`, this fails if the `link`
 /// already exists.
 ///
@@ -153,8 +198,15 @@ pub fn atomic_symlink_file(dest: &Path, link: &Path) -> io::Result<()> {
 
         #[cfg(windows)]
         // Symlinks on Windows are disabled in some editions, so creating one is unreliable.
-        // Fallback to copy if it fails.
-        Err(_) => atomic_install(dest, link),
+        // Fallback to hardlink or copy if it fails.
+        Err(_) => {
+            // `dest` might be relative to the parent of `link`
+            let dest = parent.join(dest);
+            match fs::hard_link(&dest, &temp_path) {
+                Ok(()) => persist(temp_path, link),
+                Err(_) => atomic_install(&dest, link),
+            }
+        }
 
         #[cfg(not(windows))]
         Err(err) => Err(err),
@@ -175,13 +227,125 @@ fn persist(temp_path: TempPath, to: &Path) -> io::Result<()> {
                 temp_path.display(),
                 to.display(),
             );
-            win::replace_file(&temp_path, to).map_err(io::Error::from)
+            match win::replace_file(&temp_path, to) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    warn!(
+                        "ReplaceFileW failed: {err}, fallback to renaming '{}' aside",
+                        to.display()
+                    );
+                    persist_after_renaming_aside(temp_path, to)
+                }
+            }
         }
         #[cfg(not(windows))]
         Err(err) => Err(err.into()),
     }
 }
 
+/// A running executable (e.g. binstall upgrading itself) cannot be
+/// replaced or removed on windows, but it can be renamed, so move `to`
+/// aside before persisting `temp_path` to it.
+#[cfg(windows)]
+fn persist_after_renaming_aside(temp_path: TempPath, to: &Path) -> io::Result<()> {
+    let old_path = tempfile::Builder::new()
+        .prefix(OLD_FILE_PREFIX)
+        .rand_bytes(OLD_FILE_RAND_LEN)
+        .tempfile_in(parent(to)?)?
+        .into_temp_path();
+
+    debug!("Renaming '{}' to '{}'", to.display(), old_path.display());
+    fs::rename(to, &old_path)?;
+
+    match temp_path.persist(to) {
+        Ok(()) => {
+            // This fails if the old executable is still running,
+            // in which case it is left behind.
+            if let Err(err) = old_path.close() {
+                warn!("Failed to remove the old file: {err}");
+            }
+            Ok(())
+        }
+        Err(err) => {
+            // Restore the old file
+            let old_path = old_path.keep()?;
+            fs::rename(old_path, to)?;
+            Err(err.into())
+        }
+    }
+}
+
+/// Remove the files left behind in `dir` by previous installations, that
+/// renamed a running executable aside on windows but could not remove it
+/// while it was running.
+///
+/// Files still in use are skipped, so that they are removed on a later run.
+///
+/// This is a blocking function, must be called in `block_in_place` mode.
+pub fn remove_stale_files(dir: &Path) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+
+        let is_stale = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix(OLD_FILE_PREFIX))
+            .map_or(false, |rand| {
+                rand.len() == OLD_FILE_RAND_LEN && rand.bytes().all(|b| b.is_ascii_alphanumeric())
+            });
+
+        if is_stale && entry.file_type()?.is_file() {
+            let path = entry.path();
+            debug!("Removing stale file '{}'", path.display());
+
+            if let Err(err) = fs::remove_file(&path) {
+                debug!("Failed to remove '{}': {err}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_remove_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let stale = dir.path().join(".binstall-old-Ab3dE9");
+        let kept = [
+            ".oldAb3dE9",
+            ".binstall-old-",
+            ".binstall-old-config1",
+            "cargo-binstall",
+        ];
+
+        fs::write(&stale, b"old").unwrap();
+        for name in kept {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        remove_stale_files(dir.path()).unwrap();
+
+        assert!(!stale.exists());
+        for name in kept {
+            assert!(dir.path().join(name).exists(), "{name}");
+        }
+
+        // Missing dir is not an error.
+        remove_stale_files(&dir.path().join("missing")).unwrap();
+    }
+}
+
 #[cfg(windows)]
 mod win {
     use std::{os::windows::ffi::OsStrExt, path::Path};
@@ -2,24 +2,26 @@ use std::{
     env,
     ffi::OsString,
     fmt,
-    num::{NonZeroU16, NonZeroU64, ParseIntError},
+    num::{NonZeroU16, NonZeroU64, NonZeroUsize, ParseIntError},
     path::PathBuf,
     str::FromStr,
 };
 
 use binstalk::{
     helpers::remote,
-    manifests::cargo_toml_binstall::PkgFmt,
+    manifests::cargo_toml_binstall::{self, PkgFmt},
     ops::resolve::{CrateName, VersionReqExt},
     registry::Registry,
 };
+use binstalk_manifests::binstall_config::BinstallConfig;
 use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
 use compact_str::CompactString;
-
-use log::LevelFilter;
+use dirs::home_dir;
 use semver::VersionReq;
 use strum::EnumCount;
 use strum_macros::EnumCount;
+use tracing::level_filters::LevelFilter;
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -46,14 +48,29 @@ pub struct Args {
     #[clap(
         help_heading = "Package selection",
         value_name = "crate[@version]",
-        required_unless_present_any = ["version", "help"],
+        required_unless_present_any = [
+            "version",
+            "help",
+            "self_install",
+            "list",
+            "completions",
+            "installed_crate_names",
+            "print_config",
+            "verify",
+            "info",
+            "check_metadata",
+            "cache_stats",
+            "clear_cache",
+            "url",
+        ],
     )]
     pub(crate) crate_names: Vec<CrateName>,
 
     /// Package version to install.
     ///
     /// Takes either an exact semver version or a semver version requirement expression, which will
-    /// be resolved to the highest matching version available.
+    /// be resolved to the highest matching version available, e.g. `1.2.3`, `^1.2`, `1.*` or
+    /// `>=0.10, <0.12`.
     ///
     /// Cannot be used when multiple packages are installed at once, use the attached version
     /// syntax in that case.
@@ -64,6 +81,23 @@ pub struct Args {
     )]
     pub(crate) version_req: Option<VersionReq>,
 
+    /// Consider pre-releases when looking for the latest version of a crate.
+    ///
+    /// Without it, pre-releases are only installed if the version
+    /// requirement explicitly asks for one, e.g. `--version 2.0.0-beta.1`.
+    #[clap(help_heading = "Package selection", long)]
+    pub(crate) include_prereleases: bool,
+
+    /// Install only the specified binaries, instead of all binaries
+    /// provided by the crate.
+    ///
+    /// This option can be specified multiple times, and it is an error
+    /// if the crate does not provide the specified binary.
+    ///
+    /// Cannot be used when multiple packages are installed at once.
+    #[clap(help_heading = "Package selection", long = "bin", value_name = "NAME")]
+    pub(crate) bins: Option<Vec<CompactString>>,
+
     /// Override binary target set.
     ///
     /// Binstall is able to look for binaries for several targets, installing the first one it finds
@@ -101,10 +135,56 @@ pub struct Args {
     /// runs as if `--manifest-path $cloned_repo` is passed to binstall.
     ///
     /// This option cannot be used with `--manifest-path`.
-    #[clap(help_heading = "Overrides", long, conflicts_with("manifest_path"))]
+    #[clap(
+        help_heading = "Overrides",
+        long,
+        conflicts_with_all = ["manifest_path", "url"]
+    )]
     pub(crate) git: Option<binstalk::registry::GitUrl>,
 
+    /// Install a package downloaded from the url directly, for tools that are
+    /// not published as crates.
+    ///
+    /// The package format is detected as usual, and the package is searched
+    /// for the binary named by `--name`. The lowest version matching
+    /// `--version` is recorded as the version installed, e.g. `1.2.0` for
+    /// `1.2`, otherwise the version in the file name of the url, e.g. `1.2.3`
+    /// for `tool-v1.2.3.tar.gz`, or `0.0.0` if there is none.
+    ///
+    /// Since cargo does not know about the package, it is only recorded in
+    /// the manifest of binstall, and not in `.crates.toml`. A crate already
+    /// installed with the same name is only replaced with `--force`.
+    #[clap(
+        help_heading = "Overrides",
+        long,
+        value_name = "URL",
+        requires = "url_name",
+        conflicts_with_all = ["crate_names", "manifest_path", "self_install"]
+    )]
+    pub(crate) url: Option<remote::Url>,
+
+    /// Name of the binary to install from `--url`.
+    #[clap(
+        help_heading = "Overrides",
+        long = "name",
+        value_name = "NAME",
+        requires = "url"
+    )]
+    pub(crate) url_name: Option<CompactString>,
+
+    /// SHA256 the package downloaded from `--url` must match, hex-encoded.
+    #[clap(
+        help_heading = "Overrides",
+        long = "sha256",
+        value_name = "SHA256",
+        requires = "url"
+    )]
+    pub(crate) url_sha256: Option<CompactString>,
+
     /// Override Cargo.toml package manifest bin-dir.
+    ///
+    /// It is a template of the path to the binary within the package, using the same
+    /// variables as `pkg-url`, e.g. `{ name }-{ target }/{ bin }{ binary-ext }`.
     #[clap(help_heading = "Overrides", long)]
     pub(crate) bin_dir: Option<String>,
 
@@ -125,10 +205,25 @@ pub struct Args {
     /// - zip: Download format is Zip
     ///
     /// - bin: Download format is raw / binary
+    ///
+    /// - bz2: Download format is raw / binary compressed with Bzip2
+    ///
+    /// - gz: Download format is raw / binary compressed with GZip
+    ///
+    /// - xz: Download format is raw / binary compressed with XZ
+    ///
+    /// - zstd: Download format is raw / binary compressed with Zstd
     #[clap(help_heading = "Overrides", long, value_name = "PKG_FMT")]
     pub(crate) pkg_fmt: Option<PkgFmt>,
 
     /// Override Cargo.toml package manifest pkg-url.
+    ///
+    /// It is templated just like the `pkg-url` in the manifest, which makes it possible to
+    /// install crates without (or with broken) binstall metadata, e.g.
+    ///
+    /// `--pkg-url "{ repo }/releases/download/v{ version }/{ name }-{ target }.tgz" --pkg-fmt tgz`
+    ///
+    /// See `SUPPORT.md` for the variables available.
     #[clap(help_heading = "Overrides", long)]
     pub(crate) pkg_url: Option<String>,
 
@@ -147,6 +242,34 @@ pub struct Args {
     #[clap(help_heading = "Overrides", long, default_value_t = RateLimit::default(), env = "BINSTALL_RATE_LIMIT")]
     pub(crate) rate_limit: RateLimit,
 
+    /// Limit the total bandwidth used for downloading packages, in bytes per second.
+    ///
+    /// Suffix `K`, `M` or `G` can be used for KiB, MiB or GiB, e.g. `512K`.
+    #[clap(help_heading = "Options", long, value_name = "BYTES_PER_SEC")]
+    pub(crate) limit_rate: Option<ByteRate>,
+
+    /// Timeout for each http request in seconds, from connecting to the server
+    /// until the response is fully received.
+    ///
+    /// There is no timeout by default, since downloading large packages
+    /// over slow connections might take a long time. Regardless of this
+    /// timeout, requests fail if the server sends no data for 60 seconds.
+    #[clap(help_heading = "Options", long, value_name = "SECS")]
+    pub(crate) timeout: Option<u64>,
+
+    /// Timeout for connecting to the server in seconds.
+    ///
+    /// Defaults to `http.timeout` in cargo config, or 30 seconds if it is
+    /// not set.
+    #[clap(help_heading = "Options", long, value_name = "SECS")]
+    pub(crate) connect_timeout: Option<u64>,
+
+    /// Maximum number of concurrent http requests to each host.
+    ///
+    /// There is no limit by default.
+    #[clap(help_heading = "Options", long, value_name = "N")]
+    pub(crate) max_connections_per_host: Option<NonZeroUsize>,
+
     /// Specify the strategies to be used,
     /// binstall will run the strategies specified in order.
     ///
@@ -160,6 +283,15 @@ pub struct Args {
     #[clap(help_heading = "Overrides", long, value_delimiter(','))]
     pub(crate) disable_strategies: Vec<Strategy>,
 
+    /// Send the name, version and target of crates looked up on quickinstall
+    /// to its stats server, so that packages missing from quickinstall get
+    /// built upstream.
+    ///
+    /// Nothing is sent without this flag. Sending is done in the background
+    /// and never delays or fails the installation.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) send_quickinstall_stats: bool,
+
     /// If `--github-token` or environment variable `GITHUB_TOKEN`/`GH_TOKEN`
     /// is not specified, then cargo-binstall will try to extract github token from
     /// `$HOME/.git-credentials` or `$HOME/.config/gh/hosts.yml` by default.
@@ -174,20 +306,73 @@ pub struct Args {
     #[clap(help_heading = "Options", long, default_value_t = true)]
     pub(crate) no_symlinks: bool,
 
-    /// Dry run, fetch and show changes without installing binaries.
+    /// Install binaries as `<bin>-v<version>` with a `<bin>` symlink pointing to it.
+    ///
+    /// This allows multiple versions of a binary to coexist, switching between
+    /// them is just a matter of updating the symlink.
+    ///
+    /// On Windows where symlinks might be unavailable, a hardlink or a copy of
+    /// the binary is created instead.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) versioned_symlinks: bool,
+
+    /// Dry run, resolve and show changes without downloading or installing binaries.
     #[clap(help_heading = "Options", long)]
     pub(crate) dry_run: bool,
 
     /// Disable interactive mode / confirmation prompts.
-    #[clap(help_heading = "Options", short = 'y', long)]
+    ///
+    /// Confirmation is also skipped if stdin is not a terminal, e.g. in CI.
+    #[clap(
+        help_heading = "Options",
+        short = 'y',
+        long,
+        env = "BINSTALL_NO_CONFIRM"
+    )]
     pub(crate) no_confirm: bool,
 
     /// Do not cleanup temporary files.
+    ///
+    /// The downloaded and extracted packages are kept even if the
+    /// installation fails or is interrupted, for debugging.
     #[clap(help_heading = "Options", long)]
     pub(crate) no_cleanup: bool,
 
+    /// Directory to create the temporary dir of each run in, for downloads
+    /// and extraction.
+    ///
+    /// It defaults to the temporary directory of the system, i.e. `$TMPDIR`
+    /// or `/tmp` on unix and `%TEMP%` on windows. Binaries are still moved
+    /// into place atomically if it is on another filesystem than the install
+    /// path, by copying them next to their destination first.
+    #[clap(
+        help_heading = "Options",
+        long,
+        env = "BINSTALL_TEMP_DIR",
+        value_name = "DIR"
+    )]
+    pub(crate) temp_dir: Option<PathBuf>,
+
+    /// Do not cache downloaded packages.
+    ///
+    /// By default, downloaded packages are cached in
+    /// `$CARGO_HOME/binstall/cache` and reused by later runs if their SHA256
+    /// still matches and the server still reports the same `ETag` for them,
+    /// the least recently used ones are removed once the cache grows larger
+    /// than 1GiB. Use `--clear-cache` to remove them all.
+    ///
+    /// Responses of GitHub Restful API are also cached there and revalidated
+    /// with their `ETag`, which does not count against GitHub's rate limit.
+    ///
+    /// Installed binaries are also stored there, so that installing the same
+    /// binary to another root creates a hard link instead of a copy.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) no_cache: bool,
+
     /// By default, binstall keeps track of the installed packages with metadata files
-    /// stored in the installation root directory.
+    /// stored in the installation root directory, including cargo's `.crates.toml`
+    /// and `.crates2.json` so that `cargo install --list` and `cargo uninstall`
+    /// work with them.
     ///
     /// This flag tells binstall not to use or create that file.
     ///
@@ -195,12 +380,22 @@ pub struct Args {
     /// `--force` flag is used.
     ///
     /// This also disables binstall’s ability to protect against multiple concurrent
-    /// invocations of binstall installing at the same time.
-    ///
-    /// This flag will also be passed to `cargo-install` if it is invoked.
+    /// invocations of binstall resolving crates at the same time, though the
+    /// installation itself is still locked unless `--no-lock` is specified.
     #[clap(help_heading = "Options", long)]
     pub(crate) no_track: bool,
 
+    /// Do not lock the installation root while installing binaries.
+    ///
+    /// By default, binstall takes an advisory lock on `<root>/binstall/.install-lock`
+    /// while moving binaries into place and updating the metadata files, so that
+    /// concurrent invocations, e.g. in parallel CI jobs, sharing the same root
+    /// do not race with each other.
+    ///
+    /// This is an escape hatch for filesystems that do not support file locking.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) no_lock: bool,
+
     /// Install binaries in a custom location.
     ///
     /// By default, binaries are installed to the global location `$CARGO_HOME/bin`, and global
@@ -216,8 +411,10 @@ pub struct Args {
     /// cargo root and global metadata files are updated with the
     /// package information.
     ///
-    /// Specifying another path here would install the binaries and update
-    /// the metadata files inside the path you specified.
+    /// Specifying another path here would install the binaries to its `bin`
+    /// dir and keep track of them with the metadata files inside the path
+    /// you specified, so that each root has its own set of installed crates.
+    /// A relative path is relative to the current dir.
     ///
     /// NOTE that `--install-path` takes precedence over this option.
     #[clap(help_heading = "Options", long, alias = "roots")]
@@ -250,7 +447,8 @@ pub struct Args {
     )]
     pub(crate) registry: Option<CompactString>,
 
-    /// This option will be passed through to all `cargo-install` invocations.
+    /// This option will be passed through to all `cargo build` invocations
+    /// when building crates from source.
     ///
     /// It will require `Cargo.lock` to be up to date.
     #[clap(help_heading = "Options", long)]
@@ -264,6 +462,55 @@ pub struct Args {
     #[clap(help_heading = "Options", long)]
     pub(crate) force: bool,
 
+    /// Error out instead of overwriting existing binaries not installed by
+    /// binstall or cargo, e.g. binaries installed by other tools or manually.
+    ///
+    /// Binaries of crates tracked in the installation root can still be upgraded,
+    /// and `--force` overwrites existing binaries regardless.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) strict: bool,
+
+    /// Hosts to trust to download packages from, in addition to github.com
+    /// and gitlab.com.
+    ///
+    /// Subdomains of the hosts are trusted as well. Downloading from other
+    /// hosts prints a warning, or is refused with `--only-trusted-hosts`.
+    #[clap(
+        help_heading = "Options",
+        long,
+        value_name = "HOST",
+        value_delimiter(',')
+    )]
+    pub(crate) trusted_hosts: Vec<CompactString>,
+
+    /// Error out instead of warning if the package would be downloaded
+    /// from a host not in `--trusted-hosts`.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) only_trusted_hosts: bool,
+
+    /// Download GitHub release artifacts from a mirror instead of github.com.
+    ///
+    /// Urls like `https://github.com/{org}/{repo}/releases/download/{tag}/{file}`
+    /// are rewritten to `{mirror}/{org}/{repo}/releases/download/{tag}/{file}`,
+    /// e.g. `--github-mirror https://ghproxy.com/https://github.com`.
+    #[clap(
+        help_heading = "Options",
+        long,
+        value_name = "URL",
+        env = "BINSTALL_GITHUB_MIRROR"
+    )]
+    pub(crate) github_mirror: Option<remote::Url>,
+
+    /// Install binaries even if they are not built for the target they
+    /// are downloaded for.
+    ///
+    /// By default, binstall parses the headers of the binaries (ELF, PE or
+    /// Mach-O) and refuses to install one built for another architecture or
+    /// OS, or a gnu one requiring a newer glibc than the host has, trying
+    /// the next source instead.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) no_verify_arch: bool,
+
     /// Require a minimum TLS version from remote endpoints.
     ///
     /// The default is not to require any minimum TLS version, and use the negotiated highest
@@ -277,7 +524,11 @@ pub struct Args {
     pub(crate) root_certificates: Vec<PathBuf>,
 
     /// Print logs in json format to be parsable.
-    #[clap(help_heading = "Options", long)]
+    ///
+    /// Resolution results, installed binaries and errors carry
+    /// structured fields, such as the crate name, version, package url
+    /// and the exit code, to make them easy to post-process.
+    #[clap(help_heading = "Options", long, alias = "json")]
     pub json_output: bool,
 
     /// Provide the github token for accessing the restful API of api.github.com
@@ -296,7 +547,9 @@ pub struct Args {
     ///
     /// The default is to verify signatures if they are available, but to allow
     /// unsigned packages as well.
-    #[clap(help_heading = "Options", long)]
+    ///
+    /// Packages without a signature are rejected.
+    #[clap(help_heading = "Options", long, alias = "require-signatures")]
     pub(crate) only_signed: bool,
 
     /// Don't check any signatures
@@ -313,6 +566,158 @@ pub struct Args {
     #[clap(help_heading = "Meta", short = 'V')]
     pub version: bool,
 
+    /// Upgrade cargo-binstall itself to its latest release.
+    ///
+    /// This is equivalent to `cargo binstall cargo-binstall`, the release
+    /// for the current target is resolved, downloaded, verified and then
+    /// installed to the install path, `$CARGO_HOME/bin` by default.
+    ///
+    /// A warning is printed if the running cargo-binstall is located
+    /// elsewhere, since it is left untouched.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        conflicts_with_all = ["crate_names", "version_req", "manifest_path", "bins"]
+    )]
+    pub(crate) self_install: bool,
+
+    /// List crates installed by cargo-binstall, along with their versions,
+    /// install dates, where they are downloaded from and their binaries.
+    ///
+    /// Use with `--json` to print the list in json format.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        conflicts_with_all = ["crate_names", "version_req", "self_install"]
+    )]
+    pub(crate) list: bool,
+
+    /// Print shell completions for cargo-binstall to stdout.
+    ///
+    /// For bash and fish, `cargo binstall` is completed as well as
+    /// `cargo-binstall`, and the names of crates installed by cargo-binstall
+    /// are completed for `--verify`. The other shells only complete the
+    /// options of `cargo-binstall`.
+    ///
+    /// Example for bash, which has to be loaded after the completions of
+    /// cargo to complete `cargo binstall`:
+    ///
+    /// `cargo binstall --completions bash > ~/.local/share/bash-completion/completions/cargo-binstall`
+    /// `echo 'source ~/.local/share/bash-completion/completions/cargo-binstall' >> ~/.bashrc`
+    #[clap(help_heading = "Meta", long, value_name = "SHELL", exclusive(true))]
+    pub(crate) completions: Option<Shell>,
+
+    /// Print names of crates installed by cargo-binstall, one per line.
+    ///
+    /// Used by the shell completions.
+    #[clap(hide(true), long, conflicts_with_all = ["crate_names", "list"])]
+    pub(crate) installed_crate_names: bool,
+
+    /// Print the effective configuration, which merges the options on the
+    /// command line with the config file and the defaults.
+    ///
+    /// The config file is `$BINSTALL_CONFIG` if set, otherwise
+    /// `binstall/config.toml` in `$XDG_CONFIG_HOME` or `$HOME/.config`.
+    ///
+    /// Example:
+    ///
+    /// `strategies = ["crate-meta-data", "compile"]`
+    ///
+    /// `targets = ["x86_64-unknown-linux-musl"]`
+    ///
+    /// `github-token = "..."`
+    ///
+    /// `proxy = "host:port"`
+    ///
+    /// `root = "/path/to/root"`
+    ///
+    /// `no-confirm = true`
+    ///
+    /// `trusted-hosts = ["example.com"]`
+    ///
+    /// `github-mirror = "https://mirror.example.com/github"`
+    ///
+    /// `send-quickinstall-stats = true`
+    #[clap(
+        help_heading = "Meta",
+        long,
+        conflicts_with_all = ["crate_names", "list", "installed_crate_names"]
+    )]
+    pub(crate) print_config: bool,
+
+    /// Verify binaries installed against the SHA256 checksums recorded when
+    /// installing them, to detect tampering or manual replacement.
+    ///
+    /// The crates to verify are given as the crate names, all crates
+    /// installed by cargo-binstall are verified if none is given.
+    ///
+    /// `--root` and `--install-path` are respected.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        conflicts_with_all = ["version_req", "self_install", "list", "print_config"]
+    )]
+    pub(crate) verify: bool,
+
+    /// Print the binstall metadata of a crate and probe for its packages,
+    /// without downloading or installing anything.
+    ///
+    /// It prints the `pkg-url`, `pkg-fmt` and `bin-dir` declared by the
+    /// crate, its binaries, and whether the package is found by each
+    /// strategy for each target, which is useful for debugging the
+    /// metadata before releasing.
+    ///
+    /// Other options, e.g. `--targets`, `--strategies`, `--manifest-path`
+    /// or `--pkg-url`, are respected.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        value_name = "crate[@version]",
+        conflicts_with_all = ["crate_names", "self_install", "list", "print_config", "verify"]
+    )]
+    pub(crate) info: Option<CrateName>,
+
+    /// Validate the binstall metadata in a `Cargo.toml`, or the dir
+    /// containing it, for maintainers to check it before releasing.
+    ///
+    /// It reports unknown keys, invalid templates and unknown placeholders
+    /// along with the offending key, and exits with an error if the metadata
+    /// cannot be used.
+    ///
+    /// Defaults to the `Cargo.toml` in the current dir.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "Cargo.toml",
+        conflicts_with_all = [
+            "crate_names",
+            "self_install",
+            "list",
+            "print_config",
+            "verify",
+            "info",
+        ]
+    )]
+    pub(crate) check_metadata: Option<PathBuf>,
+
+    /// Print the size of the cache in `$CARGO_HOME/binstall/cache`, the
+    /// ratio of downloads served from it and the disk space saved by hard
+    /// linking binaries from it.
+    #[clap(help_heading = "Meta", long, exclusive(true))]
+    pub(crate) cache_stats: bool,
+
+    /// Remove all packages and binaries in the cache in
+    /// `$CARGO_HOME/binstall/cache`, along with its stats.
+    #[clap(help_heading = "Meta", long, exclusive(true))]
+    pub(crate) clear_cache: bool,
+
+    /// Proxy for https connections from the config file, used if
+    /// `HTTPS_PROXY` is not set.
+    #[clap(skip)]
+    pub(crate) proxy: Option<CompactString>,
+
     /// Utility log level
     ///
     /// Set to `trace` to print very low priority, often extremely
@@ -327,7 +732,7 @@ pub struct Args {
     /// Set to `error` to only print serious errors.
     ///
     /// Set to `off` to disable logging completely, this will also
-    /// disable output from `cargo-install`.
+    /// disable output from `cargo build`.
     ///
     /// If `--log-level` is not specified on cmdline, then cargo-binstall
     /// will try to read environment variable `BINSTALL_LOG_LEVEL` and
@@ -345,6 +750,23 @@ pub struct Args {
     /// This would override the `log_level`.
     #[clap(help_heading = "Meta", short, long, conflicts_with("verbose"))]
     pub(crate) quiet: bool,
+
+    /// Also append logs in json format to `FILE`, e.g. to keep them as an
+    /// artifact of CI runs for debugging.
+    ///
+    /// Logs are written to it at `debug` level, or `trace` if specified by
+    /// `--log-level`, regardless of `--quiet`. Each event carries the span
+    /// of the crate being resolved or installed, so that logs of crates
+    /// installed in parallel can be told apart.
+    ///
+    /// cargo-binstall exits with an error if `FILE` cannot be opened.
+    #[clap(
+        help_heading = "Meta",
+        long,
+        value_name = "FILE",
+        env = "BINSTALL_LOG_FILE"
+    )]
+    pub(crate) log_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
@@ -403,6 +825,30 @@ impl Default for RateLimit {
     }
 }
 
+/// Bandwidth in bytes per second.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ByteRate(pub(crate) NonZeroU64);
+
+impl FromStr for ByteRate {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (num, shift) = match s.as_bytes().last() {
+            Some(b'k' | b'K') => (&s[..s.len() - 1], 10),
+            Some(b'm' | b'M') => (&s[..s.len() - 1], 20),
+            Some(b'g' | b'G') => (&s[..s.len() - 1], 30),
+            _ => (s, 0),
+        };
+
+        let num: NonZeroU64 = num.parse()?;
+        // Saturate instead of overflowing for unreasonably large input
+        Ok(Self(
+            num.checked_mul(NonZeroU64::new(1 << shift).unwrap())
+                .unwrap_or(NonZeroU64::MAX),
+        ))
+    }
+}
+
 /// Strategy for installing the package
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum, EnumCount)]
 #[repr(u8)]
@@ -416,6 +862,122 @@ pub(crate) enum Strategy {
     Compile,
 }
 
+impl From<cargo_toml_binstall::Strategy> for Strategy {
+    fn from(strategy: cargo_toml_binstall::Strategy) -> Self {
+        match strategy {
+            cargo_toml_binstall::Strategy::CrateMetaData => Strategy::CrateMetaData,
+            cargo_toml_binstall::Strategy::QuickInstall => Strategy::QuickInstall,
+            cargo_toml_binstall::Strategy::Compile => Strategy::Compile,
+        }
+    }
+}
+
+impl From<Strategy> for cargo_toml_binstall::Strategy {
+    fn from(strategy: Strategy) -> Self {
+        match strategy {
+            Strategy::CrateMetaData => cargo_toml_binstall::Strategy::CrateMetaData,
+            Strategy::QuickInstall => cargo_toml_binstall::Strategy::QuickInstall,
+            Strategy::Compile => cargo_toml_binstall::Strategy::Compile,
+        }
+    }
+}
+
+/// Return path to the config file of binstall.
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("BINSTALL_CONFIG") {
+        return Some(path.into());
+    }
+
+    let mut path = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")))?;
+    path.push("binstall");
+    path.push("config.toml");
+
+    Some(path)
+}
+
+/// Fill in options not specified on the command line with `config`.
+fn apply_config(opts: &mut Args, config: BinstallConfig) {
+    if opts.strategies.is_empty() {
+        if let Some(strategies) = config.strategies {
+            opts.strategies = strategies.into_iter().map(Strategy::from).collect();
+        }
+    }
+
+    if opts.disable_strategies.is_empty() {
+        if let Some(strategies) = config.disable_strategies {
+            opts.disable_strategies = strategies.into_iter().map(Strategy::from).collect();
+        }
+    }
+
+    if opts.targets.is_none() {
+        opts.targets = config
+            .targets
+            .map(|targets| targets.into_iter().map(String::from).collect());
+    }
+
+    // `GH_TOKEN` is read later and takes precedence over the config.
+    if opts.github_token.is_none() && env::var_os("GH_TOKEN").is_none() {
+        opts.github_token = config.github_token;
+    }
+
+    // The proxy in the environment takes precedence over the config.
+    if env::var_os("HTTPS_PROXY").is_none() && env::var_os("https_proxy").is_none() {
+        opts.proxy = config.proxy;
+    }
+
+    if opts.root.is_none() {
+        opts.root = config.root;
+    }
+
+    if !opts.no_confirm {
+        opts.no_confirm = config.no_confirm.unwrap_or_default();
+    }
+
+    if opts.trusted_hosts.is_empty() {
+        opts.trusted_hosts = config.trusted_hosts.unwrap_or_default();
+    }
+
+    if opts.github_mirror.is_none() {
+        opts.github_mirror = config.github_mirror;
+    }
+
+    if !opts.send_quickinstall_stats {
+        opts.send_quickinstall_stats = config.send_quickinstall_stats.unwrap_or_default();
+    }
+}
+
+/// Return the configuration in effect, with the github token redacted.
+pub fn effective_config(opts: &Args) -> BinstallConfig {
+    BinstallConfig {
+        strategies: Some(opts.strategies.iter().copied().map(From::from).collect()),
+        disable_strategies: Some(
+            opts.disable_strategies
+                .iter()
+                .copied()
+                .map(From::from)
+                .collect(),
+        ),
+        targets: opts
+            .targets
+            .as_ref()
+            .map(|targets| targets.iter().map(CompactString::from).collect()),
+        github_token: opts.github_token.as_ref().map(|_| "<redacted>".into()),
+        proxy: opts.proxy.clone().or_else(|| {
+            env::var("HTTPS_PROXY")
+                .or_else(|_| env::var("https_proxy"))
+                .ok()
+                .map(CompactString::from)
+        }),
+        root: opts.root.clone(),
+        no_confirm: Some(opts.no_confirm),
+        trusted_hosts: Some(opts.trusted_hosts.clone()),
+        github_mirror: opts.github_mirror.clone(),
+        send_quickinstall_stats: Some(opts.send_quickinstall_stats),
+    }
+}
+
 pub fn parse() -> Args {
     // Filter extraneous arg when invoked by cargo
     // `cargo run -- --help` gives ["target/debug/cargo-binstall", "--help"]
@@ -439,6 +1001,35 @@ pub fn parse() -> Args {
     // Load options
     let mut opts = Args::parse_from(args);
 
+    // Ensure no conflict
+    let mut command = Args::command();
+
+    if let Some(path) = config_path() {
+        match BinstallConfig::load_from_path(&path) {
+            Ok(config) => apply_config(&mut opts, config),
+            Err(err) => command
+                .error(
+                    ErrorKind::Io,
+                    format_args!("Failed to load config file {}: {err}", path.display()),
+                )
+                .exit(),
+        }
+    }
+
+    if opts.self_install {
+        opts.crate_names = vec![CrateName {
+            name: env!("CARGO_PKG_NAME").into(),
+            version_req: None,
+        }];
+    }
+
+    if let Some(name) = opts.url_name.clone() {
+        opts.crate_names = vec![CrateName {
+            name,
+            version_req: None,
+        }];
+    }
+
     if opts.log_level.is_none() {
         if let Some(log) = env::var("BINSTALL_LOG_LEVEL")
             .ok()
@@ -446,18 +1037,17 @@ pub fn parse() -> Args {
         {
             opts.log_level = Some(log);
         } else if opts.quiet {
-            opts.log_level = Some(LevelFilter::Off);
+            opts.log_level = Some(LevelFilter::OFF);
         } else if opts.verbose {
-            opts.log_level = Some(LevelFilter::Debug);
+            opts.log_level = Some(LevelFilter::DEBUG);
         }
     }
 
-    // Ensure no conflict
-    let mut command = Args::command();
-
     if opts.crate_names.len() > 1 {
         let option = if opts.version_req.is_some() {
             "version"
+        } else if opts.bins.is_some() {
+            "bin"
         } else if opts.manifest_path.is_some() {
             "manifest-path"
         } else {
@@ -540,9 +1130,6 @@ You cannot use --{option} and specify multiple packages at the same time. Do one
                 .error(ErrorKind::TooFewValues, "You have disabled all strategies")
                 .exit()
         }
-
-        // Free disable_strategies as it will not be used again.
-        opts.disable_strategies = Vec::new();
     }
 
     // Ensure that Strategy::Compile is specified as the last strategy
@@ -572,4 +1159,19 @@ mod test {
     fn verify_cli() {
         Args::command().debug_assert()
     }
+
+    #[test]
+    fn parse_byte_rate() {
+        let parse = |s: &str| s.parse::<ByteRate>().map(|rate| rate.0.get());
+
+        assert_eq!(parse("100").unwrap(), 100);
+        assert_eq!(parse("2k").unwrap(), 2048);
+        assert_eq!(parse("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse("3M").unwrap(), 3 * 1024 * 1024);
+        assert_eq!(parse("1G").unwrap(), 1024 * 1024 * 1024);
+
+        assert!(parse("0").is_err());
+        assert!(parse("K").is_err());
+        assert!(parse("1T").is_err());
+    }
 }
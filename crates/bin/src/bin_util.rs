@@ -52,6 +52,10 @@ impl MainExit {
 ///
 /// It will cancel the future if user requested cancellation
 /// via signal.
+///
+/// Temporary files are removed as the future is dropped, while blocking
+/// code running in `block_in_place` is waited for when the runtime is
+/// dropped, so that binaries being installed are not left truncated.
 pub fn run_tokio_main<Func, Fut>(f: Func) -> Result<()>
 where
     Func: FnOnce() -> Result<Option<Fut>>,
@@ -0,0 +1,97 @@
+use std::io::{self, Write};
+
+use binstalk::{
+    errors::BinstallError,
+    helpers::download::{CacheStats, DownloadCache},
+};
+use compact_str::{format_compact, CompactString};
+use home::cargo_home;
+use miette::Result;
+
+/// Print the size of the download cache along with its stats.
+pub fn print_stats() -> Result<()> {
+    let download_cache = download_cache()?;
+
+    let stats = download_cache.load_stats().map_err(BinstallError::from)?;
+
+    write_stats(&download_cache, &stats).map_err(BinstallError::from)?;
+
+    Ok(())
+}
+
+/// Remove all files in the download cache.
+pub fn clear() -> Result<()> {
+    let download_cache = download_cache()?;
+
+    download_cache.clear().map_err(BinstallError::from)?;
+    println!("Cleared cache at {}", download_cache.dir().display());
+
+    Ok(())
+}
+
+fn download_cache() -> Result<DownloadCache> {
+    let cargo_home = cargo_home().map_err(BinstallError::from)?;
+
+    Ok(DownloadCache::new(
+        cargo_home.join("binstall").join("cache"),
+    ))
+}
+
+fn write_stats(download_cache: &DownloadCache, stats: &CacheStats) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    let hit_rate = stats
+        .hit_rate()
+        .map(|hit_rate| format_compact!("{:.1}%", hit_rate * 100.0))
+        .unwrap_or_else(|| "-".into());
+
+    writeln!(stdout, "cache: {}", download_cache.dir().display())?;
+    writeln!(stdout, "size: {}", format_size(stats.size))?;
+    writeln!(
+        stdout,
+        "hit rate: {hit_rate} ({} hits, {} misses)",
+        stats.hits, stats.misses
+    )?;
+    writeln!(
+        stdout,
+        "reclaimed: {} ({} binaries hard linked)",
+        format_size(stats.reclaimed),
+        stats.linked_bins
+    )?;
+
+    Ok(())
+}
+
+/// Format `size` in bytes with a binary unit, e.g. `1.5 MiB`.
+fn format_size(size: u64) -> CompactString {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format_compact!("{size} B");
+    }
+
+    let mut size = size as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next_unit;
+    }
+
+    format_compact!("{size:.1} {unit}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(1024 * 1024 * 1024), "1.0 GiB");
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use binstalk::ops::validate;
+use miette::{miette, Diagnostic, Report, Result, Severity};
+use tracing::{error, info, warn};
+
+/// Validate the binstall metadata in the `Cargo.toml` at `manifest_path`
+/// and report the issues found, returning an error if any of them is not
+/// a warning.
+pub fn check_metadata(manifest_path: &Path) -> Result<()> {
+    let (name, issues) = validate::check_metadata(manifest_path)?;
+
+    if issues.is_empty() {
+        info!("The binstall metadata of {name} is valid");
+        return Ok(());
+    }
+
+    let mut errors = 0;
+
+    for issue in issues {
+        if issue.severity() == Some(Severity::Warning) {
+            warn!("{:?}", Report::new(issue));
+        } else {
+            error!("{:?}", Report::new(issue));
+            errors += 1;
+        }
+    }
+
+    if errors == 0 {
+        Ok(())
+    } else {
+        Err(miette!(
+            "Found {errors} errors in the binstall metadata of {name}"
+        ))
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::args::Args;
+
+const BIN_NAME: &str = "cargo-binstall";
+
+/// Complete names of installed crates for `--verify` in bash, and register
+/// the completions for `cargo binstall` as well.
+///
+/// It wraps the completion function generated by clap, other completions of
+/// `cargo` are delegated to the completion function registered for it
+/// before, so this has to be loaded after the completions of cargo.
+const BASH_DYNAMIC: &str = r#"
+_cargo-binstall-dynamic() {
+    local word
+    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+        if [[ "$word" == --verify ]]; then
+            COMPREPLY=( $(compgen -W "$(cargo-binstall --installed-crate-names 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}") )
+            return 0
+        fi
+    done
+    _cargo__binstall "$@"
+}
+
+complete -F _cargo-binstall-dynamic -o bashdefault -o default cargo-binstall
+
+if ! complete -p cargo &>/dev/null && declare -F __load_completion &>/dev/null; then
+    __load_completion cargo
+fi
+_cargo_binstall_cargo_completion=$(complete -p cargo 2>/dev/null | sed -n 's/.* -F \([^ ]*\) .*/\1/p')
+
+_cargo-binstall-cargo() {
+    if [[ $COMP_CWORD -ge 2 && "${COMP_WORDS[1]}" == binstall ]]; then
+        # Complete `cargo binstall ...` as `cargo-binstall ...`
+        local -a COMP_WORDS=( cargo-binstall "${COMP_WORDS[@]:2}" )
+        local COMP_CWORD=$(( COMP_CWORD - 1 ))
+        _cargo-binstall-dynamic cargo-binstall "${COMP_WORDS[COMP_CWORD]}" "${COMP_WORDS[COMP_CWORD-1]}"
+    elif [[ -n "$_cargo_binstall_cargo_completion" ]]; then
+        "$_cargo_binstall_cargo_completion" "$@"
+    fi
+}
+
+complete -F _cargo-binstall-cargo -o bashdefault -o default cargo
+"#;
+
+/// Complete names of installed crates for `--verify` in fish, and register
+/// the completions for `cargo binstall` as well.
+const FISH_DYNAMIC: &str = r#"
+complete -c cargo-binstall -n "__fish_contains_opt verify" -f -a "(cargo-binstall --installed-crate-names 2>/dev/null)"
+
+function __cargo_binstall_is_subcommand
+    set -l tokens (commandline -opc)
+    test (count $tokens) -ge 2; and test "$tokens[2]" = binstall
+end
+
+function __cargo_binstall_complete
+    set -l tokens (commandline -opc)
+    set -e tokens[1..2]
+    complete -C (string join ' ' -- cargo-binstall (string escape -- $tokens))" "(commandline -ct)
+end
+
+complete -c cargo -n __cargo_binstall_is_subcommand -f -a "(__cargo_binstall_complete)"
+"#;
+
+/// Print completions for `shell` to stdout.
+pub fn generate(shell: Shell) {
+    let mut stdout = io::stdout().lock();
+
+    clap_complete::generate(shell, &mut Args::command(), BIN_NAME, &mut stdout);
+
+    let dynamic = match shell {
+        Shell::Bash => BASH_DYNAMIC,
+        Shell::Fish => FISH_DYNAMIC,
+        _ => "",
+    };
+
+    // Ignore errors such as broken pipe
+    stdout.write_all(dynamic.as_bytes()).ok();
+    stdout.flush().ok();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bash_fn() {
+        let mut completions = Vec::new();
+        clap_complete::generate(
+            Shell::Bash,
+            &mut Args::command(),
+            BIN_NAME,
+            &mut completions,
+        );
+
+        let completions = String::from_utf8(completions).unwrap();
+
+        // BASH_DYNAMIC calls the function generated by clap.
+        assert!(completions.contains("_cargo__binstall() {\n"));
+        assert!(BASH_DYNAMIC.contains("\n    _cargo__binstall \"$@\"\n"));
+    }
+}
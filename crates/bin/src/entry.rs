@@ -1,8 +1,10 @@
 use std::{
     env, fs,
     future::Future,
+    mem,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use binstalk::{
@@ -10,6 +12,7 @@ use binstalk::{
     fetchers::{Fetcher, GhCrateMeta, QuickInstall, SignaturePolicy},
     get_desired_targets,
     helpers::{
+        download::DownloadCache,
         gh_api_client::GhApiClient,
         jobserver_client::LazyJobserverClient,
         remote::{Certificate, Client},
@@ -17,78 +20,271 @@ use binstalk::{
     },
     ops::{
         self,
-        resolve::{CrateName, Resolution, ResolutionFetch, VersionReqExt},
+        resolve::{BuiltSource, CrateName, Resolution, ResolutionFetch, VersionReqExt},
         CargoTomlFetchOverride, Options, Resolver,
     },
 };
 use binstalk_manifests::{
-    cargo_config::Config, cargo_toml_binstall::PkgOverride, crates_manifests::Manifests,
+    cargo_config::Config,
+    cargo_toml_binstall::PkgOverride,
+    crate_info::CrateInfo,
+    crates_manifests::{InstallLock, Manifests},
 };
 use file_format::FileFormat;
 use home::cargo_home;
-use log::LevelFilter;
 use miette::{miette, Result, WrapErr};
 use tokio::task::block_in_place;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, level_filters::LevelFilter, warn};
 
 use crate::{
     args::{Args, Strategy},
-    gh_token, git_credentials, install_path,
-    ui::confirm,
+    gh_token, git_credentials, info, install_path, signal,
+    ui::{confirm, print_summary, should_confirm},
 };
 
+/// Maximum size of the download cache, in bytes.
+const MAX_DOWNLOAD_CACHE_SIZE: u64 = 1024 * 1024 * 1024;
+
 pub fn install_crates(
-    args: Args,
+    mut args: Args,
     jobserver_client: LazyJobserverClient,
 ) -> Result<Option<impl Future<Output = Result<()>>>> {
-    // Compute Resolvers
-    let mut cargo_install_fallback = false;
-
-    let resolvers: Vec<_> = args
-        .strategies
-        .into_iter()
-        .filter_map(|strategy| match strategy {
-            Strategy::CrateMetaData => Some(GhCrateMeta::new as Resolver),
-            Strategy::QuickInstall => Some(QuickInstall::new as Resolver),
-            Strategy::Compile => {
-                cargo_install_fallback = true;
-                None
-            }
-        })
-        .collect();
-
     // Load .cargo/config.toml
     let cargo_home = cargo_home().map_err(BinstallError::from)?;
     let mut config = Config::load_from_path(cargo_home.join("config.toml"))?;
 
+    let download_cache =
+        (!args.no_cache).then(|| DownloadCache::new(cargo_home.join("binstall").join("cache")));
+
     // Compute paths
-    let cargo_root = args.root;
-    let (install_path, mut manifests, temp_dir) = compute_paths_and_load_manifests(
-        cargo_root.clone(),
-        args.install_path,
+    let (cargo_roots, install_path, mut manifests, temp_dir) = compute_paths_and_load_manifests(
+        args.root.clone(),
+        args.install_path.take(),
         args.no_track,
+        args.temp_dir.take(),
         cargo_home,
         &mut config,
     )?;
 
+    if args.self_install {
+        if let Some(exe_dir) = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .filter(|exe_dir| !same_dir(exe_dir, &install_path))
+        {
+            warn!(
+                "cargo-binstall is running from {} but will be installed to {}, \
+                use --install-path to replace the running one",
+                exe_dir.display(),
+                install_path.display()
+            );
+        }
+    }
+
+    // With `--no-cleanup`, the temporary dir is kept even if the installation
+    // fails or is interrupted, otherwise it is removed once dropped.
+    let temp_dir_path = temp_dir.path().to_owned();
+    let temp_dir = if args.no_cleanup {
+        info!("Keeping temporary files in {}", temp_dir_path.display());
+        // Consume temp_dir without removing it from fs.
+        let _ = temp_dir.into_path();
+        None
+    } else {
+        Some(temp_dir)
+    };
+
     // Remove installed crates
-    let mut crate_names =
-        filter_out_installed_crates(args.crate_names, args.force, manifests.as_mut())?.peekable();
+    let mut crate_names = filter_out_installed_crates(
+        mem::take(&mut args.crate_names),
+        args.force,
+        manifests.as_mut(),
+    )?
+    .peekable();
 
     if crate_names.peek().is_none() {
         debug!("Nothing to do");
         return Ok(None);
     }
 
+    // Destruct args before any async function to reduce size of the future
+    let dry_run = args.dry_run;
+    let no_confirm = args.no_confirm;
+    let install_lock_root = (!args.no_lock && !dry_run).then_some(cargo_roots);
+
+    let binstall_opts = Arc::new(create_binstall_opts(
+        args,
+        config,
+        download_cache.clone(),
+        jobserver_client,
+        temp_dir_path,
+        install_path,
+    )?);
+
+    // Resolve crates
+    let tasks: Vec<_> = crate_names
+        .map(|(crate_name, current_version)| {
+            AutoAbortJoinHandle::spawn(ops::resolve::resolve(
+                binstall_opts.clone(),
+                crate_name,
+                current_version,
+            ))
+        })
+        .collect();
+
+    Ok(Some(async move {
+        // Collect results
+        let mut resolution_fetchs = Vec::new();
+        let mut resolution_sources = Vec::new();
+
+        for task in tasks {
+            match task.await?? {
+                Resolution::AlreadyUpToDate => {}
+                Resolution::Fetch(fetch) => {
+                    fetch.print(&binstall_opts);
+                    resolution_fetchs.push(fetch)
+                }
+                Resolution::InstallFromSource(source) => {
+                    source.print();
+                    resolution_sources.push(source)
+                }
+            }
+        }
+
+        if resolution_fetchs.is_empty() && resolution_sources.is_empty() {
+            debug!("Nothing to do");
+            return Ok(());
+        }
+
+        // Confirm
+        if !dry_run && should_confirm(no_confirm) {
+            print_summary(
+                &resolution_fetchs,
+                &resolution_sources,
+                &binstall_opts.install_path,
+            )
+            .map_err(BinstallError::from)?;
+            confirm().await?;
+        }
+
+        // Build crates from source before installing anything, so that a
+        // failed build does not leave some of the crates installed.
+        let tasks: Vec<_> = resolution_sources
+            .into_iter()
+            .map(|source| AutoAbortJoinHandle::spawn(source.build(binstall_opts.clone())))
+            .collect();
+
+        let mut built_sources = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            built_sources.extend(task.await??);
+        }
+
+        // Hold the lock while moving the binaries into place and updating the
+        // manifests, so that concurrent installs to the same root do not race.
+        let _install_lock = install_lock_root
+            .map(|cargo_roots| {
+                debug!("Locking {} for installation", cargo_roots.display());
+                block_in_place(|| InstallLock::acquire(&cargo_roots))
+            })
+            .transpose()?;
+
+        do_install_fetches(
+            resolution_fetchs,
+            built_sources,
+            manifests,
+            &binstall_opts,
+            dry_run,
+            temp_dir,
+        )?;
+
+        if let Some(download_cache) = download_cache {
+            block_in_place(|| {
+                download_cache.save_stats().unwrap_or_else(|err| {
+                    warn!("Failed to save stats of the download cache: {err}")
+                });
+                download_cache
+                    .evict(MAX_DOWNLOAD_CACHE_SIZE)
+                    .unwrap_or_else(|err| {
+                        warn!("Failed to remove old packages from the download cache: {err}")
+                    });
+            });
+        }
+
+        Ok(())
+    }))
+}
+
+/// Print the binstall metadata of `crate_name` and whether its packages are
+/// found, without creating the install path or a temporary dir.
+pub fn inspect_crate(
+    args: Args,
+    crate_name: CrateName,
+    jobserver_client: LazyJobserverClient,
+) -> Result<Option<impl Future<Output = Result<()>>>> {
+    // Load .cargo/config.toml
+    let cargo_home = cargo_home().map_err(BinstallError::from)?;
+    let mut config = Config::load_from_path(cargo_home.join("config.toml"))?;
+
+    let cargo_roots =
+        install_path::get_cargo_roots_path(args.root.clone(), cargo_home, &mut config)
+            .ok_or_else(|| miette!("No cargo roots path found or specified"))?;
+    let install_path =
+        install_path::get_install_path(args.install_path.clone(), Some(&cargo_roots))
+            .0
+            .ok_or_else(|| miette!("No install path found or specified"))?;
+
+    // Packages are only probed, nothing is downloaded or built, so neither
+    // the download cache nor a temporary dir is needed.
+    let binstall_opts = Arc::new(create_binstall_opts(
+        args,
+        config,
+        None,
+        jobserver_client,
+        env::temp_dir(),
+        install_path,
+    )?);
+
+    Ok(Some(async move {
+        let report = ops::resolve::inspect(binstall_opts, crate_name).await?;
+        info::print_report(&report).map_err(|err| BinstallError::from(err).into())
+    }))
+}
+
+/// Create the options shared by installing and inspecting crates.
+fn create_binstall_opts(
+    args: Args,
+    mut config: Config,
+    download_cache: Option<DownloadCache>,
+    jobserver_client: LazyJobserverClient,
+    temp_dir: PathBuf,
+    install_path: PathBuf,
+) -> Result<Options> {
+    // Compute Resolvers
+    let mut cargo_install_fallback = false;
+
+    let resolvers: Vec<_> = args
+        .strategies
+        .into_iter()
+        .filter_map(|strategy| match strategy {
+            Strategy::CrateMetaData => Some(GhCrateMeta::new as Resolver),
+            Strategy::QuickInstall => Some(QuickInstall::new as Resolver),
+            Strategy::Compile => {
+                cargo_install_fallback = true;
+                None
+            }
+        })
+        .collect();
+
     // Launch target detection
     let desired_targets = get_desired_targets(args.targets);
 
     // Computer cli_overrides
     let cli_overrides = PkgOverride {
-        pkg_url: args.pkg_url,
+        pkg_url: args.pkg_url.map(Into::into),
         pkg_fmt: args.pkg_fmt,
-        bin_dir: args.bin_dir,
+        bin_dir: args.bin_dir.map(Into::into),
         signing: None,
+        disabled_strategies: None,
+        unknown_keys: Default::default(),
     };
 
     // Initialize reqwest client
@@ -96,17 +292,59 @@ pub fn install_crates(
 
     let mut http = config.http.take();
 
-    let client = Client::new(
-        concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
-        args.min_tls_version.map(|v| v.into()),
-        rate_limit.duration,
-        rate_limit.request_count,
-        read_root_certs(
-            args.root_certificates,
-            http.as_mut().and_then(|http| http.cainfo.take()),
-        ),
-    )
-    .map_err(BinstallError::from)?;
+    let mut client_builder = Client::builder(concat!(
+        env!("CARGO_PKG_NAME"),
+        "/",
+        env!("CARGO_PKG_VERSION")
+    ))
+    .rate_limit(rate_limit.duration, rate_limit.request_count)
+    .add_root_certificates(read_root_certs(
+        args.root_certificates,
+        http.as_mut().and_then(|http| http.cainfo.take()),
+    ));
+
+    if let Some(min_tls_version) = args.min_tls_version {
+        client_builder = client_builder.min_tls_version(min_tls_version.into());
+    }
+
+    if let Some(timeout) = args.timeout {
+        client_builder = client_builder.timeout(Duration::from_secs(timeout));
+    }
+
+    if let Some(connect_timeout) = args
+        .connect_timeout
+        .or_else(|| http.as_ref().and_then(|http| http.timeout))
+    {
+        client_builder = client_builder.connect_timeout(Duration::from_secs(connect_timeout));
+    }
+
+    if let Some(max) = args.max_connections_per_host {
+        client_builder = client_builder.max_connections_per_host(max);
+    }
+
+    if let Some(proxy) = &args.proxy {
+        client_builder = client_builder.https_proxy(proxy);
+    }
+
+    let client = client_builder.build().map_err(BinstallError::from)?;
+
+    let client = if let Some(download_cache) = download_cache {
+        client.with_download_cache(download_cache)
+    } else {
+        client
+    };
+
+    let client = if let Some(limit_rate) = args.limit_rate {
+        client.with_bandwidth_limit(limit_rate.0)
+    } else {
+        client
+    };
+
+    let client = if let Some(github_mirror) = args.github_mirror {
+        client.with_github_mirror(github_mirror)
+    } else {
+        client
+    };
 
     let gh_api_client = GhApiClient::new(
         client.clone(),
@@ -126,35 +364,56 @@ pub fn install_crates(
         }),
     );
 
-    // Create binstall_opts
-    let binstall_opts = Arc::new(Options {
-        no_symlinks: args.no_symlinks,
+    // The package url is specified explicitly by the user, so trust its host.
+    let mut trusted_hosts = args.trusted_hosts;
+    if let Some(host) = args.url.as_ref().and_then(|url| url.host_str()) {
+        trusted_hosts.push(host.into());
+    }
+
+    Ok(Options {
+        no_symlinks: args.no_symlinks && !args.versioned_symlinks,
         dry_run: args.dry_run,
         force: args.force,
-        quiet: args.log_level == Some(LevelFilter::Off),
+        quiet: args.log_level == Some(LevelFilter::OFF),
         locked: args.locked,
         no_track: args.no_track,
+        strict: args.strict,
+        no_verify_arch: args.no_verify_arch,
 
         version_req: args.version_req,
+        include_prereleases: args.include_prereleases,
+        bins: args.bins,
         #[cfg(feature = "git")]
-        cargo_toml_fetch_override: match (args.manifest_path, args.git) {
-            (Some(manifest_path), None) => Some(CargoTomlFetchOverride::Path(manifest_path)),
-            (None, Some(git_url)) => Some(CargoTomlFetchOverride::Git(git_url)),
-            (None, None) => None,
-            _ => unreachable!("manifest_path and git cannot be specified at the same time"),
+        cargo_toml_fetch_override: match (args.manifest_path, args.git, args.url) {
+            (Some(manifest_path), None, None) => Some(CargoTomlFetchOverride::Path(manifest_path)),
+            (None, Some(git_url), None) => Some(CargoTomlFetchOverride::Git(git_url)),
+            (None, None, Some(url)) => Some(CargoTomlFetchOverride::Url {
+                url,
+                sha256: args.url_sha256,
+            }),
+            (None, None, None) => None,
+            _ => unreachable!("manifest_path, git and url cannot be specified at the same time"),
         },
 
         #[cfg(not(feature = "git"))]
-        cargo_toml_fetch_override: args.manifest_path.map(CargoTomlFetchOverride::Path),
+        cargo_toml_fetch_override: match (args.manifest_path, args.url) {
+            (Some(manifest_path), None) => Some(CargoTomlFetchOverride::Path(manifest_path)),
+            (None, Some(url)) => Some(CargoTomlFetchOverride::Url {
+                url,
+                sha256: args.url_sha256,
+            }),
+            (None, None) => None,
+            _ => unreachable!("manifest_path and url cannot be specified at the same time"),
+        },
         cli_overrides,
 
         desired_targets,
         resolvers,
         cargo_install_fallback,
 
-        temp_dir: temp_dir.path().to_owned(),
+        temp_dir,
         install_path,
-        cargo_root,
+        cargo_root: args.root,
 
         client,
         gh_api_client,
@@ -199,73 +458,10 @@ pub fn install_crates(
         } else {
             SignaturePolicy::IfPresent
         },
-    });
-
-    // Destruct args before any async function to reduce size of the future
-    let dry_run = args.dry_run;
-    let no_confirm = args.no_confirm;
-    let no_cleanup = args.no_cleanup;
-
-    // Resolve crates
-    let tasks: Vec<_> = crate_names
-        .map(|(crate_name, current_version)| {
-            AutoAbortJoinHandle::spawn(ops::resolve::resolve(
-                binstall_opts.clone(),
-                crate_name,
-                current_version,
-            ))
-        })
-        .collect();
-
-    Ok(Some(async move {
-        // Collect results
-        let mut resolution_fetchs = Vec::new();
-        let mut resolution_sources = Vec::new();
-
-        for task in tasks {
-            match task.await?? {
-                Resolution::AlreadyUpToDate => {}
-                Resolution::Fetch(fetch) => {
-                    fetch.print(&binstall_opts);
-                    resolution_fetchs.push(fetch)
-                }
-                Resolution::InstallFromSource(source) => {
-                    source.print();
-                    resolution_sources.push(source)
-                }
-            }
-        }
-
-        if resolution_fetchs.is_empty() && resolution_sources.is_empty() {
-            debug!("Nothing to do");
-            return Ok(());
-        }
-
-        // Confirm
-        if !dry_run && !no_confirm {
-            confirm().await?;
-        }
-
-        do_install_fetches(
-            resolution_fetchs,
-            manifests,
-            &binstall_opts,
-            dry_run,
-            temp_dir,
-            no_cleanup,
-        )?;
-
-        let tasks: Vec<_> = resolution_sources
-            .into_iter()
-            .map(|source| AutoAbortJoinHandle::spawn(source.install(binstall_opts.clone())))
-            .collect();
-
-        for task in tasks {
-            task.await??;
-        }
-
-        Ok(())
-    }))
+        trusted_hosts,
+        only_trusted_hosts: args.only_trusted_hosts,
+        report_to_upstream: args.send_quickinstall_stats,
+    })
 }
 
 fn do_read_root_cert(path: &Path) -> Result<Option<Certificate>, BinstallError> {
@@ -315,18 +511,19 @@ fn read_root_certs(
         })
 }
 
-/// Return (install_path, manifests, temp_dir)
+/// Return (cargo_roots, install_path, manifests, temp_dir)
 fn compute_paths_and_load_manifests(
     roots: Option<PathBuf>,
     install_path: Option<PathBuf>,
     no_track: bool,
+    temp_dir: Option<PathBuf>,
     cargo_home: PathBuf,
     config: &mut Config,
-) -> Result<(PathBuf, Option<Manifests>, tempfile::TempDir)> {
+) -> Result<(PathBuf, PathBuf, Option<Manifests>, tempfile::TempDir)> {
     // Compute cargo_roots
     let cargo_roots =
         install_path::get_cargo_roots_path(roots, cargo_home, config).ok_or_else(|| {
-            error!("No viable cargo roots path found of specified, try `--roots`");
+            error!("No viable cargo roots path found of specified, try `--root`");
             miette!("No cargo roots path found or specified")
         })?;
 
@@ -337,7 +534,7 @@ fn compute_paths_and_load_manifests(
         error!("No viable install path found of specified, try `--install-path`");
         miette!("No install path found or specified")
     })?;
-    fs::create_dir_all(&install_path).map_err(BinstallError::Io)?;
+    install_path::create_install_path(&install_path).map_err(BinstallError::Io)?;
     debug!("Using install path: {}", install_path.display());
 
     let no_manifests = no_track || custom_install_path;
@@ -351,16 +548,31 @@ fn compute_paths_and_load_manifests(
 
     // Create a temporary directory for downloads etc.
     //
-    // Put all binaries to a temporary directory under `dst` first, catching
-    // some failure modes (e.g., out of space) before touching the existing
-    // binaries. This directory will get cleaned up via RAII.
+    // Put all binaries to a temporary directory first, catching some failure
+    // modes (e.g., out of space) before touching the existing binaries. This
+    // directory will get cleaned up via RAII, including on error, panic and
+    // cancellation by signals.
+    let temp_dir_parent = match temp_dir {
+        Some(temp_dir) => install_path::create_install_path(&install_path::make_absolute(temp_dir))
+            .map_err(BinstallError::Io)?,
+        None => env::temp_dir(),
+    };
     let temp_dir = tempfile::Builder::new()
         .prefix("cargo-binstall")
-        .tempdir_in(&install_path)
+        .tempdir_in(&temp_dir_parent)
         .map_err(BinstallError::from)
         .wrap_err("Creating a temporary directory failed.")?;
+    debug!("Using temporary dir: {}", temp_dir.path().display());
+
+    Ok((cargo_roots, install_path, manifests, temp_dir))
+}
 
-    Ok((install_path, manifests, temp_dir))
+/// Return true if `a` and `b` are the same directory, following symlinks.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
 }
 
 /// Return vec of (crate_name, current_version)
@@ -398,8 +610,9 @@ fn filter_out_installed_crates(
                 None
             }
 
-            // The version req is "*" thus a remote upgraded version could exist
-            (false, Some(curr_version), None) => {
+            // The version req is "*" thus a remote upgraded version could exist,
+            // or the version req does not match the installed version.
+            (false, Some(curr_version), _) => {
                 Some((crate_name, Some(curr_version)))
             }
 
@@ -411,14 +624,14 @@ fn filter_out_installed_crates(
 #[allow(clippy::vec_box)]
 fn do_install_fetches(
     resolution_fetchs: Vec<Box<ResolutionFetch>>,
+    built_sources: Vec<BuiltSource>,
     // Take manifests by value to drop the `FileLock`.
     manifests: Option<Manifests>,
     binstall_opts: &Options,
     dry_run: bool,
-    temp_dir: tempfile::TempDir,
-    no_cleanup: bool,
+    temp_dir: Option<tempfile::TempDir>,
 ) -> Result<()> {
-    if resolution_fetchs.is_empty() {
+    if resolution_fetchs.is_empty() && built_sources.is_empty() {
         return Ok(());
     }
 
@@ -428,19 +641,29 @@ fn do_install_fetches(
     }
 
     block_in_place(|| {
-        let metadata_vec = resolution_fetchs
-            .into_iter()
-            .map(|fetch| fetch.install(binstall_opts))
-            .collect::<Result<Vec<_>, BinstallError>>()?;
+        let mut metadata_vec = Vec::with_capacity(resolution_fetchs.len() + built_sources.len());
 
-        if let Some(manifests) = manifests {
-            manifests.update(metadata_vec)?;
+        let res = install_all(
+            resolution_fetchs,
+            built_sources,
+            binstall_opts,
+            &mut metadata_vec,
+        );
+
+        // Record the crates installed before the failure or cancellation, so
+        // that they are not left untracked.
+        let updated = manifests
+            .map(|manifests| manifests.update(metadata_vec))
+            .transpose();
+        if let Err(err) = res {
+            if let Err(update_err) = updated {
+                warn!("Failed to record the crates installed before the failure: {update_err}");
+            }
+            return Err(err.into());
         }
+        updated?;
 
-        if no_cleanup {
-            // Consume temp_dir without removing it from fs.
-            let _ = temp_dir.into_path();
-        } else {
+        if let Some(temp_dir) = temp_dir {
             temp_dir.close().unwrap_or_else(|err| {
                 warn!("Failed to clean up some resources: {err}");
             });
@@ -449,3 +672,86 @@ fn do_install_fetches(
         Ok(())
     })
 }
+
+/// Install the fetched and built crates, pushing the metadata of each crate
+/// installed to `metadata_vec`.
+///
+/// The binaries of a crate are rolled back if its installation fails, so on
+/// cancellation, stop between crates instead of leaving one half-installed.
+#[allow(clippy::vec_box)]
+fn install_all(
+    resolution_fetchs: Vec<Box<ResolutionFetch>>,
+    built_sources: Vec<BuiltSource>,
+    binstall_opts: &Options,
+    metadata_vec: &mut Vec<CrateInfo>,
+) -> Result<(), BinstallError> {
+    for fetch in resolution_fetchs {
+        signal::check_cancelled()?;
+        metadata_vec.push(fetch.install(binstall_opts)?);
+    }
+    for built in built_sources {
+        signal::check_cancelled()?;
+        metadata_vec.push(built.install(binstall_opts)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use compact_str::CompactString;
+    use semver::Version;
+
+    fn filter(
+        crate_names: &[&str],
+        force: bool,
+        manifests: &mut Manifests,
+    ) -> Vec<(CompactString, Option<Version>)> {
+        let crate_names = crate_names
+            .iter()
+            .map(|crate_name| crate_name.parse().unwrap())
+            .collect();
+
+        filter_out_installed_crates(crate_names, force, Some(manifests))
+            .unwrap()
+            .map(|(crate_name, curr_version)| (crate_name.name, curr_version))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_out_installed_crates() {
+        let cargo_roots = tempfile::tempdir().unwrap();
+        fs::write(
+            cargo_roots.path().join(".crates.toml"),
+            r#"[v1]
+"bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)" = ["bar"]
+"baz 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)" = ["baz"]
+"foo 1.2.3 (registry+https://github.com/rust-lang/crates.io-index)" = ["foo"]
+"#,
+        )
+        .unwrap();
+
+        let mut manifests = Manifests::open_exclusive(cargo_roots.path()).unwrap();
+        let version = |version| Some(Version::parse(version).unwrap());
+
+        assert_eq!(
+            filter(
+                &["foo@1.2.3", "bar@0.2", "baz", "qux"],
+                false,
+                &mut manifests
+            ),
+            [
+                // foo@1.2.3 is already installed at a matching version.
+                ("bar".into(), version("0.1.0")),
+                ("baz".into(), version("1.0.0")),
+                ("qux".into(), None),
+            ]
+        );
+
+        assert_eq!(
+            filter(&["foo@1.2.3"], true, &mut manifests),
+            [("foo".into(), None)]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{self, Write};
+
+use binstalk::{
+    manifests::cargo_toml_binstall::PkgMeta,
+    ops::resolve::{FetcherStatus, PackageReport},
+};
+
+/// Print the binstall metadata of a crate, along with whether its package
+/// is found by each fetcher for each target.
+pub fn print_report(report: &PackageReport) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    writeln!(stdout, "{} v{}", report.name, report.version)?;
+    writeln!(
+        stdout,
+        "repository: {}",
+        report.repo.as_deref().unwrap_or("-")
+    )?;
+    write_meta(&mut stdout, &report.meta, "")?;
+
+    writeln!(stdout, "binaries:")?;
+    for (name, required_features) in &report.binaries {
+        if required_features.is_empty() {
+            writeln!(stdout, "  {name}")?;
+        } else {
+            writeln!(
+                stdout,
+                "  {name} (requires features {})",
+                required_features.join(", ")
+            )?;
+        }
+    }
+
+    let mut target = None;
+
+    for fetcher in &report.fetchers {
+        if target != Some(&fetcher.target) {
+            target = Some(&fetcher.target);
+
+            writeln!(stdout, "target {}:", fetcher.target)?;
+            // Only print the metadata of the target if it is overriden.
+            if !is_same_meta(&fetcher.meta, &report.meta) {
+                write_meta(&mut stdout, &fetcher.meta, "  ")?;
+            }
+        }
+
+        let name = fetcher.fetcher_name;
+        match &fetcher.status {
+            FetcherStatus::Disabled => writeln!(stdout, "  {name}: disabled by the crate")?,
+            FetcherStatus::Found(url) => writeln!(stdout, "  {name}: found {url}")?,
+            FetcherStatus::NotFound => writeln!(stdout, "  {name}: not found")?,
+            FetcherStatus::Failed(err) => writeln!(stdout, "  {name}: failed: {err}")?,
+        }
+    }
+
+    Ok(())
+}
+
+fn write_meta(out: &mut dyn Write, meta: &PkgMeta, indent: &str) -> io::Result<()> {
+    match &meta.pkg_url {
+        Some(pkg_url) => writeln!(out, "{indent}pkg-url: {pkg_url}")?,
+        None => writeln!(out, "{indent}pkg-url: default for the repository")?,
+    }
+    match &meta.pkg_fmt {
+        Some(pkg_fmt) => writeln!(out, "{indent}pkg-fmt: {pkg_fmt}")?,
+        None => writeln!(out, "{indent}pkg-fmt: detected")?,
+    }
+    match &meta.bin_dir {
+        Some(bin_dir) => writeln!(out, "{indent}bin-dir: {bin_dir}")?,
+        None => writeln!(out, "{indent}bin-dir: inferred from the package")?,
+    }
+    if let Some(disabled_strategies) = &meta.disabled_strategies {
+        writeln!(out, "{indent}disabled-strategies: {disabled_strategies:?}")?;
+    }
+
+    Ok(())
+}
+
+fn is_same_meta(meta: &PkgMeta, other: &PkgMeta) -> bool {
+    meta.pkg_url == other.pkg_url
+        && meta.pkg_fmt == other.pkg_fmt
+        && meta.bin_dir == other.bin_dir
+        && meta.disabled_strategies == other.disabled_strategies
+}
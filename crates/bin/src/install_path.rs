@@ -1,29 +1,53 @@
 use std::{
-    env::var_os,
+    env::{current_dir, var_os},
+    fs, io,
     path::{Path, PathBuf},
 };
 
+use binstalk::errors::BinstallError;
 use binstalk_manifests::cargo_config::Config;
+use home::cargo_home;
+use miette::{miette, Result};
 use tracing::debug;
 
+/// `cargo_home` falls back to `%USERPROFILE%\.cargo` on Windows if
+/// `CARGO_HOME` is not set, see [`home::cargo_home`].
 pub fn get_cargo_roots_path(
     cargo_roots: Option<PathBuf>,
     cargo_home: PathBuf,
     config: &mut Config,
 ) -> Option<PathBuf> {
-    if let Some(p) = cargo_roots {
-        Some(p)
+    let p = if let Some(p) = cargo_roots {
+        make_absolute(p)
     } else if let Some(p) = var_os("CARGO_INSTALL_ROOT") {
         // Environmental variables
-        let p = PathBuf::from(p);
+        let p = make_absolute(PathBuf::from(p));
         debug!("using CARGO_INSTALL_ROOT ({})", p.display());
-        Some(p)
+        p
     } else if let Some(root) = config.install.take().and_then(|install| install.root) {
         debug!("using `install.root` {} from cargo config", root.display());
-        Some(root)
+        root
     } else {
         debug!("using ({}) as cargo home", cargo_home.display());
-        Some(cargo_home)
+        cargo_home
+    };
+
+    Some(normalize_path(p))
+}
+
+/// Relative roots are relative to the current dir, same as cargo, make
+/// them absolute so that they are independent of the cwd of later runs.
+pub fn make_absolute(path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        return path;
+    }
+
+    match current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(err) => {
+            debug!("Failed to get current dir: {err}");
+            path
+        }
     }
 }
 
@@ -37,7 +61,7 @@ pub fn get_install_path(
 ) -> (Option<PathBuf>, bool) {
     // Command line override first first
     if let Some(p) = install_path {
-        return (Some(p), true);
+        return (Some(normalize_path(p)), true);
     }
 
     // Then cargo_roots
@@ -46,7 +70,7 @@ pub fn get_install_path(
     }
 
     // Local executable dir if no cargo is found
-    let dir = dirs::executable_dir();
+    let dir = dirs::executable_dir().map(normalize_path);
 
     if let Some(d) = &dir {
         debug!("Fallback to {}", d.display());
@@ -54,3 +78,98 @@ pub fn get_install_path(
 
     (dir, true)
 }
+
+/// Return (cargo_roots, install_path) for inspecting crates already
+/// installed.
+pub fn get_cargo_roots_and_install_path(
+    cargo_roots: Option<PathBuf>,
+    install_path: Option<PathBuf>,
+) -> Result<(PathBuf, PathBuf)> {
+    let cargo_home = cargo_home().map_err(BinstallError::from)?;
+    let mut config = Config::load_from_path(cargo_home.join("config.toml"))?;
+
+    let cargo_roots = get_cargo_roots_path(cargo_roots, cargo_home, &mut config)
+        .ok_or_else(|| miette!("No cargo roots path found or specified"))?;
+    let install_path = get_install_path(install_path, Some(&cargo_roots))
+        .0
+        .ok_or_else(|| miette!("No install path found or specified"))?;
+
+    Ok((cargo_roots, install_path))
+}
+
+/// Create `install_path` if it does not exist yet, return the dir to put
+/// temporary dirs for downloads and extraction in.
+///
+/// On Windows, it is an extended-length (`\\?\`) path, so that extracting
+/// deeply nested archives is not limited by `MAX_PATH`.
+pub fn create_install_path(install_path: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(install_path)?;
+
+    if cfg!(windows) {
+        fs::canonicalize(install_path)
+    } else {
+        Ok(install_path.to_owned())
+    }
+}
+
+/// Strip the extended-length prefix `\\?\` off `path` on Windows, e.g.
+/// returned by [`fs::canonicalize`], so that it is displayed and recorded in
+/// the form users and other tools expect.
+pub fn normalize_path(path: PathBuf) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(p) = path.to_str().and_then(strip_extended_length_prefix) {
+        return p.into();
+    }
+
+    path
+}
+
+/// Return `None` if `path` has no extended-length prefix or it cannot be
+/// stripped without changing the meaning of the path.
+#[cfg_attr(not(any(windows, test)), allow(dead_code))]
+fn strip_extended_length_prefix(path: &str) -> Option<String> {
+    /// Paths at least this long can only be used with the prefix.
+    const MAX_PATH: usize = 260;
+
+    let path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{rest}")
+    } else {
+        let rest = path.strip_prefix(r"\\?\")?;
+        // Only drive paths, e.g. `C:\`, can be used without the prefix.
+        match rest.as_bytes() {
+            [drive, b':', b'\\', ..] if drive.is_ascii_alphabetic() => rest.to_owned(),
+            _ => return None,
+        }
+    };
+
+    // `.`, `..` and `/` are taken literally in extended-length paths.
+    let is_verbatim_only = path.contains('/')
+        || path
+            .split('\\')
+            .any(|component| component == "." || component == "..");
+
+    (path.len() < MAX_PATH && !is_verbatim_only).then_some(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strip_extended_length_prefix() {
+        let strip = strip_extended_length_prefix;
+
+        assert_eq!(
+            strip(r"\\?\C:\Users\me\.cargo\bin").as_deref(),
+            Some(r"C:\Users\me\.cargo\bin")
+        );
+        assert_eq!(
+            strip(r"\\?\UNC\server\share\bin").as_deref(),
+            Some(r"\\server\share\bin")
+        );
+        assert_eq!(strip(r"C:\Users\me\.cargo\bin"), None);
+        assert_eq!(strip(r"\\?\Volume{x}\bin"), None);
+        assert_eq!(strip(r"\\?\C:\a\..\b"), None);
+        assert_eq!(strip(&format!(r"\\?\C:\{}", "a".repeat(300))), None);
+    }
+}
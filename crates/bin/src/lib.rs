@@ -2,13 +2,19 @@
 
 mod args;
 mod bin_util;
+mod cache;
+mod check_metadata;
+mod completions;
 mod entry;
 mod gh_token;
 mod git_credentials;
+mod info;
 mod install_path;
+mod list;
 mod logging;
 mod main_impl;
 mod signal;
 mod ui;
+mod verify;
 
 pub use main_impl::do_main;
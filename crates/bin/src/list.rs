@@ -0,0 +1,125 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use binstalk::errors::BinstallError;
+use binstalk_manifests::binstall_crates_v1;
+use compact_str::{format_compact, CompactString, ToCompactString};
+use miette::Result;
+use serde_json::json;
+
+use crate::{install_path, ui::print_table};
+
+/// Print crates installed by binstall along with their binaries, as json
+/// if `json` is true.
+pub fn list(roots: Option<PathBuf>, install_path: Option<PathBuf>, json: bool) -> Result<()> {
+    let (cargo_roots, install_path) =
+        install_path::get_cargo_roots_and_install_path(roots, install_path)?;
+
+    let records = binstall_crates_v1::read_from_path(cargo_roots.join("binstall/crates-v1.json"))?;
+
+    if json {
+        let crates: Vec<_> = records
+            .into_iter()
+            .map(|data| {
+                let crate_info = &data.crate_info;
+                json!({
+                    "name": crate_info.name.as_str(),
+                    "version": crate_info.current_version.to_string(),
+                    "target": crate_info.target.as_str(),
+                    "install_time": crate_info.install_time,
+                    "source": crate_info.package_source.as_deref(),
+                    "bins": crate_info
+                        .bins
+                        .iter()
+                        .map(|bin| install_path.join(bin.as_str()))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(io::stdout().lock(), &crates)
+            .map_err(|err| BinstallError::from(io::Error::from(err)))?;
+        println!();
+    } else {
+        let rows: Vec<[CompactString; 5]> = records
+            .into_iter()
+            .map(|data| {
+                let crate_info = &data.crate_info;
+                [
+                    crate_info.name.clone(),
+                    crate_info.current_version.to_compact_string(),
+                    crate_info
+                        .install_time
+                        .map(format_date)
+                        .unwrap_or_else(|| "-".into()),
+                    crate_info
+                        .package_source
+                        .clone()
+                        .unwrap_or_else(|| "-".into()),
+                    crate_info
+                        .bins
+                        .iter()
+                        .map(|bin| install_path.join(bin.as_str()).display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                        .into(),
+                ]
+            })
+            .collect();
+
+        print_table(
+            io::stdout().lock(),
+            ["Crate", "Version", "Installed", "Source", "Binaries"],
+            &rows,
+        )
+        .map_err(BinstallError::from)?;
+    }
+
+    Ok(())
+}
+
+/// Print names of crates installed by binstall, one per line.
+pub fn list_names(roots: Option<PathBuf>, install_path: Option<PathBuf>) -> Result<()> {
+    let (cargo_roots, _) = install_path::get_cargo_roots_and_install_path(roots, install_path)?;
+
+    let records = binstall_crates_v1::read_from_path(cargo_roots.join("binstall/crates-v1.json"))?;
+
+    let mut stdout = io::stdout().lock();
+    for data in &records {
+        writeln!(&mut stdout, "{}", data.crate_info.name).map_err(BinstallError::from)?;
+    }
+
+    Ok(())
+}
+
+/// Format seconds since the unix epoch as an UTC date, e.g. `2023-11-05`.
+fn format_date(secs: u64) -> CompactString {
+    // Algorithm from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format_compact!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_699_142_400), "2023-11-05");
+        assert_eq!(format_date(1_704_067_199), "2023-12-31");
+    }
+}
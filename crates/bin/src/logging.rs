@@ -1,24 +1,33 @@
 use std::{
-    cmp::min,
+    cmp::{max, min},
+    fs::{File, OpenOptions},
     io::{self, Write},
     iter::repeat,
+    path::Path,
+    sync::Mutex,
 };
 
-use log::{LevelFilter, Log, STATIC_MAX_LEVEL};
+use binstalk::errors::BinstallError;
+use log::Log;
+use miette::Result;
 use once_cell::sync::Lazy;
 use supports_color::{on as supports_color_on_stream, Stream::Stdout};
 use tracing::{
     callsite::Callsite,
     dispatcher, field,
+    level_filters::{LevelFilter, STATIC_MAX_LEVEL},
     subscriber::{self, set_global_default},
-    Event, Level, Metadata,
+    Event, Level, Metadata, Subscriber,
 };
-use tracing_core::{identify_callsite, metadata::Kind, subscriber::Subscriber};
-use tracing_log::AsTrace;
+use tracing_core::{identify_callsite, metadata::Kind};
+use tracing_log::{AsLog, AsTrace};
 use tracing_subscriber::{
     filter::targets::Targets,
-    fmt::{fmt, MakeWriter},
+    fmt::{self, MakeWriter},
     layer::SubscriberExt,
+    registry,
+    registry::LookupSpan,
+    Layer, Registry,
 };
 
 // Shamelessly taken from tracing-log
@@ -100,7 +109,7 @@ struct Logger;
 
 impl Logger {
     fn init(log_level: LevelFilter) {
-        log::set_max_level(log_level);
+        log::set_max_level(log_level.as_log());
         log::set_logger(&Self).unwrap();
     }
 }
@@ -189,36 +198,86 @@ impl<'a> MakeWriter<'a> for ErrorFreeWriter {
     }
 }
 
-pub fn logging(log_level: LevelFilter, json_output: bool) {
+/// Targets logged unless the log level is `trace`.
+const ALLOWED_TARGETS: [&str; 10] = [
+    "atomic_file_install",
+    "binstalk",
+    "binstalk_bins",
+    "binstalk_downloader",
+    "binstalk_fetchers",
+    "binstalk_registry",
+    "cargo_binstall",
+    "cargo_toml_workspace",
+    "detect_targets",
+    "simple_git",
+];
+
+fn targets_filter(log_level: LevelFilter) -> Targets {
+    if log_level == LevelFilter::TRACE {
+        Targets::new().with_default(log_level)
+    } else {
+        Targets::new().with_targets(ALLOWED_TARGETS.into_iter().zip(repeat(log_level)))
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<File, BinstallError> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| {
+            BinstallError::Io(io::Error::new(
+                err.kind(),
+                format!("Failed to open log file '{}': {err}", path.display()),
+            ))
+        })
+}
+
+/// Json layer writing to the log file, with the spans of each event so
+/// that logs of concurrent installs can be told apart.
+fn file_layer<S>(log_file: File, file_log_level: LevelFilter) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fmt::layer()
+        .json()
+        .with_writer(Mutex::new(log_file))
+        .with_filter(targets_filter(file_log_level))
+}
+
+/// Set up logging to stdout at `log_level`, and in json format to
+/// `log_file` at `debug` level at least, if specified.
+///
+/// Logs are attributed to the crate being resolved or installed via spans.
+///
+/// If `log_file` cannot be opened, logging to stdout is still set up so
+/// that the returned error can be reported.
+pub fn logging(log_level: LevelFilter, json_output: bool, log_file: Option<&Path>) -> Result<()> {
     // Calculate log_level
     let log_level = min(log_level, STATIC_MAX_LEVEL);
 
-    let allowed_targets = (log_level != LevelFilter::Trace).then_some([
-        "atomic_file_install",
-        "binstalk",
-        "binstalk_bins",
-        "binstalk_downloader",
-        "binstalk_fetchers",
-        "binstalk_registry",
-        "cargo_binstall",
-        "cargo_toml_workspace",
-        "detect_targets",
-        "simple_git",
-    ]);
+    let (log_file, res) = match log_file.map(open_log_file).transpose() {
+        Ok(log_file) => (log_file, Ok(())),
+        Err(err) => (None, Err(err.into())),
+    };
+    let file_log_level = min(max(log_level, LevelFilter::DEBUG), STATIC_MAX_LEVEL);
 
     // Forward log to tracing
-    Logger::init(log_level);
+    Logger::init(if log_file.is_some() {
+        file_log_level
+    } else {
+        log_level
+    });
 
-    // Build fmt subscriber
-    let log_level = log_level.as_trace();
-    let subscriber_builder = fmt().with_max_level(log_level).with_writer(ErrorFreeWriter);
+    // Build fmt layer
+    let stdout_layer = fmt::layer().with_writer(ErrorFreeWriter);
 
-    let subscriber: Box<dyn Subscriber + Send + Sync> = if json_output {
-        Box::new(subscriber_builder.json().finish())
+    let stdout_layer: Box<dyn Layer<Registry> + Send + Sync> = if json_output {
+        Box::new(stdout_layer.json())
     } else {
         // Disable time, target, file, line_num, thread name/ids to make the
         // output more readable
-        let subscriber_builder = subscriber_builder
+        let stdout_layer = stdout_layer
             .without_time()
             .with_target(false)
             .with_file(false)
@@ -226,23 +285,70 @@ pub fn logging(log_level: LevelFilter, json_output: bool) {
             .with_thread_names(false)
             .with_thread_ids(false);
 
-        // subscriber_builder defaults to write to io::stdout(),
-        // so tests whether it supports color.
+        // stdout_layer writes to io::stdout(), so tests whether it supports
+        // color.
         let stdout_supports_color = supports_color_on_stream(Stdout)
             .map(|color_level| color_level.has_basic)
             .unwrap_or_default();
 
-        Box::new(subscriber_builder.with_ansi(stdout_supports_color).finish())
+        Box::new(stdout_layer.with_ansi(stdout_supports_color))
     };
 
-    // Builder layer for filtering
-    let filter_layer = allowed_targets.map(|allowed_targets| {
-        Targets::new().with_targets(allowed_targets.into_iter().zip(repeat(log_level)))
-    });
+    let file_layer = log_file.map(|log_file| file_layer(log_file, file_log_level));
 
     // Builder final subscriber with filtering
-    let subscriber = subscriber.with(filter_layer);
+    let subscriber = registry()
+        .with(stdout_layer.with_filter(targets_filter(log_level)))
+        .with(file_layer);
 
     // Setup global subscriber
     set_global_default(subscriber).unwrap();
+
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    use serde_json::Value;
+    use tracing::{debug, info_span};
+
+    #[test]
+    fn test_open_log_file_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("binstall.log");
+
+        let err = open_log_file(&path).unwrap_err();
+        assert!(matches!(err, BinstallError::Io(_)));
+        assert!(err.to_string().contains("Failed to open log file"));
+    }
+
+    #[test]
+    fn test_file_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("binstall.log");
+
+        let subscriber = registry().with(file_layer(
+            open_log_file(&path).unwrap(),
+            LevelFilter::DEBUG,
+        ));
+        subscriber::with_default(subscriber, || {
+            let span = info_span!("install", crate_name = "foo");
+            let _guard = span.enter();
+
+            debug!("Installing");
+        });
+
+        let logs = fs::read_to_string(&path).unwrap();
+        let mut lines = logs.lines();
+        let log: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(lines.next(), None);
+
+        assert_eq!(log["level"], "DEBUG");
+        assert_eq!(log["fields"]["message"], "Installing");
+        assert_eq!(log["spans"][0]["crate_name"], "foo");
+    }
 }
@@ -1,14 +1,17 @@
-use std::{process::Termination, time::Instant};
+use std::{
+    process::Termination,
+    time::{Duration, Instant},
+};
 
 use binstalk::{helpers::jobserver_client::LazyJobserverClient, TARGET};
-use log::LevelFilter;
-use tracing::debug;
+use tracing::{debug, level_filters::LevelFilter};
 
 use crate::{
     args,
     bin_util::{run_tokio_main, MainExit},
-    entry,
+    cache, check_metadata, completions, entry, list,
     logging::logging,
+    verify,
 };
 
 pub fn do_main() -> impl Termination {
@@ -46,11 +49,95 @@ rustc-llvm-version: {rustc_llvm_version}"#
             println!("{cargo_binstall_version}");
         }
         MainExit::Success(None)
+    } else if args.print_config {
+        print!("{}", args::effective_config(&args));
+        MainExit::Success(None)
+    } else if let Some(shell) = args.completions {
+        completions::generate(shell);
+        MainExit::Success(None)
+    } else if args.installed_crate_names {
+        // No logging, the output is parsed by the shell completions.
+        match list::list_names(args.root, args.install_path) {
+            Ok(()) => MainExit::Success(None),
+            Err(err) => MainExit::new(Err(err), Duration::ZERO),
+        }
+    } else if args.list {
+        if let Err(err) = logging(
+            args.log_level.unwrap_or(LevelFilter::INFO),
+            args.json_output,
+            args.log_file.as_deref(),
+        ) {
+            return MainExit::new(Err(err), Duration::ZERO);
+        }
+
+        // Do not print the run time, which would break the json output.
+        match list::list(args.root, args.install_path, args.json_output) {
+            Ok(()) => MainExit::Success(None),
+            Err(err) => MainExit::new(Err(err), Duration::ZERO),
+        }
+    } else if args.verify {
+        if let Err(err) = logging(
+            args.log_level.unwrap_or(LevelFilter::INFO),
+            args.json_output,
+            args.log_file.as_deref(),
+        ) {
+            return MainExit::new(Err(err), Duration::ZERO);
+        }
+
+        let crate_names: Vec<_> = args
+            .crate_names
+            .into_iter()
+            .map(|crate_name| crate_name.name)
+            .collect();
+
+        let start = Instant::now();
+        let result = verify::verify(&crate_names, args.root, args.install_path);
+
+        MainExit::new(result, start.elapsed())
+    } else if let Some(crate_name) = args.info.clone() {
+        if let Err(err) = logging(
+            args.log_level.unwrap_or(LevelFilter::INFO),
+            args.json_output,
+            args.log_file.as_deref(),
+        ) {
+            return MainExit::new(Err(err), Duration::ZERO);
+        }
+
+        let start = Instant::now();
+        let result = run_tokio_main(|| entry::inspect_crate(args, crate_name, jobserver_client));
+
+        MainExit::new(result, start.elapsed())
+    } else if let Some(manifest_path) = &args.check_metadata {
+        if let Err(err) = logging(
+            args.log_level.unwrap_or(LevelFilter::INFO),
+            args.json_output,
+            args.log_file.as_deref(),
+        ) {
+            return MainExit::new(Err(err), Duration::ZERO);
+        }
+
+        let start = Instant::now();
+        let result = check_metadata::check_metadata(manifest_path);
+
+        MainExit::new(result, start.elapsed())
+    } else if args.clear_cache {
+        match cache::clear() {
+            Ok(()) => MainExit::Success(None),
+            Err(err) => MainExit::new(Err(err), Duration::ZERO),
+        }
+    } else if args.cache_stats {
+        match cache::print_stats() {
+            Ok(()) => MainExit::Success(None),
+            Err(err) => MainExit::new(Err(err), Duration::ZERO),
+        }
     } else {
-        logging(
-            args.log_level.unwrap_or(LevelFilter::Info),
+        if let Err(err) = logging(
+            args.log_level.unwrap_or(LevelFilter::INFO),
             args.json_output,
-        );
+            args.log_file.as_deref(),
+        ) {
+            return MainExit::new(Err(err), Duration::ZERO);
+        }
 
         let start = Instant::now();
 
@@ -1,8 +1,28 @@
-use std::io;
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use binstalk::{errors::BinstallError, helpers::tasks::AutoAbortJoinHandle};
 use tokio::signal;
 
+/// Set once a cancellation signal is received.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Return [`BinstallError::UserAbort`] if a cancellation signal has been
+/// received.
+///
+/// Aborting the main task only takes effect at the next `.await`, so blocking
+/// code run in `block_in_place`, e.g. installing the binaries, has to check it
+/// to stop at a point where it is safe to.
+pub fn check_cancelled() -> Result<(), BinstallError> {
+    if CANCELLED.load(Ordering::Relaxed) {
+        Err(BinstallError::UserAbort)
+    } else {
+        Ok(())
+    }
+}
+
 /// This function will poll the handle while listening for ctrl_c,
 /// `SIGINT`, `SIGHUP`, `SIGTERM` and `SIGQUIT`.
 ///
@@ -21,8 +41,9 @@ pub async fn cancel_on_user_sig_term<T>(
         biased;
 
         res = wait_on_cancellation_signal() => {
-            res.map_err(BinstallError::Io)
-                .and(Err(BinstallError::UserAbort))
+            res.map_err(BinstallError::Io)?;
+            CANCELLED.store(true, Ordering::Relaxed);
+            Err(BinstallError::UserAbort)
         }
         res = handle => res,
     }
@@ -1,10 +1,120 @@
 use std::{
-    io::{self, BufRead, StdinLock, Write},
+    io::{self, BufRead, IsTerminal, StdinLock, Write},
+    iter,
+    path::Path,
     thread,
 };
 
-use binstalk::errors::BinstallError;
+use binstalk::{
+    errors::BinstallError,
+    ops::resolve::{ResolutionFetch, ResolutionSource},
+};
+use compact_str::{format_compact, CompactString, ToCompactString};
 use tokio::sync::oneshot;
+use tracing::info;
+
+/// Return true if the user should be asked for confirmation.
+///
+/// Confirmation is skipped if `no_confirm` is set or if stdin is not a
+/// terminal, e.g. in CI, where nobody can answer the prompt.
+pub fn should_confirm(no_confirm: bool) -> bool {
+    if no_confirm {
+        false
+    } else if io::stdin().is_terminal() {
+        true
+    } else {
+        info!("stdin is not a terminal, skipping confirmation");
+        false
+    }
+}
+
+/// Print a table of the crates to be installed, along with the urls the
+/// packages are downloaded from and their SHA256 checksums.
+///
+/// The table is printed to stderr, to keep stdout clean for `--json-output`.
+pub fn print_summary(
+    fetches: &[Box<ResolutionFetch>],
+    sources: &[Box<ResolutionSource>],
+    install_path: &Path,
+) -> io::Result<()> {
+    let rows: Vec<[CompactString; 6]> = fetches
+        .iter()
+        .map(|fetch| {
+            let fetcher = &fetch.fetcher;
+            let source = if fetcher.is_third_party() {
+                format_compact!("{} (third-party)", fetcher.source_name())
+            } else {
+                fetcher.source_name()
+            };
+            let dests = fetch
+                .bin_files
+                .iter()
+                .map(|bin_file| bin_file.dest.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            [
+                fetch.name.clone(),
+                fetch.new_version.to_compact_string(),
+                source,
+                dests.into(),
+                fetcher
+                    .package_url()
+                    .map(|url| url.as_str().into())
+                    .unwrap_or_else(|| "-".into()),
+                fetch.package_sha256.clone().unwrap_or_else(|| "-".into()),
+            ]
+        })
+        .chain(sources.iter().map(|source| {
+            let dests = source
+                .bin_dests(install_path)
+                .map(|dest| dest.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            [
+                source.name.clone(),
+                source.new_version.to_compact_string(),
+                "cargo build".into(),
+                dests.into(),
+                "-".into(),
+                "-".into(),
+            ]
+        }))
+        .collect();
+
+    print_table(
+        io::stderr().lock(),
+        ["Crate", "Version", "Source", "Destination", "Url", "Sha256"],
+        &rows,
+    )
+}
+
+/// Print `rows` to `out` with the columns aligned.
+pub fn print_table<const N: usize>(
+    mut out: impl Write,
+    header: [&str; N],
+    rows: &[[CompactString; N]],
+) -> io::Result<()> {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    for row in iter::once(header.map(CompactString::from)).chain(rows.iter().cloned()) {
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if i + 1 == N {
+                writeln!(&mut out, "{cell}")?;
+            } else {
+                write!(&mut out, "{cell:<width$}  ")?;
+            }
+        }
+    }
+
+    out.flush()
+}
 
 fn ask_for_confirm(stdin: &mut StdinLock, input: &mut String) -> io::Result<()> {
     {
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use binstalk::ops::verify::{verify_bins, BinStatus};
+use binstalk_manifests::binstall_crates_v1;
+use compact_str::CompactString;
+use miette::{miette, Result};
+use tracing::{error, info, warn};
+
+use crate::install_path;
+
+/// Re-hash binaries of `crate_names` (all crates installed by binstall if
+/// empty) and report those modified since installation.
+pub fn verify(
+    crate_names: &[CompactString],
+    roots: Option<PathBuf>,
+    install_path: Option<PathBuf>,
+) -> Result<()> {
+    let (cargo_roots, install_path) =
+        install_path::get_cargo_roots_and_install_path(roots, install_path)?;
+
+    let records = binstall_crates_v1::read_from_path(cargo_roots.join("binstall/crates-v1.json"))?;
+
+    let crate_infos = if crate_names.is_empty() {
+        records.iter().map(|data| &data.crate_info).collect()
+    } else {
+        crate_names
+            .iter()
+            .map(|name| {
+                records
+                    .get(name.as_str())
+                    .map(|data| &data.crate_info)
+                    .ok_or_else(|| miette!("{name} is not installed by cargo-binstall"))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut modified = 0;
+
+    for crate_info in crate_infos {
+        let name = &crate_info.name;
+
+        for (bin, status) in verify_bins(crate_info, &install_path)? {
+            let path = install_path.join(bin.as_str());
+            let path = path.display();
+
+            match status {
+                BinStatus::Intact => info!("{name}: {path} is intact"),
+                BinStatus::Modified { found } => {
+                    error!("{name}: {path} has been modified since installation (sha256 {found})");
+                    modified += 1;
+                }
+                BinStatus::Missing => {
+                    error!("{name}: {path} has been removed since installation");
+                    modified += 1;
+                }
+                BinStatus::Unrecorded => {
+                    warn!("{name}: no checksum is recorded for {path}, reinstall to record it")
+                }
+            }
+        }
+    }
+
+    if modified == 0 {
+        Ok(())
+    } else {
+        Err(miette!(
+            "{modified} binaries have been modified or removed since installation"
+        ))
+    }
+}
@@ -0,0 +1,253 @@
+//! Parse headers of executables to find out the architecture they are
+//! built for.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use compact_str::{format_compact, CompactString};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Elf,
+    Pe,
+    MachO,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Elf => "ELF",
+            Format::Pe => "PE",
+            Format::MachO => "Mach-O",
+        }
+    }
+
+    fn of_target(target: &str) -> Option<Self> {
+        if target.contains("windows") {
+            Some(Format::Pe)
+        } else if target.contains("apple") {
+            Some(Format::MachO)
+        } else if ["linux", "android", "bsd", "illumos", "solaris"]
+            .iter()
+            .any(|os| target.contains(os))
+        {
+            Some(Format::Elf)
+        } else {
+            None
+        }
+    }
+}
+
+/// Machine types of each format along with the target arch prefixes that
+/// use it, the first match wins.
+const MACHINES: &[(Format, u32, &[&str])] = &[
+    (Format::Elf, 62, &["x86_64"]),
+    (Format::Elf, 3, &["i386", "i586", "i686"]),
+    (Format::Elf, 183, &["aarch64"]),
+    (Format::Elf, 40, &["arm", "thumb"]),
+    (Format::Elf, 243, &["riscv"]),
+    (Format::Elf, 21, &["powerpc64"]),
+    (Format::Elf, 20, &["powerpc"]),
+    (Format::Elf, 22, &["s390x"]),
+    (Format::Elf, 8, &["mips"]),
+    (Format::Elf, 258, &["loongarch64"]),
+    (Format::Pe, 0x8664, &["x86_64"]),
+    (Format::Pe, 0x14c, &["i586", "i686"]),
+    (Format::Pe, 0xaa64, &["aarch64"]),
+    (Format::Pe, 0x1c4, &["thumbv7a"]),
+    (Format::MachO, 0x0100_0007, &["x86_64"]),
+    (Format::MachO, 7, &["i386", "i686"]),
+    (Format::MachO, 0x0100_000c, &["aarch64", "arm64"]),
+    (Format::MachO, 12, &["armv7"]),
+];
+
+#[derive(Debug, Eq, PartialEq)]
+struct Header {
+    format: Format,
+    /// More than one for Mach-O universal binaries.
+    machines: Vec<u32>,
+}
+
+impl Header {
+    /// Return `None` if `bytes` is not the start of a known executable, e.g.
+    /// a shell script.
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let u16_at = |offset: usize, big_endian: bool| {
+            let bytes = bytes.get(offset..offset + 2)?.try_into().unwrap();
+            Some(if big_endian {
+                u16::from_be_bytes(bytes)
+            } else {
+                u16::from_le_bytes(bytes)
+            })
+        };
+        let u32_at = |offset: usize, big_endian: bool| {
+            let bytes = bytes.get(offset..offset + 4)?.try_into().unwrap();
+            Some(if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            })
+        };
+
+        let (format, machines) = match bytes.get(..4)? {
+            [0x7f, b'E', b'L', b'F'] => {
+                let big_endian = *bytes.get(5)? == 2;
+                (Format::Elf, vec![u16_at(18, big_endian)?.into()])
+            }
+            [b'M', b'Z', ..] => {
+                let offset = usize::try_from(u32_at(0x3c, false)?).ok()?;
+                if bytes.get(offset..offset.checked_add(4)?)? != b"PE\0\0" {
+                    return None;
+                }
+                (Format::Pe, vec![u16_at(offset + 4, false)?.into()])
+            }
+            [0xfe, 0xed, 0xfa, 0xce | 0xcf] => (Format::MachO, vec![u32_at(4, true)?]),
+            [0xce | 0xcf, 0xfa, 0xed, 0xfe] => (Format::MachO, vec![u32_at(4, false)?]),
+            // Universal binary, 32-bit and 64-bit variants.
+            [0xca, 0xfe, 0xba, 0xbe | 0xbf] => {
+                let entry_len = if bytes[3] == 0xbe { 20 } else { 32 };
+                // Java class files share the same magic, but it is unlikely
+                // for a universal binary to have that many archs.
+                let n = u32_at(4, true)?;
+                if n == 0 || n > 20 {
+                    return None;
+                }
+
+                let machines = (0..n as usize)
+                    .map(|i| u32_at(8 + i * entry_len, true))
+                    .collect::<Option<_>>()?;
+
+                (Format::MachO, machines)
+            }
+            _ => return None,
+        };
+
+        Some(Self { format, machines })
+    }
+
+    fn describe(&self) -> CompactString {
+        let mut description = CompactString::new(self.format.name());
+
+        for (i, machine) in self.machines.iter().enumerate() {
+            description.push_str(if i == 0 { " " } else { ", " });
+
+            match MACHINES
+                .iter()
+                .find(|(format, m, _)| *format == self.format && m == machine)
+            {
+                Some((_, _, archs)) => description.push_str(archs[0]),
+                None => description += &format_compact!("unknown machine {machine:#x}"),
+            }
+        }
+
+        description
+    }
+}
+
+/// Return `Some(description)` of the arch `path` is built for if it is not
+/// `target`.
+///
+/// Files that are not recognised as executables, e.g. scripts, and
+/// targets unknown to this function are not checked.
+pub(super) fn find_arch_mismatch(path: &Path, target: &str) -> io::Result<Option<CompactString>> {
+    let mut bytes = Vec::with_capacity(4096);
+    File::open(path)?.take(4096).read_to_end(&mut bytes)?;
+
+    Ok(check_header(&bytes, target))
+}
+
+fn check_header(bytes: &[u8], target: &str) -> Option<CompactString> {
+    let header = Header::parse(bytes)?;
+    let expected_format = Format::of_target(target)?;
+
+    if header.format != expected_format {
+        return Some(header.describe());
+    }
+
+    let arch = target.split('-').next().unwrap_or(target);
+    let expected_machine = MACHINES.iter().find_map(|(format, machine, archs)| {
+        (*format == expected_format && archs.iter().any(|prefix| arch.starts_with(prefix)))
+            .then_some(*machine)
+    })?;
+
+    if header.machines.contains(&expected_machine) {
+        None
+    } else {
+        Some(header.describe())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn elf(machine: u16) -> Vec<u8> {
+        let mut bytes = vec![0; 64];
+        bytes[..4].copy_from_slice(b"\x7fELF");
+        bytes[4] = 2;
+        bytes[5] = 1;
+        bytes[18..20].copy_from_slice(&machine.to_le_bytes());
+        bytes
+    }
+
+    fn pe(machine: u16) -> Vec<u8> {
+        let mut bytes = vec![0; 0x100];
+        bytes[..2].copy_from_slice(b"MZ");
+        bytes[0x3c..0x40].copy_from_slice(&0x80_u32.to_le_bytes());
+        bytes[0x80..0x84].copy_from_slice(b"PE\0\0");
+        bytes[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        bytes
+    }
+
+    fn universal(machines: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe];
+        bytes.extend_from_slice(&(machines.len() as u32).to_be_bytes());
+        for machine in machines {
+            bytes.extend_from_slice(&machine.to_be_bytes());
+            bytes.extend_from_slice(&[0; 16]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_check_header() {
+        assert_eq!(check_header(&elf(62), "x86_64-unknown-linux-gnu"), None);
+        assert_eq!(check_header(&elf(183), "aarch64-unknown-linux-musl"), None);
+        assert_eq!(
+            check_header(&elf(183), "x86_64-unknown-linux-gnu").as_deref(),
+            Some("ELF aarch64")
+        );
+        assert_eq!(
+            check_header(&elf(40), "armv7-unknown-linux-gnueabihf"),
+            None
+        );
+
+        assert_eq!(check_header(&pe(0x8664), "x86_64-pc-windows-msvc"), None);
+        assert_eq!(
+            check_header(&pe(0x14c), "x86_64-pc-windows-msvc").as_deref(),
+            Some("PE i586")
+        );
+        assert_eq!(
+            check_header(&elf(62), "x86_64-pc-windows-gnu").as_deref(),
+            Some("ELF x86_64")
+        );
+
+        let fat = universal(&[0x0100_0007, 0x0100_000c]);
+        assert_eq!(check_header(&fat, "aarch64-apple-darwin"), None);
+        assert_eq!(check_header(&fat, "universal2-apple-darwin"), None);
+        assert_eq!(
+            check_header(&universal(&[0x0100_0007]), "aarch64-apple-darwin").as_deref(),
+            Some("Mach-O x86_64")
+        );
+
+        // Not an executable or unknown target
+        assert_eq!(
+            check_header(b"#!/bin/sh\n", "x86_64-unknown-linux-gnu"),
+            None
+        );
+        assert_eq!(check_header(&elf(62), "wasm32-wasi"), None);
+    }
+}
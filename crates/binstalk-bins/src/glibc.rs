@@ -0,0 +1,244 @@
+//! Parse the version requirements of ELF executables to find out the
+//! glibc version they need.
+
+use std::{fs, io, path::Path};
+
+/// Section type of `.gnu.version_r`.
+const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+
+/// Return the highest `GLIBC_x.y` version `path` requires, or `None` if
+/// it is not a dynamically linked ELF requiring glibc, e.g. a static musl
+/// executable or a script.
+pub(super) fn find_required_glibc(path: &Path) -> io::Result<Option<(u32, u32)>> {
+    Ok(parse_required_glibc(&fs::read(path)?))
+}
+
+fn parse_required_glibc(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = match *bytes.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = *bytes.get(5)? == 2;
+
+    let u16_at = |offset: usize| {
+        let bytes = bytes
+            .get(offset..offset.checked_add(2)?)?
+            .try_into()
+            .unwrap();
+        Some(usize::from(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }))
+    };
+    let u32_at = |offset: usize| {
+        let bytes = bytes
+            .get(offset..offset.checked_add(4)?)?
+            .try_into()
+            .unwrap();
+        usize::try_from(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+        .ok()
+    };
+    let u64_at = |offset: usize| {
+        let bytes = bytes
+            .get(offset..offset.checked_add(8)?)?
+            .try_into()
+            .unwrap();
+        usize::try_from(if big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+        .ok()
+    };
+    let addr_at = |offset: usize| {
+        if is_64 {
+            u64_at(offset)
+        } else {
+            u32_at(offset)
+        }
+    };
+
+    // Return (type, offset, size, link) of the section at `index`.
+    let section = |index: usize| {
+        let (shoff, shentsize) = if is_64 {
+            (u64_at(0x28)?, u16_at(0x3a)?)
+        } else {
+            (u32_at(0x20)?, u16_at(0x2e)?)
+        };
+        let header = shoff.checked_add(index.checked_mul(shentsize)?)?;
+        let (offset, size, link) = if is_64 {
+            (addr_at(header + 0x18)?, addr_at(header + 0x20)?, 0x28)
+        } else {
+            (addr_at(header + 0x10)?, addr_at(header + 0x14)?, 0x18)
+        };
+        Some((u32_at(header + 4)?, offset, size, u32_at(header + link)?))
+    };
+
+    let shnum = u16_at(if is_64 { 0x3c } else { 0x30 })?;
+    let (verneed_offset, verneed_size, dynstr_index) = (0..shnum).find_map(|index| {
+        let (sh_type, offset, size, link) = section(index)?;
+        (sh_type == SHT_GNU_VERNEED as usize).then_some((offset, size, link))
+    })?;
+    let (_, dynstr_offset, dynstr_size, _) = section(dynstr_index)?;
+    let dynstr = bytes.get(dynstr_offset..dynstr_offset.checked_add(dynstr_size)?)?;
+
+    let name_at = |offset: usize| {
+        let name = dynstr.get(offset..)?;
+        let len = name.iter().position(|b| *b == 0)?;
+        std::str::from_utf8(&name[..len]).ok()
+    };
+
+    // Each entry is at least 16 bytes, which bounds the number of entries
+    // to visit even if the `next` offsets form a loop.
+    let max_entries = verneed_size / 16;
+    let mut required = None;
+
+    // Walk the `Elf_Verneed` entries and their `Elf_Vernaux` entries.
+    let mut verneed = verneed_offset;
+    for _ in 0..max_entries {
+        let mut vernaux = verneed.checked_add(u32_at(verneed + 8)?)?;
+        for _ in 0..u16_at(verneed + 2)?.min(max_entries) {
+            if let Some(version) = name_at(u32_at(vernaux + 8)?)
+                .and_then(|name| name.strip_prefix("GLIBC_"))
+                .and_then(parse_version)
+            {
+                required = required.max(Some(version));
+            }
+
+            match u32_at(vernaux + 12)? {
+                0 => break,
+                next => vernaux = vernaux.checked_add(next)?,
+            }
+        }
+
+        match u32_at(verneed + 12)? {
+            0 => break,
+            next => verneed = verneed.checked_add(next)?,
+        }
+    }
+
+    required
+}
+
+/// Parse `x.y` or `x.y.z` into `(x, y)`.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build an ELF with `.dynstr` and `.gnu.version_r` sections requiring
+    /// `versions` of `libc.so.6`.
+    fn elf_requiring(versions: &[&str], is_64: bool, big_endian: bool) -> Vec<u8> {
+        let mut bytes = vec![0; 64];
+        bytes[..4].copy_from_slice(b"\x7fELF");
+        bytes[4] = if is_64 { 2 } else { 1 };
+        bytes[5] = if big_endian { 2 } else { 1 };
+
+        let put = |bytes: &mut Vec<u8>, offset: usize, value: u64, len: usize| {
+            let value = if big_endian {
+                value.to_be_bytes()[8 - len..].to_vec()
+            } else {
+                value.to_le_bytes()[..len].to_vec()
+            };
+            bytes[offset..offset + len].copy_from_slice(&value);
+        };
+        let addr_len = if is_64 { 8 } else { 4 };
+
+        // .dynstr
+        let dynstr_offset = bytes.len();
+        bytes.extend_from_slice(b"\0libc.so.6\0");
+        let names: Vec<_> = versions
+            .iter()
+            .map(|version| {
+                let name = bytes.len() - dynstr_offset;
+                bytes.extend_from_slice(version.as_bytes());
+                bytes.push(0);
+                name
+            })
+            .collect();
+        let dynstr_size = bytes.len() - dynstr_offset;
+        bytes.resize((bytes.len() + 7) / 8 * 8, 0);
+
+        // .gnu.version_r, with one Elf_Verneed followed by its Elf_Vernaux.
+        let verneed_offset = bytes.len();
+        bytes.resize(verneed_offset + 16 * (1 + names.len()), 0);
+        put(&mut bytes, verneed_offset, 1, 2);
+        put(&mut bytes, verneed_offset + 2, names.len() as u64, 2);
+        put(&mut bytes, verneed_offset + 4, 1, 4);
+        put(&mut bytes, verneed_offset + 8, 16, 4);
+        for (i, name) in names.iter().enumerate() {
+            let vernaux = verneed_offset + 16 * (1 + i);
+            put(&mut bytes, vernaux + 8, *name as u64, 4);
+            if i + 1 != names.len() {
+                put(&mut bytes, vernaux + 12, 16, 4);
+            }
+        }
+        let verneed_size = bytes.len() - verneed_offset;
+
+        // Section headers: null, .dynstr and .gnu.version_r
+        let shoff = bytes.len();
+        let shentsize = if is_64 { 64 } else { 40 };
+        bytes.resize(shoff + 3 * shentsize, 0);
+        for (index, sh_type, offset, size, link) in [
+            (1, 3, dynstr_offset, dynstr_size, 0),
+            (2, SHT_GNU_VERNEED, verneed_offset, verneed_size, 1),
+        ] {
+            let header = shoff + index * shentsize;
+            let (offset_at, link_at) = if is_64 { (0x18, 0x28) } else { (0x10, 0x18) };
+            put(&mut bytes, header + 4, sh_type.into(), 4);
+            put(&mut bytes, header + offset_at, offset as u64, addr_len);
+            put(
+                &mut bytes,
+                header + offset_at + addr_len,
+                size as u64,
+                addr_len,
+            );
+            put(&mut bytes, header + link_at, link, 4);
+        }
+
+        let (shoff_at, shentsize_at, shnum_at) = if is_64 {
+            (0x28, 0x3a, 0x3c)
+        } else {
+            (0x20, 0x2e, 0x30)
+        };
+        put(&mut bytes, shoff_at, shoff as u64, addr_len);
+        put(&mut bytes, shentsize_at, shentsize as u64, 2);
+        put(&mut bytes, shnum_at, 3, 2);
+
+        bytes
+    }
+
+    #[test]
+    fn test_parse_required_glibc() {
+        let versions = ["GLIBC_2.2.5", "GLIBC_2.99", "GLIBC_2.17", "GLIBC_PRIVATE"];
+
+        for (is_64, big_endian) in [(true, false), (true, true), (false, false), (false, true)] {
+            assert_eq!(
+                parse_required_glibc(&elf_requiring(&versions, is_64, big_endian)),
+                Some((2, 99)),
+                "is_64={is_64}, big_endian={big_endian}"
+            );
+        }
+
+        assert_eq!(
+            parse_required_glibc(&elf_requiring(&["GLIBC_2.3"], true, false)),
+            Some((2, 3))
+        );
+        // Static executables and scripts have no requirements.
+        assert_eq!(parse_required_glibc(&elf_requiring(&[], true, false)), None);
+        assert_eq!(parse_required_glibc(b"#!/bin/sh\n"), None);
+    }
+}
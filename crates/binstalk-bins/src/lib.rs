@@ -1,20 +1,28 @@
 use std::{
     borrow::Cow,
-    fmt, io,
+    ffi::OsStr,
+    fmt, fs, io,
     path::{self, Component, Path, PathBuf},
 };
 
 use atomic_file_install::{
-    atomic_install, atomic_install_noclobber, atomic_symlink_file, atomic_symlink_file_noclobber,
+    atomic_hard_link, atomic_hard_link_noclobber, atomic_install, atomic_install_noclobber,
+    atomic_symlink_file, atomic_symlink_file_noclobber,
 };
+
+pub use atomic_file_install::remove_stale_files;
 use binstalk_types::cargo_toml_binstall::{PkgFmt, PkgMeta};
 use compact_str::{format_compact, CompactString};
 use leon::Template;
 use miette::Diagnostic;
 use normalize_path::NormalizePath;
+use sha2::{Digest, Sha256};
 use thiserror::Error as ThisError;
 use tracing::debug;
 
+mod arch;
+mod glibc;
+
 #[derive(Debug, ThisError, Diagnostic)]
 pub enum Error {
     /// bin-dir configuration provided generates source path outside
@@ -32,6 +40,22 @@ pub enum Error {
     #[error("bin file {} not found", .0.display())]
     BinFileNotFound(Box<Path>),
 
+    /// Bin file is built for another target.
+    #[error("bin file {} is built for {found}, not for target {target}", path.display())]
+    ArchMismatch {
+        path: Box<Path>,
+        found: CompactString,
+        target: CompactString,
+    },
+
+    /// Bin file requires a newer glibc than the one on the host.
+    #[error("bin file {} requires glibc {required}, but the host only has glibc {host}", path.display())]
+    GlibcTooNew {
+        path: Box<Path>,
+        required: CompactString,
+        host: CompactString,
+    },
+
     #[error(transparent)]
     Io(#[from] io::Error),
 
@@ -40,6 +64,10 @@ pub enum Error {
     TemplateRender(#[from] leon::RenderError),
 }
 
+/// Value of `bin-dir` that makes binstall search the extracted package for
+/// the binaries, for packages with unpredictable directory names.
+pub const AUTO_BIN_DIR: &str = "auto";
+
 /// Return true if the path does not look outside of current dir
 ///
 ///  * `path` - must be normalized before passing to this function
@@ -52,6 +80,16 @@ fn is_valid_path(path: &Path) -> bool {
     )
 }
 
+/// Return SHA256 of the file at `path`, hex-encoded.
+///
+/// This function uses blocking I/O.
+pub fn sha256_file(path: &Path) -> io::Result<CompactString> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+
+    Ok(format_compact!("{:x}", hasher.finalize()))
+}
+
 /// Must be called after the archive is downloaded and extracted.
 /// This function might uses blocking I/O.
 pub fn infer_bin_dir_template(
@@ -126,7 +164,9 @@ impl BinFile {
             target_related_info: data.target_related_info,
         };
 
-        let (source, archive_source_path) = if data.meta.pkg_fmt == Some(PkgFmt::Bin) {
+        let is_bin = data.meta.pkg_fmt.map(PkgFmt::is_bin).unwrap_or_default();
+
+        let (source, archive_source_path) = if is_bin {
             (
                 data.bin_path.to_path_buf(),
                 data.bin_path.file_name().unwrap().into(),
@@ -207,11 +247,81 @@ impl BinFile {
         }
     }
 
+    /// Search `files` extracted into `bin_path` for the bin file, for when it
+    /// is not at the path generated from `bin-dir`, e.g. because the package
+    /// has a top-level directory with an unpredictable name.
+    ///
+    /// The match with the least components wins, return `false` if none is
+    /// found.
+    pub fn search_source<'a>(
+        &mut self,
+        bin_path: &Path,
+        files: impl Iterator<Item = &'a Path>,
+    ) -> bool {
+        let base_name = OsStr::new(self.base_name.as_str());
+
+        let Some(found) = files
+            .filter(|path| path.file_name() == Some(base_name))
+            .min_by_key(|path| (path.components().count(), *path))
+        else {
+            return false;
+        };
+
+        debug!(
+            "Found bin file '{}' at '{}' instead of '{}'",
+            self.base_name,
+            found.display(),
+            self.archive_source_path.display()
+        );
+
+        self.source = bin_path.join(found);
+        self.archive_source_path = found.to_path_buf();
+
+        true
+    }
+
+    /// Return `Ok` if the source is built for `target` or is not a
+    /// recognised executable, e.g. a script.
+    ///
+    /// This function uses blocking I/O.
+    pub fn verify_arch(&self, target: &str) -> Result<(), Error> {
+        match arch::find_arch_mismatch(&self.source, target)? {
+            None => Ok(()),
+            Some(found) => Err(Error::ArchMismatch {
+                path: (&*self.source).into(),
+                found,
+                target: target.into(),
+            }),
+        }
+    }
+
+    /// Check that the glibc version required by the bin file, if any, is
+    /// not newer than `host_glibc`, the `(major, minor)` version of glibc
+    /// on the host.
+    ///
+    /// This function uses blocking I/O.
+    pub fn verify_glibc(&self, host_glibc: (u32, u32)) -> Result<(), Error> {
+        match glibc::find_required_glibc(&self.source)? {
+            Some(required) if required > host_glibc => Err(Error::GlibcTooNew {
+                path: (&*self.source).into(),
+                required: format_compact!("{}.{}", required.0, required.1),
+                host: format_compact!("{}.{}", host_glibc.0, host_glibc.1),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     fn pre_install_bin(&self) -> Result<(), Error> {
         if !self.source.try_exists()? {
             return Err(Error::BinFileNotFound((&*self.source).into()));
         }
 
+        // The dest dir may be removed after the install path is created,
+        // or a bin may be installed to a subdir of it.
+        if let Some(parent) = self.dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
         #[cfg(unix)]
         std::fs::set_permissions(
             &self.source,
@@ -249,6 +359,36 @@ impl BinFile {
         Ok(())
     }
 
+    /// Install the binary by hard linking `file`, which must have the same
+    /// content as the source, instead of copying the source.
+    pub fn install_bin_hard_link(&self, file: &Path) -> Result<(), Error> {
+        self.pre_install_bin()?;
+
+        debug!(
+            "Atomically hard link file '{}' to '{}'",
+            file.display(),
+            self.dest.display()
+        );
+
+        atomic_hard_link(file, &self.dest)?;
+
+        Ok(())
+    }
+
+    pub fn install_bin_hard_link_noclobber(&self, file: &Path) -> Result<(), Error> {
+        self.pre_install_bin()?;
+
+        debug!(
+            "Hard link file '{}' to '{}' only if dst not exists",
+            file.display(),
+            self.dest.display()
+        );
+
+        atomic_hard_link_noclobber(file, &self.dest)?;
+
+        Ok(())
+    }
+
     pub fn install_link(&self) -> Result<(), Error> {
         if let Some(link) = &self.link {
             let dest = self.link_dest();
@@ -277,6 +417,40 @@ impl BinFile {
         Ok(())
     }
 
+    /// Back up the file at `dest` before installing, so that it can be
+    /// restored by [`BinBackup::restore`] if installation of the crate fails.
+    pub fn backup(&self) -> Result<BinBackup, Error> {
+        let mut backup = self.dest.clone().into_os_string();
+        backup.push(".binstall-backup");
+        let backup = PathBuf::from(backup);
+
+        let backup = match fs::hard_link(&self.dest, &backup) {
+            Ok(()) => Some(backup),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                fs::remove_file(&backup)?;
+                return self.backup();
+            }
+            // Hard link might not be supported, fallback to copying
+            Err(_) if self.dest.try_exists()? => {
+                fs::copy(&self.dest, &backup)?;
+                Some(backup)
+            }
+            Err(_) => None,
+        };
+
+        debug!(
+            "Backup '{}' to '{:?}' before installing",
+            self.dest.display(),
+            backup
+        );
+
+        Ok(BinBackup {
+            dest: self.dest.clone(),
+            backup,
+        })
+    }
+
     fn link_dest(&self) -> &Path {
         if cfg!(target_family = "unix") {
             Path::new(self.dest.file_name().unwrap())
@@ -286,6 +460,45 @@ impl BinFile {
     }
 }
 
+/// Backup of a bin file taken by [`BinFile::backup`].
+#[derive(Debug)]
+pub struct BinBackup {
+    dest: PathBuf,
+    /// `None` if the bin file did not exist.
+    backup: Option<PathBuf>,
+}
+
+impl BinBackup {
+    /// Restore the bin file to the state before installation.
+    pub fn restore(mut self) -> Result<(), Error> {
+        if let Some(backup) = self.backup.take() {
+            debug!(
+                "Restore '{}' from backup '{}'",
+                self.dest.display(),
+                backup.display()
+            );
+            atomic_install(&backup, &self.dest)?;
+            fs::remove_file(&backup)?;
+        } else {
+            debug!("Remove newly installed '{}'", self.dest.display());
+            match fs::remove_file(&self.dest) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for BinBackup {
+    fn drop(&mut self) {
+        if let Some(backup) = &self.backup {
+            fs::remove_file(backup).ok();
+        }
+    }
+}
+
 /// Data required to get bin paths
 pub struct Data<'a> {
     pub name: &'a str,
@@ -1,24 +1,37 @@
 use std::{fmt, io, marker::PhantomData, path::Path};
 
 use binstalk_types::cargo_toml_binstall::PkgFmtDecomposed;
-use bytes::Bytes;
-use futures_util::{stream::FusedStream, Stream, StreamExt};
+use bytes::{Bytes, BytesMut};
+use compact_str::{format_compact, CompactString};
+use futures_util::{
+    future::{self, Either},
+    stream::{self, FusedStream},
+    Stream, StreamExt,
+};
+use sha2::{Digest, Sha256};
 use thiserror::Error as ThisError;
+use tokio_util::io::ReaderStream;
 use tracing::{debug, error, instrument};
 
-pub use binstalk_types::cargo_toml_binstall::{PkgFmt, TarBasedFmt};
+pub use binstalk_types::cargo_toml_binstall::{CompressedBinFmt, PkgFmt, TarBasedFmt};
 
 use crate::remote::{Client, Error as RemoteError, Url};
 
 mod async_extracter;
 use async_extracter::*;
 
+mod cache;
+pub use cache::{CacheStats, DownloadCache};
+
 mod async_tar_visitor;
 use async_tar_visitor::extract_tar_based_stream_and_visit;
 pub use async_tar_visitor::{TarEntriesVisitor, TarEntry, TarEntryType};
 
 mod extracter;
 
+mod format_detection;
+use format_detection::{detect_pkg_fmt, PREFIX_LEN};
+
 mod extracted_files;
 pub use extracted_files::{ExtractedFiles, ExtractedFilesEntry};
 
@@ -40,6 +53,14 @@ pub enum DownloadError {
     /// - Exit: 74
     #[error("I/O Error: {0}")]
     Io(io::Error),
+
+    /// The format of the package downloaded could not be detected from its
+    /// content.
+    ///
+    /// - Code: `binstall::download`
+    /// - Exit: 68
+    #[error("Failed to detect the format of the downloaded file")]
+    UnknownFormat,
 }
 
 impl From<io::Error> for DownloadError {
@@ -90,10 +111,47 @@ impl DataVerifier for () {
     }
 }
 
+/// Compute SHA256 of the data passed to the inner verifier, if `enabled`.
+struct Sha256Verifier<'a> {
+    hasher: Option<Sha256>,
+    inner: Option<&'a mut dyn DataVerifier>,
+}
+
+impl<'a> Sha256Verifier<'a> {
+    fn new(enabled: bool, inner: Option<&'a mut dyn DataVerifier>) -> Self {
+        Self {
+            hasher: enabled.then(Sha256::new),
+            inner,
+        }
+    }
+
+    fn finalize(self) -> Option<CompactString> {
+        self.hasher
+            .map(|hasher| format_compact!("{:x}", hasher.finalize()))
+    }
+}
+
+impl DataVerifier for Sha256Verifier<'_> {
+    fn update(&mut self, data: &Bytes) {
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(data);
+        }
+        if let Some(inner) = &mut self.inner {
+            inner.update(data);
+        }
+    }
+
+    /// The inner verifier is validated by its owner.
+    fn validate(&mut self) -> bool {
+        true
+    }
+}
+
 pub struct Download<'a> {
     client: Client,
     url: Url,
     data_verifier: Option<&'a mut dyn DataVerifier>,
+    sha256: bool,
 }
 
 impl fmt::Debug for Download<'_> {
@@ -104,6 +162,7 @@ impl fmt::Debug for Download<'_> {
             client: &'a Client,
             url: &'a Url,
             data_verifier: Option<PhantomData<&'a mut dyn DataVerifier>>,
+            sha256: bool,
         }
 
         fmt::Debug::fmt(
@@ -111,6 +170,7 @@ impl fmt::Debug for Download<'_> {
                 client: &self.client,
                 url: &self.url,
                 data_verifier: self.data_verifier.as_ref().map(|_| PhantomData),
+                sha256: self.sha256,
             },
             f,
         )
@@ -123,6 +183,7 @@ impl Download<'static> {
             client,
             url,
             data_verifier: None,
+            sha256: false,
         }
     }
 }
@@ -137,6 +198,17 @@ impl<'a> Download<'a> {
             client,
             url,
             data_verifier: Some(data_verifier),
+            sha256: false,
+        }
+    }
+
+    /// Compute SHA256 of the file downloaded by [`Download::and_extract`]
+    /// or [`Download::and_extract_detected`], which is then available via
+    /// [`ExtractedFiles::sha256`].
+    pub fn with_sha256(self) -> Self {
+        Self {
+            sha256: true,
+            ..self
         }
     }
 
@@ -147,10 +219,26 @@ impl<'a> Download<'a> {
         DownloadError,
     > {
         let mut data_verifier = self.data_verifier;
-        Ok(self
-            .client
-            .get_stream(self.url)
-            .await?
+
+        let stream = if let Some(download_cache) = self.client.download_cache() {
+            let path = download_cache
+                .get_or_download(&self.client, &self.url)
+                .await?;
+
+            Either::Left(
+                ReaderStream::new(tokio::fs::File::open(path).await?)
+                    .map(|res| res.map_err(DownloadError::from)),
+            )
+        } else {
+            Either::Right(
+                self.client
+                    .get_stream(self.url)
+                    .await?
+                    .map(|res| res.map_err(DownloadError::from)),
+            )
+        };
+
+        Ok(stream
             .map(move |res| {
                 let bytes = res?;
 
@@ -182,7 +270,41 @@ where
     }
 }
 
-impl Download<'_> {
+/// Extract `stream` to `path` as `fmt`, consuming the rest of it if
+/// `consume_all` so that the data verifier sees the entire file.
+async fn extract_stream<S>(
+    stream: &mut S,
+    fmt: PkgFmt,
+    path: &Path,
+    consume_all: bool,
+) -> Result<ExtractedFiles, DownloadError>
+where
+    S: Stream<Item = Result<Bytes, DownloadError>> + FusedStream + Send + Sync + Unpin,
+{
+    debug!("Downloading and extracting to: '{}'", path.display());
+
+    let res = match fmt.decompose() {
+        PkgFmtDecomposed::Tar(fmt) => extract_tar_based_stream(&mut *stream, path, fmt).await,
+        PkgFmtDecomposed::Bin => extract_bin(&mut *stream, path).await,
+        PkgFmtDecomposed::CompressedBin(fmt) => {
+            extract_compressed_bin(&mut *stream, path, fmt).await
+        }
+        PkgFmtDecomposed::Zip => extract_zip(&mut *stream, path).await,
+    };
+
+    // Archives might have trailing data not read by the extracter.
+    if consume_all {
+        consume_stream(stream).await;
+    }
+
+    if res.is_ok() {
+        debug!("Download OK, extracted to: '{}'", path.display());
+    }
+
+    res
+}
+
+impl<'a> Download<'a> {
     /// Download a file from the provided URL and process it in memory.
     ///
     /// This does not support verifying a checksum due to the partial extraction
@@ -215,8 +337,30 @@ impl Download<'_> {
         }
     }
 
+    /// Replace the data verifier with one computing SHA256 of the file
+    /// downloaded if requested, which wraps the original one.
+    fn with_sha256_verifier<'b>(
+        mut self,
+        sha256_verifier: &'b mut Sha256Verifier<'a>,
+    ) -> Download<'b>
+    where
+        'a: 'b,
+    {
+        *sha256_verifier = Sha256Verifier::new(self.sha256, self.data_verifier.take());
+
+        Download {
+            client: self.client,
+            url: self.url,
+            data_verifier: Some(sha256_verifier),
+            sha256: self.sha256,
+        }
+    }
+
     /// Download a file from the provided URL and extract it to the provided path.
     ///
+    /// SHA256 of the file downloaded is available via [`ExtractedFiles::sha256`]
+    /// if [`Download::with_sha256`] is called.
+    ///
     /// NOTE that this will only extract directory and regular files.
     #[instrument(skip(path))]
     pub async fn and_extract(
@@ -229,39 +373,98 @@ impl Download<'_> {
             fmt: PkgFmt,
             path: &Path,
         ) -> Result<ExtractedFiles, DownloadError> {
-            let has_data_verifier = this.data_verifier.is_some();
-            let mut stream = this.get_stream().await?;
+            // Only consume the whole file if its checksum is needed.
+            let consume_all = this.sha256 || this.data_verifier.is_some();
+            let mut sha256_verifier = Sha256Verifier::new(false, None);
+            let mut stream = this
+                .with_sha256_verifier(&mut sha256_verifier)
+                .get_stream()
+                .await?;
 
-            debug!("Downloading and extracting to: '{}'", path.display());
+            let res = extract_stream(&mut stream, fmt, path, consume_all).await;
+            drop(stream);
 
-            let res = match fmt.decompose() {
-                PkgFmtDecomposed::Tar(fmt) => {
-                    extract_tar_based_stream(&mut stream, path, fmt).await
-                }
-                PkgFmtDecomposed::Bin => extract_bin(&mut stream, path).await,
-                PkgFmtDecomposed::Zip => extract_zip(&mut stream, path).await,
-            };
-
-            match res {
-                Ok(extracted_files) => {
-                    debug!("Download OK, extracted to: '{}'", path.display());
-                    Ok(extracted_files)
-                }
-                Err(err) => {
-                    if has_data_verifier {
-                        consume_stream(&mut stream).await;
-                    }
-                    Err(err)
+            let mut extracted_files = res?;
+            extracted_files.sha256 = sha256_verifier.finalize();
+
+            Ok(extracted_files)
+        }
+
+        inner(self, fmt, path.as_ref()).await
+    }
+
+    /// Download a file from the provided URL and extract it to the provided path,
+    /// detecting its format from its magic bytes.
+    ///
+    /// `fallback` is used if the format cannot be detected, e.g. one guessed
+    /// from the URL.
+    ///
+    /// Return the detected format along with the extracted files, SHA256 of
+    /// the file downloaded is available via [`ExtractedFiles::sha256`] if
+    /// [`Download::with_sha256`] is called.
+    ///
+    /// NOTE that this will only extract directory and regular files.
+    #[instrument(skip(path))]
+    pub async fn and_extract_detected(
+        self,
+        fallback: Option<PkgFmt>,
+        path: impl AsRef<Path>,
+    ) -> Result<(PkgFmt, ExtractedFiles), DownloadError> {
+        async fn inner(
+            this: Download<'_>,
+            fallback: Option<PkgFmt>,
+            path: &Path,
+        ) -> Result<(PkgFmt, ExtractedFiles), DownloadError> {
+            // Only consume the whole file if its checksum is needed.
+            let consume_all = this.sha256 || this.data_verifier.is_some();
+            let mut sha256_verifier = Sha256Verifier::new(false, None);
+            let mut stream = this
+                .with_sha256_verifier(&mut sha256_verifier)
+                .get_stream()
+                .await?;
+
+            let mut prefix = BytesMut::new();
+            while prefix.len() < PREFIX_LEN {
+                match stream.next().await {
+                    Some(bytes) => prefix.extend_from_slice(&bytes?),
+                    None => break,
                 }
             }
+            let prefix = prefix.freeze();
+
+            let fmt = detect_pkg_fmt(&prefix, fallback)
+                .or(fallback)
+                .ok_or(DownloadError::UnknownFormat)?;
+            debug!("Detected format of the downloaded file: {fmt}");
+
+            let mut chained_stream = stream::once(future::ready(prefix))
+                .map(Ok)
+                .chain(&mut stream);
+
+            let res = extract_stream(&mut chained_stream, fmt, path, consume_all).await;
+            drop(chained_stream);
+            drop(stream);
+
+            let mut extracted_files = res?;
+            extracted_files.sha256 = sha256_verifier.finalize();
+
+            Ok((fmt, extracted_files))
         }
 
-        inner(self, fmt, path.as_ref()).await
+        inner(self, fallback, path.as_ref()).await
     }
 
     #[instrument]
     pub async fn into_bytes(self) -> Result<Bytes, DownloadError> {
-        let bytes = self.client.get(self.url).send(true).await?.bytes().await?;
+        let bytes = if let Some(download_cache) = self.client.download_cache() {
+            let path = download_cache
+                .get_or_download(&self.client, &self.url)
+                .await?;
+
+            tokio::fs::read(path).await?.into()
+        } else {
+            self.client.get(self.url).send(true).await?.bytes().await?
+        };
         if let Some(verifier) = self.data_verifier {
             verifier.update(&bytes);
         }
@@ -280,6 +483,36 @@ mod test {
     };
     use tempfile::tempdir;
 
+    #[test]
+    fn test_sha256_verifier() {
+        struct Counter(usize);
+
+        impl DataVerifier for Counter {
+            fn update(&mut self, data: &Bytes) {
+                self.0 += data.len();
+            }
+
+            fn validate(&mut self) -> bool {
+                true
+            }
+        }
+
+        let mut counter = Counter(0);
+        let mut verifier = Sha256Verifier::new(true, Some(&mut counter));
+        verifier.update(&Bytes::from_static(b"hello "));
+        verifier.update(&Bytes::from_static(b"world"));
+        assert_eq!(
+            verifier.finalize().as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+        // Data is passed to the inner verifier as well
+        assert_eq!(counter.0, 11);
+
+        let mut verifier = Sha256Verifier::new(false, None);
+        verifier.update(&Bytes::from_static(b"hello world"));
+        assert_eq!(verifier.finalize(), None);
+    }
+
     #[tokio::test]
     async fn test_and_extract() {
         let client = crate::remote::Client::new(
@@ -307,7 +540,7 @@ mod test {
         assert_eq!(extracted_files.get_dir(Path::new(".")).unwrap(), &files);
 
         assert_eq!(
-            extracted_files.0,
+            extracted_files.entries,
             HashMap::from([
                 (
                     Path::new("cargo-binstall").into(),
@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashSet,
     fs,
     future::Future,
     io::{self, Write},
@@ -14,8 +15,8 @@ use tokio_util::io::StreamReader;
 use tracing::debug;
 
 use super::{
-    extracter::*, zip_extraction::extract_zip_entry, DownloadError, ExtractedFiles, TarBasedFmt,
-    ZipError,
+    extracter::*, zip_extraction::extract_zip_entry, CompressedBinFmt, DownloadError,
+    ExtractedFiles, TarBasedFmt, ZipError,
 };
 use crate::utils::{extract_with_blocking_task, StreamReadable};
 
@@ -43,6 +44,36 @@ where
     Ok(extracted_files)
 }
 
+pub async fn extract_compressed_bin<S>(
+    stream: S,
+    path: &Path,
+    fmt: CompressedBinFmt,
+) -> Result<ExtractedFiles, DownloadError>
+where
+    S: Stream<Item = Result<Bytes, DownloadError>> + Send + Sync + Unpin,
+{
+    debug!(
+        "Decompressing from {fmt} compressed binary to `{}`",
+        path.display()
+    );
+
+    extract_with_blocking_decoder(stream, path, move |rx, path| {
+        let mut decoder = create_bin_decoder(StreamReadable::new(rx), fmt)?;
+        let mut file = fs::File::create(path)?;
+
+        io::copy(&mut decoder, &mut file)?;
+
+        file.flush()
+    })
+    .await?;
+
+    let mut extracted_files = ExtractedFiles::new();
+
+    extracted_files.add_file(Path::new(path.file_name().unwrap()));
+
+    Ok(extracted_files)
+}
+
 pub async fn extract_zip<S>(stream: S, path: &Path) -> Result<ExtractedFiles, DownloadError>
 where
     S: Stream<Item = Result<Bytes, DownloadError>> + Unpin + Send + Sync,
@@ -53,6 +84,7 @@ where
     let mut zip = ZipFileReader::with_tokio(reader);
     let mut buf = BytesMut::with_capacity(4 * 4096);
     let mut extracted_files = ExtractedFiles::new();
+    let mut link_paths = HashSet::new();
 
     while let Some(mut zip_reader) = zip.next_with_entry().await.map_err(ZipError::from_inner)? {
         extract_zip_entry(
@@ -60,6 +92,7 @@ where
             path,
             &mut buf,
             &mut extracted_files,
+            &mut link_paths,
         )
         .await?;
 
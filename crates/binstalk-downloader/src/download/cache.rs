@@ -0,0 +1,649 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use bytes::Bytes;
+use compact_str::CompactString;
+use filetime::{set_file_mtime, FileTime};
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+use tokio::{io::AsyncWriteExt, task::spawn_blocking};
+use tracing::debug;
+
+use super::DownloadError;
+use crate::remote::{Client, Url};
+
+/// Dir of the cache storing installed binaries, keyed by their SHA256.
+const BINS_DIR: &str = "bins";
+const STATS_FILE: &str = "stats";
+/// Extension of the files storing the SHA256 of the cached downloads.
+const SHA256_EXT: &str = "sha256";
+
+/// Cache of downloaded files, keyed by their url along with their `ETag`,
+/// or `Last-Modified` if there is no `ETag`.
+///
+/// Set it via [`Client::with_download_cache`] to make [`super::Download`]
+/// reuse files downloaded by previous runs. The `ETag` is requested before
+/// reusing a file, so that a file re-published at the same url is
+/// downloaded again. Files of servers providing neither are assumed to
+/// never change.
+///
+/// The SHA256 of each file is stored along with it and checked before the
+/// file is reused, so that corrupted files are downloaded again. Files used
+/// least recently are evicted first.
+///
+/// It also stores copies of installed binaries keyed by their SHA256, so
+/// that installing the same binary again, e.g. to another root, creates a
+/// hard link to it instead of a copy.
+#[derive(Clone, Debug)]
+pub struct DownloadCache(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+
+    // Stats of this run, added to the stats file by
+    // `DownloadCache::save_stats`.
+    hits: AtomicU64,
+    misses: AtomicU64,
+    linked_bins: AtomicU64,
+    reclaimed: AtomicU64,
+}
+
+/// Stats of the cache, accumulated across runs.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Total size of the files in the cache, in bytes.
+    pub size: u64,
+    /// Number of downloads served from the cache.
+    pub hits: u64,
+    /// Number of downloads not found in the cache.
+    pub misses: u64,
+    /// Number of binaries installed by hard linking them from the cache.
+    pub linked_bins: u64,
+    /// Disk space saved by hard linking binaries instead of copying them,
+    /// in bytes.
+    pub reclaimed: u64,
+}
+
+impl CacheStats {
+    /// Return the ratio of downloads served from the cache, `None` if
+    /// nothing has been downloaded.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total != 0).then(|| self.hits as f64 / total as f64)
+    }
+
+    fn parse(s: &str) -> Self {
+        let mut stats = Self::default();
+
+        for (key, value) in s.lines().filter_map(|line| line.split_once(' ')) {
+            let Ok(value) = value.trim().parse() else {
+                continue;
+            };
+
+            match key {
+                "hits" => stats.hits = value,
+                "misses" => stats.misses = value,
+                "linked-bins" => stats.linked_bins = value,
+                "reclaimed" => stats.reclaimed = value,
+                _ => (),
+            }
+        }
+
+        stats
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "hits {}\nmisses {}\nlinked-bins {}\nreclaimed {}\n",
+            self.hits, self.misses, self.linked_bins, self.reclaimed
+        )
+    }
+}
+
+impl DownloadCache {
+    /// Create a cache storing downloaded files in `dir`, which is created
+    /// when the first file is added to the cache.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self(Arc::new(Inner {
+            dir: dir.into(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            linked_bins: AtomicU64::new(0),
+            reclaimed: AtomicU64::new(0),
+        }))
+    }
+
+    /// Return the directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.0.dir
+    }
+
+    /// Return path to the entry of `url`, which is different for each
+    /// `validator` of its content.
+    fn entry_path(&self, url: &Url, validator: Option<&str>) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_str());
+        if let Some(validator) = validator {
+            hasher.update(b"\n");
+            hasher.update(validator);
+        }
+
+        self.0.dir.join(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Return the cached response of `url` along with its `ETag`, which
+    /// can be revalidated using `If-None-Match`.
+    pub async fn get_etag_response(&self, url: &Url) -> Option<(CompactString, Bytes)> {
+        let mut content = Bytes::from(tokio::fs::read(self.etag_entry_path(url)).await.ok()?);
+        let pos = content.iter().position(|b| *b == b'\n')?;
+
+        let etag = std::str::from_utf8(&content[..pos]).ok()?.into();
+        let body = content.split_off(pos + 1);
+
+        Some((etag, body))
+    }
+
+    /// Cache response of `url` along with its `ETag`.
+    pub async fn put_etag_response(&self, url: &Url, etag: &str, body: &[u8]) {
+        let dir = self.0.dir.clone();
+        let path = self.etag_entry_path(url);
+        let mut content = Vec::with_capacity(etag.len() + 1 + body.len());
+        content.extend_from_slice(etag.as_bytes());
+        content.push(b'\n');
+        content.extend_from_slice(body);
+
+        let res = spawn_blocking(move || {
+            fs::create_dir_all(&dir)?;
+            let mut file = NamedTempFile::new_in(&dir)?;
+            io::Write::write_all(&mut file, &content)?;
+            file.persist(&path).map_err(io::Error::from)?;
+            io::Result::Ok(())
+        })
+        .await
+        .map_err(io::Error::from)
+        .and_then(|res| res);
+
+        if let Err(err) = res {
+            debug!("Failed to cache response of '{url}': {err}");
+        }
+    }
+
+    fn etag_entry_path(&self, url: &Url) -> PathBuf {
+        let mut path = self.entry_path(url, None).into_os_string();
+        path.push(".etag");
+        path.into()
+    }
+
+    /// Return path to the cached file of `url`, downloading it first if it
+    /// is not in the cache yet or has changed on the server.
+    pub(super) async fn get_or_download(
+        &self,
+        client: &Client,
+        url: &Url,
+    ) -> Result<PathBuf, DownloadError> {
+        let validator = client.get_validator(url.clone()).await?;
+
+        let this = self.clone();
+        let cached_url = url.clone();
+        let cached_validator = validator.clone();
+        if let Some(path) =
+            spawn_blocking(move || this.get(&cached_url, cached_validator.as_deref()))
+                .await
+                .map_err(io::Error::from)?
+        {
+            debug!("Using cached download of '{url}' at '{}'", path.display());
+            self.0.hits.fetch_add(1, Relaxed);
+            return Ok(path);
+        }
+        self.0.misses.fetch_add(1, Relaxed);
+
+        self.put(
+            url,
+            validator.as_deref(),
+            client.get_stream(url.clone()).await?,
+        )
+        .await
+    }
+
+    /// Return path to the cached file of `url` with `validator` if its
+    /// SHA256 still matches the one stored along with it, and mark it as
+    /// used.
+    ///
+    /// A file that no longer matches is removed.
+    ///
+    /// This is a blocking function.
+    fn get(&self, url: &Url, validator: Option<&str>) -> Option<PathBuf> {
+        let path = self.entry_path(url, validator);
+
+        let expected = fs::read_to_string(sha256_path(&path)).ok()?;
+        if sha256_file(&path).ok()? != expected {
+            debug!("Removing corrupted download of '{url}' from cache");
+            fs::remove_file(&path).ok();
+            return None;
+        }
+
+        touch(&path);
+
+        Some(path)
+    }
+
+    /// Store the file of `url` with `validator` downloaded from `stream`,
+    /// along with its SHA256, and return its path.
+    async fn put<E>(
+        &self,
+        url: &Url,
+        validator: Option<&str>,
+        mut stream: impl Stream<Item = Result<Bytes, E>> + Unpin,
+    ) -> Result<PathBuf, DownloadError>
+    where
+        DownloadError: From<E>,
+    {
+        let path = self.entry_path(url, validator);
+
+        let dir = self.0.dir.clone();
+        let (file, temp_path) = spawn_blocking(move || {
+            fs::create_dir_all(&dir)?;
+            NamedTempFile::new_in(&dir).map(NamedTempFile::into_parts)
+        })
+        .await
+        .map_err(io::Error::from)??;
+
+        debug!("Downloading '{url}' to cache at '{}'", path.display());
+
+        let mut file = tokio::fs::File::from_std(file);
+        let mut hasher = Sha256::new();
+
+        while let Some(bytes) = stream.next().await {
+            let bytes = bytes?;
+            hasher.update(&bytes);
+            file.write_all(&bytes).await?;
+        }
+        file.flush().await?;
+        drop(file);
+
+        let sha256 = format!("{:x}", hasher.finalize());
+        let dir = self.0.dir.clone();
+        let sha256_dst = sha256_path(&path);
+
+        spawn_blocking(move || {
+            let mut file = NamedTempFile::new_in(&dir)?;
+            io::Write::write_all(&mut file, sha256.as_bytes())?;
+            file.persist(&sha256_dst)?;
+            io::Result::Ok(())
+        })
+        .await
+        .map_err(io::Error::from)??;
+
+        temp_path.persist(&path).map_err(io::Error::from)?;
+
+        Ok(path)
+    }
+
+    fn bin_path(&self, sha256: &str) -> PathBuf {
+        self.0.dir.join(BINS_DIR).join(sha256)
+    }
+
+    /// Return path to the cached binary with `sha256`, if any.
+    ///
+    /// The cached binary is hashed again, since binaries installed by hard
+    /// linking it share its content and might have been modified. It is
+    /// removed from the cache if its SHA256 no longer matches.
+    ///
+    /// This is a blocking function.
+    pub fn get_bin(&self, sha256: &str) -> Option<PathBuf> {
+        let path = self.bin_path(sha256);
+
+        if sha256_file(&path).ok()? == sha256 {
+            touch(&path);
+            Some(path)
+        } else {
+            debug!("Removing modified binary '{}' from cache", path.display());
+            fs::remove_file(&path).ok();
+            None
+        }
+    }
+
+    /// Add a copy of the binary installed at `path` with `sha256` to the
+    /// cache.
+    ///
+    /// It is copied rather than hard linked, so that modifying the installed
+    /// binary does not modify the cached one.
+    ///
+    /// Failures are only logged.
+    ///
+    /// This is a blocking function.
+    pub fn put_bin(&self, path: &Path, sha256: &str) {
+        let bins_dir = self.0.dir.join(BINS_DIR);
+        let bin_path = self.bin_path(sha256);
+
+        let res = fs::create_dir_all(&bins_dir).and_then(|()| {
+            let mut file = NamedTempFile::new_in(&bins_dir)?;
+            io::copy(&mut fs::File::open(path)?, &mut file)?;
+            // Keep the permissions, e.g. the executable bit.
+            fs::set_permissions(file.path(), fs::metadata(path)?.permissions())?;
+            file.persist_noclobber(&bin_path)?;
+            Ok(())
+        });
+
+        match res {
+            Ok(()) => debug!("Added '{}' to cache as '{sha256}'", path.display()),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => (),
+            Err(err) => debug!("Failed to add '{}' to cache: {err}", path.display()),
+        }
+    }
+
+    /// Record that a binary of `len` bytes is installed by hard linking it
+    /// from the cache.
+    pub fn record_linked_bin(&self, len: u64) {
+        self.0.linked_bins.fetch_add(1, Relaxed);
+        self.0.reclaimed.fetch_add(len, Relaxed);
+    }
+
+    /// Add stats of this run to the stats file of the cache.
+    ///
+    /// Concurrent runs might race when updating it, so the stats are only
+    /// approximate.
+    ///
+    /// This is a blocking function.
+    pub fn save_stats(&self) -> io::Result<()> {
+        let inner = &self.0;
+        let hits = inner.hits.swap(0, Relaxed);
+        let misses = inner.misses.swap(0, Relaxed);
+        let linked_bins = inner.linked_bins.swap(0, Relaxed);
+        let reclaimed = inner.reclaimed.swap(0, Relaxed);
+
+        if hits == 0 && misses == 0 && linked_bins == 0 {
+            return Ok(());
+        }
+
+        let mut stats = self.read_stats_file()?;
+        stats.hits += hits;
+        stats.misses += misses;
+        stats.linked_bins += linked_bins;
+        stats.reclaimed += reclaimed;
+
+        fs::create_dir_all(&inner.dir)?;
+        let mut file = NamedTempFile::new_in(&inner.dir)?;
+        io::Write::write_all(&mut file, stats.format().as_bytes())?;
+        file.persist(inner.dir.join(STATS_FILE))?;
+
+        Ok(())
+    }
+
+    /// Return the size of the cache along with the stats saved by
+    /// [`DownloadCache::save_stats`].
+    ///
+    /// This is a blocking function.
+    pub fn load_stats(&self) -> io::Result<CacheStats> {
+        let mut stats = self.read_stats_file()?;
+        stats.size = self.entries()?.iter().map(|(_, len, _)| len).sum();
+
+        Ok(stats)
+    }
+
+    fn read_stats_file(&self) -> io::Result<CacheStats> {
+        match fs::read_to_string(self.0.dir.join(STATS_FILE)) {
+            Ok(s) => Ok(CacheStats::parse(&s)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(CacheStats::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Remove all files in the cache, including its stats.
+    ///
+    /// This is a blocking function.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.0.dir) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Return modified time, size and path of the cached files, including
+    /// the binaries.
+    ///
+    /// The modified time is updated whenever a file is used.
+    fn entries(&self) -> io::Result<Vec<(SystemTime, u64, PathBuf)>> {
+        fn collect(dir: &Path, entries: &mut Vec<(SystemTime, u64, PathBuf)>) -> io::Result<()> {
+            let read_dir = match fs::read_dir(dir) {
+                Ok(read_dir) => read_dir,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            for entry in read_dir {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+
+                let path = entry.path();
+                let is_sha256 = path.extension().map_or(false, |ext| ext == SHA256_EXT);
+
+                if metadata.is_file() && entry.file_name() != STATS_FILE && !is_sha256 {
+                    entries.push((metadata.modified()?, metadata.len(), path));
+                }
+            }
+
+            Ok(())
+        }
+
+        let mut entries = Vec::new();
+        collect(&self.0.dir, &mut entries)?;
+        collect(&self.0.dir.join(BINS_DIR), &mut entries)?;
+
+        Ok(entries)
+    }
+
+    /// Remove the least recently used files until the cache takes at most
+    /// `max_size` bytes.
+    ///
+    /// This is a blocking function.
+    pub fn evict(&self, max_size: u64) -> io::Result<()> {
+        let mut entries = self.entries()?;
+
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+
+        // Sort by the time of last use, oldest first
+        entries.sort_unstable();
+
+        for (_, len, path) in entries {
+            if size <= max_size {
+                break;
+            }
+
+            debug!("Removing '{}' from download cache", path.display());
+            fs::remove_file(&path)?;
+            // Only downloads have their SHA256 stored separately.
+            match fs::remove_file(sha256_path(&path)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => (),
+            }
+            size -= len;
+        }
+
+        Ok(())
+    }
+}
+
+/// Return path to the file storing the SHA256 of the download at `path`.
+fn sha256_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(SHA256_EXT);
+    path.into()
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Mark the file at `path` as used, failures are ignored since it only
+/// affects the order of eviction.
+fn touch(path: &Path) {
+    if let Err(err) = set_file_mtime(path, FileTime::now()) {
+        debug!(
+            "Failed to update modified time of '{}': {err}",
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bins_and_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path().join("cache"));
+
+        let bin = dir.path().join("bin");
+        fs::write(&bin, b"binary").unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(b"binary"));
+
+        assert_eq!(cache.get_bin(&sha256), None);
+        cache.put_bin(&bin, &sha256);
+        assert_eq!(cache.get_bin(&sha256), Some(cache.bin_path(&sha256)));
+
+        // The cache holds a copy, which is not modified along with the
+        // installed binary.
+        fs::write(&bin, b"modified").unwrap();
+        assert_eq!(cache.get_bin(&sha256), Some(cache.bin_path(&sha256)));
+
+        cache.0.hits.fetch_add(3, Relaxed);
+        cache.0.misses.fetch_add(1, Relaxed);
+        cache.record_linked_bin(6);
+        cache.save_stats().unwrap();
+        cache.record_linked_bin(6);
+        cache.save_stats().unwrap();
+
+        let stats = cache.load_stats().unwrap();
+        assert_eq!(
+            stats,
+            CacheStats {
+                size: 6,
+                hits: 3,
+                misses: 1,
+                linked_bins: 2,
+                reclaimed: 12,
+            }
+        );
+        assert_eq!(stats.hit_rate(), Some(0.75));
+
+        cache.evict(0).unwrap();
+        assert_eq!(cache.get_bin(&sha256), None);
+        assert!(bin.exists());
+    }
+
+    async fn put(
+        cache: &DownloadCache,
+        url: &Url,
+        validator: Option<&str>,
+        content: &'static str,
+    ) -> PathBuf {
+        let stream = futures_util::stream::iter([io::Result::Ok(Bytes::from(content))]);
+        cache.put(url, validator, stream).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_put_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path());
+
+        let url = Url::parse("https://example.com/a.tar.gz").unwrap();
+        assert_eq!(cache.get(&url, None), None);
+
+        let path = put(&cache, &url, None, "content").await;
+        assert_eq!(fs::read_to_string(&path).unwrap(), "content");
+        assert_eq!(cache.get(&url, None), Some(path.clone()));
+
+        // Corrupted files are removed instead of reused.
+        fs::write(&path, "corrupted").unwrap();
+        assert_eq!(cache.get(&url, None), None);
+        assert!(!path.exists());
+
+        put(&cache, &url, None, "content").await;
+        assert_eq!(cache.get(&url, None), Some(path));
+    }
+
+    #[tokio::test]
+    async fn test_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path());
+
+        let url = Url::parse("https://example.com/a.tar.gz").unwrap();
+
+        let old = put(&cache, &url, Some("\"v1\""), "old").await;
+        assert_eq!(cache.get(&url, Some("\"v1\"")), Some(old.clone()));
+
+        // The server now serves changed content at the same url, with a
+        // different `ETag`.
+        assert_eq!(cache.get(&url, Some("\"v2\"")), None);
+
+        let new = put(&cache, &url, Some("\"v2\""), "new").await;
+        assert_ne!(new, old);
+        assert_eq!(fs::read_to_string(&new).unwrap(), "new");
+        assert_eq!(cache.get(&url, Some("\"v2\"")), Some(new));
+    }
+
+    #[tokio::test]
+    async fn test_evict_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path());
+
+        let urls: Vec<_> = ["a", "b", "c"]
+            .into_iter()
+            .map(|name| Url::parse(&format!("https://example.com/{name}")).unwrap())
+            .collect();
+
+        for (i, url) in urls.iter().enumerate() {
+            let path = put(&cache, url, None, "1234").await;
+            // Added in order, a first.
+            set_file_mtime(&path, FileTime::from_unix_time(1_000_000 + i as i64, 0)).unwrap();
+        }
+
+        // Using a makes b the least recently used.
+        assert!(cache.get(&urls[0], None).is_some());
+
+        cache.evict(8).unwrap();
+        assert!(cache.get(&urls[0], None).is_some());
+        assert_eq!(cache.get(&urls[1], None), None);
+        assert!(!sha256_path(&cache.entry_path(&urls[1], None)).exists());
+        assert!(cache.get(&urls[2], None).is_some());
+
+        assert_eq!(cache.load_stats().unwrap().size, 8);
+
+        cache.clear().unwrap();
+        assert!(!dir.path().exists());
+        assert_eq!(cache.load_stats().unwrap(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_modified_bin() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path().join("cache"));
+
+        let bin = dir.path().join("bin");
+        fs::write(&bin, b"binary").unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(b"binary"));
+
+        cache.put_bin(&bin, &sha256);
+
+        // E.g. modified via a binary installed by hard linking it.
+        fs::write(cache.bin_path(&sha256), b"modified").unwrap();
+
+        assert_eq!(cache.get_bin(&sha256), None);
+        assert!(!cache.bin_path(&sha256).exists());
+    }
+}
@@ -4,6 +4,8 @@ use std::{
     path::Path,
 };
 
+use compact_str::CompactString;
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub enum ExtractedFilesEntry {
@@ -22,11 +24,23 @@ impl ExtractedFilesEntry {
 }
 
 #[derive(Debug)]
-pub struct ExtractedFiles(pub(super) HashMap<Box<Path>, ExtractedFilesEntry>);
+pub struct ExtractedFiles {
+    pub(super) entries: HashMap<Box<Path>, ExtractedFilesEntry>,
+    /// SHA256 of the downloaded file, hex-encoded.
+    pub(super) sha256: Option<CompactString>,
+}
 
 impl ExtractedFiles {
     pub(super) fn new() -> Self {
-        Self(Default::default())
+        Self {
+            entries: Default::default(),
+            sha256: None,
+        }
+    }
+
+    /// Return SHA256 checksum of the file downloaded, hex-encoded.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
     }
 
     /// * `path` - must be canonical and must not be empty
@@ -34,7 +48,7 @@ impl ExtractedFiles {
     /// NOTE that if the entry for the `path` is previously set to a dir,
     /// it would be replaced with a file.
     pub(super) fn add_file(&mut self, path: &Path) {
-        self.0.insert(path.into(), ExtractedFilesEntry::File);
+        self.entries.insert(path.into(), ExtractedFilesEntry::File);
         self.add_dir_if_has_parent(path);
     }
 
@@ -64,7 +78,7 @@ impl ExtractedFiles {
     /// it would be replaced with a Dir entry containing `file_name` if it
     /// is `Some(..)`, or an empty Dir entry.
     fn add_dir_inner(&mut self, path: &Path, file_name: Option<&OsStr>) {
-        match self.0.entry(path.into()) {
+        match self.entries.entry(path.into()) {
             HashMapEntry::Vacant(entry) => {
                 entry.insert(ExtractedFilesEntry::new_dir(file_name));
             }
@@ -84,7 +98,7 @@ impl ExtractedFiles {
     ///            return `None`.
     ///            But could be set to "." for top-level.
     pub fn get_entry(&self, path: &Path) -> Option<&ExtractedFilesEntry> {
-        self.0.get(path)
+        self.entries.get(path)
     }
 
     /// * `path` - must be a relative path without `.`, `..`, `/`, `prefix:/`
@@ -105,4 +119,11 @@ impl ExtractedFiles {
     pub fn has_file(&self, path: &Path) -> bool {
         matches!(self.get_entry(path), Some(ExtractedFilesEntry::File))
     }
+
+    /// Return paths of all files extracted, in arbitrary order.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().filter_map(|(path, entry)| {
+            matches!(entry, ExtractedFilesEntry::File).then_some(&**path)
+        })
+    }
 }
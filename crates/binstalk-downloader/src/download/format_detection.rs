@@ -0,0 +1,156 @@
+use std::io::Read;
+
+use bytes::{Buf, Bytes};
+
+use super::{extracter::create_bin_decoder, CompressedBinFmt, PkgFmt, TarBasedFmt};
+
+/// Number of bytes to buffer before detecting the format.
+///
+/// It has to be large enough to decompress the header of the first tar
+/// entry.
+pub(super) const PREFIX_LEN: usize = 64 * 1024;
+
+/// Detect the format of a package from the first bytes of it.
+///
+/// `fallback` is used to decide whether a compressed file is a tarball or
+/// a single binary if it cannot be told from `prefix`.
+///
+/// Return `None` if the format is unknown.
+pub(super) fn detect_pkg_fmt(prefix: &Bytes, fallback: Option<PkgFmt>) -> Option<PkgFmt> {
+    let compressed_fmt = if prefix.starts_with(b"\x1f\x8b") {
+        CompressedBinFmt::Gz
+    } else if prefix.starts_with(b"\xfd7zXZ\x00") {
+        CompressedBinFmt::Xz
+    } else if prefix.starts_with(b"\x28\xb5\x2f\xfd") {
+        CompressedBinFmt::Zstd
+    } else if prefix.starts_with(b"BZh") {
+        CompressedBinFmt::Bz2
+    } else if prefix.starts_with(b"PK\x03\x04") {
+        return Some(PkgFmt::Zip);
+    } else if is_tar(prefix.as_ref()) {
+        return Some(PkgFmt::Tar);
+    } else if is_executable(prefix) {
+        return Some(PkgFmt::Bin);
+    } else {
+        return None;
+    };
+
+    let tar_fmt = match compressed_fmt {
+        CompressedBinFmt::Bz2 => TarBasedFmt::Tbz2,
+        CompressedBinFmt::Gz => TarBasedFmt::Tgz,
+        CompressedBinFmt::Xz => TarBasedFmt::Txz,
+        CompressedBinFmt::Zstd => TarBasedFmt::Tzstd,
+    };
+
+    let is_tar = create_bin_decoder(prefix.clone().reader(), compressed_fmt)
+        .ok()
+        .and_then(is_decompressed_tar);
+
+    Some(match is_tar {
+        Some(true) => tar_fmt.into(),
+        Some(false) => compressed_fmt.into(),
+        // The decompressed prefix is too short to tell, e.g. bzip2 has to
+        // read the entire block before producing any output.
+        None => match fallback {
+            Some(fallback) if fallback == compressed_fmt.into() => fallback,
+            _ => tar_fmt.into(),
+        },
+    })
+}
+
+fn is_tar(block: &[u8]) -> bool {
+    block.get(257..262) == Some(b"ustar")
+}
+
+/// Return `None` if not enough data can be decompressed.
+fn is_decompressed_tar(mut decoder: impl Read) -> Option<bool> {
+    let mut block = [0; 512];
+    let mut len = 0;
+
+    // The prefix is truncated, so errors are expected once it is
+    // exhausted.
+    while len < block.len() {
+        match decoder.read(&mut block[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => len += n,
+        }
+    }
+
+    if len >= 262 {
+        Some(is_tar(&block))
+    } else if len > 0 && is_executable(&block[..len]) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn is_executable(prefix: &[u8]) -> bool {
+    const MAGICS: &[&[u8]] = &[
+        // ELF
+        b"\x7fELF",
+        // PE
+        b"MZ",
+        // Mach-O, 32-bit and 64-bit in both endianness
+        b"\xfe\xed\xfa\xce",
+        b"\xfe\xed\xfa\xcf",
+        b"\xce\xfa\xed\xfe",
+        b"\xcf\xfa\xed\xfe",
+        // Mach-O universal binary
+        b"\xca\xfe\xba\xbe",
+    ];
+
+    MAGICS.iter().any(|magic| prefix.starts_with(magic))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    fn tar_block() -> Vec<u8> {
+        let mut block = vec![0; 512];
+        block[257..262].copy_from_slice(b"ustar");
+        block
+    }
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap().into()
+    }
+
+    #[test]
+    fn test_detect_pkg_fmt() {
+        let detect = |prefix: &[u8]| detect_pkg_fmt(&Bytes::copy_from_slice(prefix), None);
+
+        assert_eq!(detect(b"\x7fELF\x02\x01\x01"), Some(PkgFmt::Bin));
+        assert_eq!(detect(b"MZ\x90\x00"), Some(PkgFmt::Bin));
+        assert_eq!(detect(b"\xcf\xfa\xed\xfe\x07"), Some(PkgFmt::Bin));
+        assert_eq!(detect(b"PK\x03\x04\x14\x00"), Some(PkgFmt::Zip));
+        assert_eq!(detect(&tar_block()), Some(PkgFmt::Tar));
+        assert_eq!(detect(b"#!/bin/sh\n"), None);
+    }
+
+    #[test]
+    fn test_detect_compressed_pkg_fmt() {
+        assert_eq!(detect_pkg_fmt(&gzip(&tar_block()), None), Some(PkgFmt::Tgz));
+        assert_eq!(
+            detect_pkg_fmt(&gzip(b"\x7fELF\x02\x01\x01"), None),
+            Some(PkgFmt::Gz)
+        );
+
+        // Too short to tell, use fallback if it matches the compression
+        assert_eq!(
+            detect_pkg_fmt(&gzip(b"#!"), Some(PkgFmt::Gz)),
+            Some(PkgFmt::Gz)
+        );
+        assert_eq!(
+            detect_pkg_fmt(&gzip(b"#!"), Some(PkgFmt::Zip)),
+            Some(PkgFmt::Tgz)
+        );
+    }
+}
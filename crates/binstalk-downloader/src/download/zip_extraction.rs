@@ -1,6 +1,8 @@
 use std::{
     borrow::Cow,
-    io::Write,
+    collections::HashSet,
+    fs,
+    io::{self, Write},
     path::{Component, Path, PathBuf},
 };
 
@@ -17,10 +19,16 @@ use tokio::{
     sync::mpsc,
 };
 use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt};
+use tracing::debug;
 
 use super::{DownloadError, ExtractedFiles};
 use crate::utils::asyncify;
 
+/// File type bits of unix mode.
+const S_IFMT: u16 = 0o170000;
+/// File type of symlinks.
+const S_IFLNK: u16 = 0o120000;
+
 #[derive(Debug, ThisError)]
 enum ZipErrorInner {
     #[error(transparent)]
@@ -28,6 +36,9 @@ enum ZipErrorInner {
 
     #[error("Invalid file path: {0}")]
     InvalidFilePath(Box<str>),
+
+    #[error("Invalid symlink target: {0}")]
+    InvalidLinkTarget(Box<str>),
 }
 
 #[derive(Debug, ThisError)]
@@ -40,11 +51,14 @@ impl ZipError {
     }
 }
 
+/// * `link_paths` - paths passed through by the targets of the symlinks
+///   extracted so far, which must not be replaced by symlinks.
 pub(super) async fn extract_zip_entry<R>(
     zip_reader: &mut ZipEntryReader<'_, Take<Compat<R>>, WithEntry<'_>>,
     path: &Path,
     buf: &mut BytesMut,
     extracted_files: &mut ExtractedFiles,
+    link_paths: &mut HashSet<PathBuf>,
 ) -> Result<(), DownloadError>
 where
     R: AsyncRead + Unpin + Send + Sync,
@@ -56,6 +70,14 @@ where
     // Calculates the outpath
     let outpath = path.join(&filename);
 
+    // Mode bits stored in the external attributes, only present if the
+    // archive is created on unix.
+    let mode = zip_reader.entry().unix_permissions();
+
+    if !is_dir && mode.map(|mode| mode & S_IFMT == S_IFLNK).unwrap_or(false) {
+        return extract_symlink(zip_reader, path, &filename, extracted_files, link_paths).await;
+    }
+
     // Get permissions
     #[cfg_attr(not(unix), allow(unused_mut))]
     let mut perms = None;
@@ -64,23 +86,29 @@ where
     {
         use std::{fs::Permissions, os::unix::fs::PermissionsExt};
 
-        if let Some(mode) = zip_reader.entry().unix_permissions() {
+        if let Some(mode) = mode {
             // If it is a dir, then it needs to be at least rwx for the current
             // user so that we can create new files, search for existing files
             // and list its contents.
             //
             // If it is a file, then it needs to be at least readable for the
             // current user.
-            let mode: u16 = mode | if is_dir { 0o700 } else { 0o400 };
+            //
+            // The file type bits are masked out since they are not
+            // permissions.
+            let mode: u16 = (mode & 0o7777) | if is_dir { 0o700 } else { 0o400 };
             perms = Some(Permissions::from_mode(mode as u32));
         }
     }
 
+    let root = path.to_owned();
+
     if is_dir {
         extracted_files.add_dir(&filename);
 
         // This entry is a dir.
         asyncify(move || {
+            check_not_through_symlink(&root, &filename)?;
             std::fs::create_dir_all(&outpath)?;
             if let Some(perms) = perms {
                 std::fs::set_permissions(&outpath, perms)?;
@@ -98,6 +126,7 @@ where
         // This entry is a file.
 
         let write_task = asyncify(move || {
+            check_not_through_symlink(&root, &filename)?;
             if let Some(p) = outpath.parent() {
                 std::fs::create_dir_all(p)?;
             }
@@ -143,6 +172,96 @@ where
     Ok(())
 }
 
+/// Extract a symlink entry, whose content is the link target.
+///
+/// On platforms without (unprivileged) symlinks, the target is copied if it
+/// has already been extracted, otherwise the link target is written to a
+/// regular file, like what unzip does on filesystems without symlinks.
+async fn extract_symlink<R>(
+    zip_reader: &mut ZipEntryReader<'_, Take<Compat<R>>, WithEntry<'_>>,
+    path: &Path,
+    filename: &Path,
+    extracted_files: &mut ExtractedFiles,
+    link_paths: &mut HashSet<PathBuf>,
+) -> Result<(), DownloadError>
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    let mut target = Vec::new();
+    // Also checks crc32 checksum.
+    zip_reader
+        .read_to_end_checked(&mut target)
+        .await
+        .map_err(ZipError::from_inner)?;
+
+    let target = String::from_utf8_lossy(&target).into_owned();
+
+    // An earlier link would get out of the extraction dir through this one,
+    // e.g. `c -> a/b/../../x` followed by `a/b -> ..`.
+    if link_paths.contains(filename) {
+        return Err(invalid_file_path(filename));
+    }
+
+    debug!("Creating symlink `{}` -> `{target}`", filename.display());
+
+    extracted_files.add_file(filename);
+
+    let root = path.to_owned();
+    let link = filename.to_owned();
+
+    let traversed =
+        asyncify(move || create_symlink(&root, &link, &target).map_err(io::Error::from)).await?;
+
+    link_paths.extend(traversed);
+
+    Ok(())
+}
+
+/// Create symlink `link` to `target` in the extraction dir `root`, return
+/// the paths passed through by `target`.
+///
+/// This is a blocking function.
+fn create_symlink(root: &Path, link: &Path, target: &str) -> Result<Vec<PathBuf>, DownloadError> {
+    #[cfg_attr(unix, allow(unused_variables))]
+    let resolved = root.join(check_link_target(link, target)?);
+    let outpath = root.join(link);
+    let parent = link.parent().unwrap_or_else(|| Path::new(""));
+
+    check_not_through_symlink(root, parent)?;
+
+    // A chain of links could get out of the extraction dir, e.g.
+    // `a/b -> ..` followed by `c -> a/b/..`.
+    let traversed =
+        walk_path(root, &parent.join(target))?.ok_or_else(|| invalid_link_target(target))?;
+
+    if let Some(p) = outpath.parent() {
+        std::fs::create_dir_all(p)?;
+    }
+
+    // Replace any existing entry, like a regular file entry would be.
+    if let Err(err) = std::fs::remove_file(&outpath) {
+        if err.kind() != io::ErrorKind::NotFound {
+            return Err(err.into());
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, &outpath)?;
+    }
+
+    #[cfg(not(unix))]
+    {
+        if resolved.is_file() {
+            std::fs::copy(&resolved, &outpath)?;
+        } else {
+            std::fs::write(&outpath, target)?;
+        }
+    }
+
+    Ok(traversed)
+}
+
 async fn copy_file_to_mpsc<R: AsyncRead>(
     mut entry_reader: R,
     tx: mpsc::Sender<Bytes>,
@@ -210,24 +329,154 @@ fn check_filename_and_normalize(filename: &ZipString) -> Result<(PathBuf, bool),
         return bail(filename);
     }
 
-    let mut path = PathBuf::new();
+    match normalize(Path::new(&*filename)) {
+        Some(path) => Ok((path, filename.ends_with('/'))),
+        None => bail(filename),
+    }
+}
+
+/// Ensure the symlink `target` of entry `filename` does not point outside
+/// the extraction dir, return the normalized path it points to.
+fn check_link_target(filename: &Path, target: &str) -> Result<PathBuf, DownloadError> {
+    let parent = filename.parent().unwrap_or_else(|| Path::new(""));
+
+    if target.contains('\0') {
+        return Err(invalid_link_target(target));
+    }
+
+    normalize(&parent.join(target)).ok_or_else(|| invalid_link_target(target))
+}
+
+/// Walk `path`, relative to the extraction dir `root`, and return the paths
+/// it passes through, or `None` if any of them is a symlink extracted
+/// already.
+///
+/// This is a blocking function.
+fn walk_path(root: &Path, path: &Path) -> io::Result<Option<Vec<PathBuf>>> {
+    let mut current = PathBuf::new();
+    let mut traversed = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => {
+                current.push(c);
+
+                match fs::symlink_metadata(root.join(&current)) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => return Ok(None),
+                    Ok(_) => (),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(err),
+                }
+
+                traversed.push(current.clone());
+            }
+            Component::ParentDir => {
+                current.pop();
+            }
+            // `path` is checked by `normalize` before, so it is relative.
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => (),
+        }
+    }
+
+    Ok(Some(traversed))
+}
+
+/// Ensure `path`, relative to the extraction dir `root`, does not pass
+/// through a symlink, so that an entry is not written out of the extraction
+/// dir through a linked dir.
+///
+/// This is a blocking function.
+fn check_not_through_symlink(root: &Path, path: &Path) -> io::Result<()> {
+    match walk_path(root, path)? {
+        Some(_) => Ok(()),
+        None => Err(invalid_file_path(path).into()),
+    }
+}
+
+fn invalid_file_path(path: &Path) -> DownloadError {
+    ZipError(ZipErrorInner::InvalidFilePath(
+        path.to_string_lossy().into_owned().into(),
+    ))
+    .into()
+}
+
+fn invalid_link_target(target: &str) -> DownloadError {
+    ZipError(ZipErrorInner::InvalidLinkTarget(target.into())).into()
+}
+
+/// Normalize `path`, return `None` if it is absolute or resolves to a path
+/// outside the current directory.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
 
     // The following loop is adapted from
     // `normalize_path::NormalizePath::normalize`.
-    for component in Path::new(&*filename).components() {
+    for component in path.components() {
         match component {
-            Component::Prefix(_) | Component::RootDir => return bail(filename),
+            Component::Prefix(_) | Component::RootDir => return None,
             Component::CurDir => (),
             Component::ParentDir => {
-                if !path.pop() {
+                if !normalized.pop() {
                     // `PathBuf::pop` returns false if there is no parent.
                     // which means the path is invalid.
-                    return bail(filename);
+                    return None;
                 }
             }
-            Component::Normal(c) => path.push(c),
+            Component::Normal(c) => normalized.push(c),
         }
     }
 
-    Ok((path, filename.ends_with('/')))
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_extraction_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        fs::create_dir_all(root.join("libexec")).unwrap();
+        fs::write(root.join("libexec/foo"), "foo").unwrap();
+        create_symlink(root, Path::new("bin/cargo-foo"), "../libexec/foo").unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("bin/cargo-foo")).unwrap(),
+            "foo"
+        );
+
+        // A chain of links getting out of the extraction dir.
+        fs::create_dir(root.join("a")).unwrap();
+        create_symlink(root, Path::new("a/b"), "..").unwrap();
+        create_symlink(root, Path::new("c"), "a/b/..").unwrap_err();
+        create_symlink(root, Path::new("c"), "a/b").unwrap_err();
+        assert!(!root.join("c").exists());
+
+        // `e/f` must not be replaced by a link afterwards.
+        let traversed = create_symlink(root, Path::new("d"), "e/f/../../evil").unwrap();
+        assert!(traversed.contains(&PathBuf::from("e/f")));
+
+        // Writing through a linked dir.
+        fs::create_dir(root.join("g")).unwrap();
+        create_symlink(root, Path::new("h"), "g").unwrap();
+        check_not_through_symlink(root, Path::new("h/evil")).unwrap_err();
+        create_symlink(root, Path::new("h/evil"), "../g").unwrap_err();
+        check_not_through_symlink(root, Path::new("g/file")).unwrap();
+    }
+
+    #[test]
+    fn test_check_link_target() {
+        let check =
+            |filename: &str, target: &str| check_link_target(Path::new(filename), target).ok();
+
+        assert_eq!(
+            check("bin/cargo-foo", "../libexec/foo"),
+            Some("libexec/foo".into())
+        );
+        assert_eq!(check("cargo-foo", "foo"), Some("foo".into()));
+        assert_eq!(check("bin/cargo-foo", "../../foo"), None);
+        assert_eq!(check("cargo-foo", "/usr/bin/foo"), None);
+    }
 }
@@ -9,7 +9,10 @@ use std::{
 };
 
 use compact_str::{CompactString, ToCompactString};
-use reqwest::{header::HeaderMap, StatusCode};
+use reqwest::{
+    header::{HeaderMap, ETAG},
+    StatusCode,
+};
 use serde::{de::Deserializer, Deserialize, Serialize};
 use serde_json::to_string as to_json_string;
 use thiserror::Error as ThisError;
@@ -138,13 +141,15 @@ async fn fetch_release_artifacts_restful_api(
     GhRelease { owner, repo, tag }: &GhRelease,
     auth_token: Option<&str>,
 ) -> Result<FetchReleaseRet, GhApiError> {
+    let url = Url::parse(&format!(
+        "https://api.github.com/repos/{owner}/{repo}/releases/tags/{tag}",
+        owner = percent_encode_http_url_path(owner),
+        repo = percent_encode_http_url_path(repo),
+        tag = percent_encode_http_url_path(tag),
+    ))?;
+
     let mut request_builder = client
-        .get(Url::parse(&format!(
-            "https://api.github.com/repos/{owner}/{repo}/releases/tags/{tag}",
-            owner = percent_encode_http_url_path(owner),
-            repo = percent_encode_http_url_path(repo),
-            tag = percent_encode_http_url_path(tag),
-        ))?)
+        .get(url.clone())
         .header("Accept", "application/vnd.github+json")
         .header("X-GitHub-Api-Version", "2022-11-28");
 
@@ -152,13 +157,44 @@ async fn fetch_release_artifacts_restful_api(
         request_builder = request_builder.bearer_auth(&auth_token);
     }
 
+    // Conditional requests which GitHub responds with 304 Not Modified
+    // do not count against the rate limit.
+    let cache = client.download_cache();
+    let cached = match cache {
+        Some(cache) => cache.get_etag_response(&url).await,
+        None => None,
+    };
+    if let Some((etag, _body)) = &cached {
+        request_builder = request_builder.header("If-None-Match", etag);
+    }
+
     let response = request_builder.send(false).await?;
 
+    if let (remote::StatusCode::NOT_MODIFIED, Some((_etag, body))) = (response.status(), cached) {
+        debug!("Using cached response of '{url}'");
+        return Ok(FetchReleaseRet::Artifacts(
+            serde_json::from_slice(&body).map_err(remote::Error::from)?,
+        ));
+    }
+
     if let Some(ret) = check_for_status(response.status(), response.headers()) {
-        Ok(ret)
-    } else {
-        Ok(FetchReleaseRet::Artifacts(response.json().await?))
+        return Ok(ret);
     }
+
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(CompactString::from);
+    let body = response.error_for_status()?.bytes().await?;
+
+    if let (Some(cache), Some(etag)) = (cache, etag) {
+        cache.put_etag_response(&url, &etag, &body).await;
+    }
+
+    Ok(FetchReleaseRet::Artifacts(
+        serde_json::from_slice(&body).map_err(remote::Error::from)?,
+    ))
 }
 
 #[derive(Deserialize)]
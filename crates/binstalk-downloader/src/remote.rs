@@ -6,6 +6,7 @@ use std::{
 };
 
 use bytes::Bytes;
+use compact_str::CompactString;
 use futures_util::Stream;
 use httpdate::parse_http_date;
 use reqwest::{
@@ -13,17 +14,34 @@ use reqwest::{
     Request,
 };
 use thiserror::Error as ThisError;
+use tokio::sync::OwnedSemaphorePermit;
 use tracing::{debug, info, instrument};
 
+use crate::download::DownloadCache;
+
 pub use reqwest::{header, Error as ReqwestError, Method, StatusCode};
 pub use url::Url;
 
 mod delay_request;
 use delay_request::DelayRequest;
 
+mod bandwidth_limit;
+use bandwidth_limit::{BandwidthLimit, BandwidthLimitedStream};
+
 mod certificate;
 pub use certificate::Certificate;
 
+mod client_builder;
+pub use client_builder::ClientBuilder;
+
+mod connection_limit;
+use connection_limit::ConnectionLimit;
+
+mod github_mirror;
+
+mod read_timeout;
+use read_timeout::ReadTimeoutStream;
+
 mod request_builder;
 pub use request_builder::{Body, RequestBuilder, Response};
 
@@ -42,6 +60,16 @@ const MAX_RETRY_DURATION: Duration = Duration::from_secs(120);
 const MAX_RETRY_COUNT: u8 = 3;
 const DEFAULT_RETRY_DURATION_FOR_RATE_LIMIT: Duration = Duration::from_millis(200);
 const RETRY_DURATION_FOR_TIMEOUT: Duration = Duration::from_millis(200);
+/// Default timeout for connecting to the server, so that a stalled server
+/// would not hang forever.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default timeout for receiving the response headers and each chunk of
+/// the response body, so that a server that accepted the connection and
+/// then stalled would not hang forever.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// Interval of keep-alive probes, so that pooled connections are not
+/// silently dropped by middleboxes between requests.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
 #[allow(dead_code)]
 const DEFAULT_MIN_TLS: TLSVersion = TLSVersion::TLS_1_2;
 
@@ -54,6 +82,9 @@ pub enum Error {
     #[error(transparent)]
     Http(Box<HttpError>),
 
+    #[error("No data received from {0} within the read timeout")]
+    ReadTimeout(Box<Url>),
+
     #[cfg(feature = "json")]
     #[error("Failed to parse http response body as Json: {0}")]
     Json(#[from] JsonError),
@@ -73,18 +104,36 @@ impl HttpError {
     pub fn is_status(&self) -> bool {
         self.err.is_status()
     }
+
+    /// Returns the status code if the error is from
+    /// [`Response::error_for_status`].
+    pub fn status(&self) -> Option<StatusCode> {
+        self.err.status()
+    }
 }
 
 #[derive(Debug)]
 struct Inner {
     client: reqwest::Client,
     service: DelayRequest,
+    connection_limit: Option<ConnectionLimit>,
+    read_timeout: Duration,
 }
 
+/// HTTP client shared by all requests.
+///
+/// Clones share the same connection pool, so create one `Client` and clone
+/// it, to reuse connections (HTTP/2 is used if the server supports it) and
+/// avoid redundant TLS handshakes. Responses compressed with gzip, brotli
+/// or deflate are decompressed transparently.
 #[derive(Clone, Debug)]
-pub struct Client(Arc<Inner>);
+pub struct Client {
+    inner: Arc<Inner>,
+    download_cache: Option<DownloadCache>,
+    bandwidth_limit: Option<Arc<BandwidthLimit>>,
+    github_mirror: Option<Arc<Url>>,
+}
 
-#[cfg_attr(not(feature = "__tls"), allow(unused_variables, unused_mut))]
 impl Client {
     /// * `per_millis` - The duration (in millisecond) for which at most
     ///   `num_request` can be sent, itcould be increased if rate-limit
@@ -93,6 +142,9 @@ impl Client {
     ///   each `per` duration.
     ///
     /// The Client created would use at least tls 1.2
+    ///
+    /// Use [`Client::builder`] to configure timeouts and the maximum number
+    /// of connections per host.
     pub fn new(
         user_agent: impl AsRef<str>,
         min_tls: Option<TLSVersion>,
@@ -100,68 +152,79 @@ impl Client {
         num_request: NonZeroU64,
         certificates: impl IntoIterator<Item = Certificate>,
     ) -> Result<Self, Error> {
-        fn inner(
-            user_agent: &str,
-            min_tls: Option<TLSVersion>,
-            per_millis: NonZeroU16,
-            num_request: NonZeroU64,
-            certificates: &mut dyn Iterator<Item = Certificate>,
-        ) -> Result<Client, Error> {
-            let mut builder = reqwest::ClientBuilder::new()
-                .user_agent(user_agent)
-                .https_only(true)
-                .tcp_nodelay(false);
-
-            #[cfg(feature = "hickory-dns")]
-            {
-                builder = builder.dns_resolver(Arc::new(TrustDnsResolver::default()));
-            }
+        let builder = Self::builder(user_agent)
+            .rate_limit(per_millis, num_request)
+            .add_root_certificates(certificates);
 
-            #[cfg(feature = "__tls")]
-            {
-                let tls_ver = min_tls
-                    .map(|tls| tls.max(DEFAULT_MIN_TLS))
-                    .unwrap_or(DEFAULT_MIN_TLS);
+        match min_tls {
+            Some(min_tls) => builder.min_tls_version(min_tls),
+            None => builder,
+        }
+        .build()
+    }
 
-                builder = builder.min_tls_version(tls_ver.into());
+    /// Create a [`ClientBuilder`] with the default settings.
+    pub fn builder(user_agent: impl AsRef<str>) -> ClientBuilder {
+        ClientBuilder::new(user_agent.as_ref())
+    }
 
-                for certificate in certificates {
-                    builder = builder.add_root_certificate(certificate.0);
-                }
-            }
+    /// Cache files downloaded via [`crate::download::Download`] in
+    /// `download_cache` and reuse them.
+    pub fn with_download_cache(mut self, download_cache: DownloadCache) -> Self {
+        self.download_cache = Some(download_cache);
+        self
+    }
 
-            let client = builder.build()?;
+    /// Limit the total bandwidth of all downloads via [`Client::get_stream`]
+    /// to `bytes_per_sec`.
+    pub fn with_bandwidth_limit(mut self, bytes_per_sec: NonZeroU64) -> Self {
+        self.bandwidth_limit = Some(Arc::new(BandwidthLimit::new(bytes_per_sec)));
+        self
+    }
+
+    /// Download GitHub release artifacts from `mirror` instead of
+    /// `https://github.com`.
+    ///
+    /// Urls like `https://github.com/{org}/{repo}/releases/download/{tag}/{file}`
+    /// are rewritten to `{mirror}/{org}/{repo}/releases/download/{tag}/{file}`.
+    pub fn with_github_mirror(mut self, mirror: Url) -> Self {
+        self.github_mirror = Some(Arc::new(mirror));
+        self
+    }
 
-            Ok(Client(Arc::new(Inner {
-                client: client.clone(),
-                service: DelayRequest::new(
-                    num_request,
-                    Duration::from_millis(per_millis.get() as u64),
-                    client,
-                ),
-            })))
+    fn rewrite_url(&self, url: Url) -> Url {
+        match &self.github_mirror {
+            Some(mirror) => github_mirror::rewrite_url(mirror, url),
+            None => url,
         }
+    }
+
+    /// Return the download cache, if any.
+    pub fn download_cache(&self) -> Option<&DownloadCache> {
+        self.download_cache.as_ref()
+    }
 
-        inner(
-            user_agent.as_ref(),
-            min_tls,
-            per_millis,
-            num_request,
-            &mut certificates.into_iter(),
-        )
+    /// Wait until a request to `url` is allowed by the maximum number of
+    /// connections per host, the permit returned must be kept until the
+    /// response is fully received.
+    async fn acquire_connection(&self, url: &Url) -> Option<OwnedSemaphorePermit> {
+        match &self.inner.connection_limit {
+            Some(limit) => Some(limit.acquire(url).await),
+            None => None,
+        }
     }
 
     /// Return inner reqwest client.
     pub fn get_inner(&self) -> &reqwest::Client {
-        &self.0.client
+        &self.inner.client
     }
 
     /// Return `Err(_)` for fatal error tht cannot be retried.
     ///
     /// Return `Ok(ControlFlow::Continue(res))` for retryable error, `res`
-    /// will contain the previous `Result<Response, ReqwestError>`.
-    /// A retryable error could be a `ReqwestError` or `Response` with
-    /// unsuccessful status code.
+    /// will contain the previous `Result<Response, Error>`.
+    /// A retryable error could be a `ReqwestError`, a read timeout or
+    /// `Response` with unsuccessful status code.
     ///
     /// Return `Ok(ControlFlow::Break(response))` when succeeds and no need
     /// to retry.
@@ -170,19 +233,38 @@ impl Client {
         &self,
         request: Request,
         url: &Url,
-    ) -> Result<ControlFlow<reqwest::Response, Result<reqwest::Response, ReqwestError>>, ReqwestError>
-    {
-        let response = match self.0.service.call(request).await {
-            Err(err) if err.is_timeout() || err.is_connect() => {
+    ) -> Result<ControlFlow<reqwest::Response, Result<reqwest::Response, Error>>, Error> {
+        let method = request.method().clone();
+        let http_error = |err| {
+            Error::Http(Box::new(HttpError {
+                method,
+                url: url.clone(),
+                err,
+            }))
+        };
+
+        let response = match self.inner.service.call(request).await {
+            Err(_elapsed) => {
+                let duration = RETRY_DURATION_FOR_TIMEOUT;
+
+                info!("No response received within the read timeout. Delay future request by {duration:#?}");
+
+                self.inner.service.add_urls_to_delay(&[url], duration);
+
+                return Ok(ControlFlow::Continue(Err(Error::ReadTimeout(Box::new(
+                    url.clone(),
+                )))));
+            }
+            Ok(Err(err)) if err.is_timeout() || err.is_connect() => {
                 let duration = RETRY_DURATION_FOR_TIMEOUT;
 
                 info!("Received timeout error from reqwest. Delay future request by {duration:#?}");
 
-                self.0.service.add_urls_to_delay(&[url], duration);
+                self.inner.service.add_urls_to_delay(&[url], duration);
 
-                return Ok(ControlFlow::Continue(Err(err)));
+                return Ok(ControlFlow::Continue(Err(http_error(err))));
             }
-            res => res?,
+            Ok(res) => res.map_err(http_error)?,
         };
 
         let status = response.status();
@@ -190,7 +272,7 @@ impl Client {
         let add_delay_and_continue = |response: reqwest::Response, duration| {
             info!("Received status code {status}, will wait for {duration:#?} and retry");
 
-            self.0
+            self.inner
                 .service
                 .add_urls_to_delay(&[url, response.url()], duration);
 
@@ -217,10 +299,7 @@ impl Client {
     }
 
     /// * `request` - `Request::try_clone` must always return `Some`.
-    async fn send_request_inner(
-        &self,
-        request: &Request,
-    ) -> Result<reqwest::Response, ReqwestError> {
+    async fn send_request_inner(&self, request: &Request) -> Result<reqwest::Response, Error> {
         let mut count = 0;
         let max_retry_count = NonZeroU8::new(MAX_RETRY_COUNT).unwrap();
 
@@ -250,22 +329,19 @@ impl Client {
     ) -> Result<reqwest::Response, Error> {
         debug!("Downloading from: '{}'", request.url());
 
-        self.send_request_inner(&request)
-            .await
-            .and_then(|response| {
-                if error_for_status {
-                    response.error_for_status()
-                } else {
-                    Ok(response)
-                }
-            })
-            .map_err(|err| {
+        let response = self.send_request_inner(&request).await?;
+
+        if error_for_status {
+            response.error_for_status().map_err(|err| {
                 Error::Http(Box::new(HttpError {
                     method: request.method().clone(),
                     url: request.url().clone(),
                     err,
                 }))
             })
+        } else {
+            Ok(response)
+        }
     }
 
     async fn head_or_fallback_to_get(
@@ -273,6 +349,10 @@ impl Client {
         url: Url,
         error_for_status: bool,
     ) -> Result<reqwest::Response, Error> {
+        let url = self.rewrite_url(url);
+
+        let _permit = self.acquire_connection(&url).await;
+
         let res = self
             .send_request(Request::new(Method::HEAD, url.clone()), error_for_status)
             .await;
@@ -312,6 +392,22 @@ impl Client {
         Ok(self.get(url).send(false).await?.status().is_success())
     }
 
+    /// Return the `ETag` of `url`, or its `Last-Modified` if there is no
+    /// `ETag`, using `Method::HEAD` or fallback to `Method::GET`.
+    ///
+    /// It changes whenever the content of `url` changes, so it can be used
+    /// to tell whether a cached copy is stale.
+    pub async fn get_validator(&self, url: Url) -> Result<Option<CompactString>, Error> {
+        let response = self.head_or_fallback_to_get(url, true).await?;
+        let headers = response.headers();
+
+        Ok(headers
+            .get(header::ETAG)
+            .or_else(|| headers.get(header::LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(CompactString::from))
+    }
+
     /// Attempt to get final redirected url using `Method::HEAD` or fallback
     /// to `Method::GET`.
     pub async fn get_redirected_final_url(&self, url: Url) -> Result<Url, Error> {
@@ -326,14 +422,17 @@ impl Client {
         &self,
         url: Url,
     ) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
-        Ok(self.get(url).send(true).await?.bytes_stream())
+        Ok(BandwidthLimitedStream::new(
+            self.get(url).send(true).await?.bytes_stream(),
+            self.bandwidth_limit.clone(),
+        ))
     }
 
     /// Create a new request.
     pub fn request(&self, method: Method, url: Url) -> RequestBuilder {
         RequestBuilder {
             client: self.clone(),
-            inner: self.0.client.request(method, url),
+            inner: self.inner.client.request(method, self.rewrite_url(url)),
         }
     }
 
@@ -0,0 +1,80 @@
+use std::{
+    future::Future,
+    num::NonZeroU64,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use tokio::time::{sleep_until, Duration, Instant, Sleep};
+
+/// Token bucket shared by all downloads of a [`super::Client`].
+#[derive(Debug)]
+pub(super) struct BandwidthLimit {
+    bytes_per_sec: NonZeroU64,
+    /// The instant at which all bytes received so far are paid for.
+    until: Mutex<Instant>,
+}
+
+impl BandwidthLimit {
+    pub(super) fn new(bytes_per_sec: NonZeroU64) -> Self {
+        Self {
+            bytes_per_sec,
+            until: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Pay for `len` bytes, return the instant at which the next chunk
+    /// can be received.
+    fn consume(&self, len: usize) -> Instant {
+        let duration = Duration::from_secs_f64(len as f64 / self.bytes_per_sec.get() as f64);
+
+        let mut until = self.until.lock().unwrap();
+        *until = (*until).max(Instant::now()) + duration;
+        *until
+    }
+}
+
+/// Stream that sleeps after each chunk to stay within the [`BandwidthLimit`].
+pub(super) struct BandwidthLimitedStream<S> {
+    stream: S,
+    limit: Option<Arc<BandwidthLimit>>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> BandwidthLimitedStream<S> {
+    pub(super) fn new(stream: S, limit: Option<Arc<BandwidthLimit>>) -> Self {
+        Self {
+            stream,
+            limit,
+            sleep: None,
+        }
+    }
+}
+
+impl<S, E> Stream for BandwidthLimitedStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Some(sleep) = &mut this.sleep {
+            ready!(sleep.as_mut().poll(cx));
+            this.sleep = None;
+        }
+
+        let item = ready!(Pin::new(&mut this.stream).poll_next(cx));
+
+        if let (Some(Ok(bytes)), Some(limit)) = (&item, &this.limit) {
+            let until = limit.consume(bytes.len());
+            this.sleep = Some(Box::pin(sleep_until(until)));
+        }
+
+        Poll::Ready(item)
+    }
+}
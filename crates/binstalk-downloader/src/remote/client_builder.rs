@@ -0,0 +1,177 @@
+use std::{
+    num::{NonZeroU16, NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{
+    Certificate, Client, ConnectionLimit, DelayRequest, Error, Inner, TLSVersion,
+    DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT, KEEP_ALIVE_INTERVAL,
+};
+
+#[cfg(feature = "__tls")]
+use super::DEFAULT_MIN_TLS;
+
+#[cfg(feature = "hickory-dns")]
+use super::TrustDnsResolver;
+
+/// Builder for [`Client`], created by [`Client::builder`].
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "__tls"), allow(dead_code))]
+pub struct ClientBuilder {
+    user_agent: String,
+    min_tls: Option<TLSVersion>,
+    per_millis: NonZeroU16,
+    num_request: NonZeroU64,
+    timeout: Option<Duration>,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    max_connections_per_host: Option<NonZeroUsize>,
+    https_proxy: Option<String>,
+    certificates: Vec<Certificate>,
+}
+
+impl ClientBuilder {
+    pub(super) fn new(user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_owned(),
+            min_tls: None,
+            per_millis: NonZeroU16::new(10).unwrap(),
+            num_request: NonZeroU64::new(1).unwrap(),
+            timeout: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            max_connections_per_host: None,
+            https_proxy: None,
+            certificates: Vec::new(),
+        }
+    }
+
+    /// Require at least `min_tls`, the client uses at least tls 1.2
+    /// regardless.
+    pub fn min_tls_version(mut self, min_tls: TLSVersion) -> Self {
+        self.min_tls = Some(min_tls);
+        self
+    }
+
+    /// * `per_millis` - The duration (in millisecond) for which at most
+    ///   `num_request` can be sent, itcould be increased if rate-limit
+    ///   happens.
+    /// * `num_request` - maximum number of requests to be processed for
+    ///   each `per` duration.
+    ///
+    /// Defaults to 1 request per 10 milliseconds.
+    pub fn rate_limit(mut self, per_millis: NonZeroU16, num_request: NonZeroU64) -> Self {
+        self.per_millis = per_millis;
+        self.num_request = num_request;
+        self
+    }
+
+    /// Timeout for each request, from connecting until the response body
+    /// is fully received.
+    ///
+    /// There is no timeout by default, but requests still fail if the
+    /// server stalls for longer than the [`ClientBuilder::read_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for connecting to the server, defaults to
+    /// [`DEFAULT_CONNECT_TIMEOUT`].
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Timeout for receiving the response headers and each chunk of the
+    /// response body, defaults to [`DEFAULT_READ_TIMEOUT`].
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    /// Maximum number of concurrent requests to each host, further
+    /// requests wait until one of them is fully received.
+    ///
+    /// There is no limit by default.
+    pub fn max_connections_per_host(mut self, max: NonZeroUsize) -> Self {
+        self.max_connections_per_host = Some(max);
+        self
+    }
+
+    /// Send all https requests through `proxy`, instead of the proxy
+    /// specified in the environment.
+    pub fn https_proxy(mut self, proxy: &str) -> Self {
+        self.https_proxy = Some(proxy.to_owned());
+        self
+    }
+
+    /// Trust `certificates` in addition to the system-wide root certificates.
+    pub fn add_root_certificates(
+        mut self,
+        certificates: impl IntoIterator<Item = Certificate>,
+    ) -> Self {
+        self.certificates.extend(certificates);
+        self
+    }
+
+    /// Build the [`Client`].
+    pub fn build(self) -> Result<Client, Error> {
+        let mut builder = reqwest::ClientBuilder::new()
+            .user_agent(self.user_agent)
+            .https_only(true)
+            .tcp_nodelay(false)
+            .tcp_keepalive(KEEP_ALIVE_INTERVAL)
+            .http2_keep_alive_interval(KEEP_ALIVE_INTERVAL)
+            .http2_keep_alive_while_idle(true)
+            .http2_adaptive_window(true)
+            .connect_timeout(self.connect_timeout);
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(proxy) = self.https_proxy {
+            builder = builder.proxy(reqwest::Proxy::https(proxy)?);
+        }
+
+        #[cfg(feature = "hickory-dns")]
+        {
+            builder = builder.dns_resolver(Arc::new(TrustDnsResolver::default()));
+        }
+
+        #[cfg(feature = "__tls")]
+        {
+            let tls_ver = self
+                .min_tls
+                .map(|tls| tls.max(DEFAULT_MIN_TLS))
+                .unwrap_or(DEFAULT_MIN_TLS);
+
+            builder = builder.min_tls_version(tls_ver.into());
+
+            for certificate in self.certificates {
+                builder = builder.add_root_certificate(certificate.0);
+            }
+        }
+
+        let client = builder.build()?;
+
+        Ok(Client {
+            inner: Arc::new(Inner {
+                client: client.clone(),
+                service: DelayRequest::new(
+                    self.num_request,
+                    Duration::from_millis(self.per_millis.get() as u64),
+                    client,
+                    self.read_timeout,
+                ),
+                connection_limit: self.max_connections_per_host.map(ConnectionLimit::new),
+                read_timeout: self.read_timeout,
+            }),
+            download_cache: None,
+            bandwidth_limit: None,
+            github_mirror: None,
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use compact_str::{CompactString, ToCompactString};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+/// Limit on the number of concurrent requests to each host, shared by all
+/// requests of a [`super::Client`].
+#[derive(Debug)]
+pub(super) struct ConnectionLimit {
+    max_per_host: NonZeroUsize,
+    hosts: Mutex<HashMap<CompactString, Arc<Semaphore>>>,
+}
+
+impl ConnectionLimit {
+    pub(super) fn new(max_per_host: NonZeroUsize) -> Self {
+        Self {
+            max_per_host,
+            hosts: Default::default(),
+        }
+    }
+
+    /// Wait until a request to the host of `url` is allowed, the returned
+    /// permit must be kept until the response is fully received.
+    pub(super) async fn acquire(&self, url: &Url) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(url.host_str().unwrap_or_default().to_compact_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_host.get())))
+            .clone();
+
+        // The semaphore is never closed.
+        semaphore.acquire_owned().await.unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        let limit = ConnectionLimit::new(NonZeroUsize::new(1).unwrap());
+
+        let a = Url::parse("https://a.example.com/1").unwrap();
+        let b = Url::parse("https://b.example.com/1").unwrap();
+
+        let permit = limit.acquire(&a).await;
+
+        // Other hosts are not affected.
+        drop(limit.acquire(&b).await);
+
+        // The host is busy until the permit is dropped.
+        let same_host = Url::parse("https://a.example.com/2").unwrap();
+        assert!(tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            limit.acquire(&same_host)
+        )
+        .await
+        .is_err());
+
+        drop(permit);
+        drop(limit.acquire(&same_host).await);
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use compact_str::{CompactString, ToCompactString};
 use reqwest::{Request, Url};
-use tokio::time::{sleep_until, Duration, Instant};
+use tokio::time::{error::Elapsed, sleep_until, timeout, Duration, Instant};
 use tracing::debug;
 
 pub(super) type RequestResult = Result<reqwest::Response, reqwest::Error>;
@@ -137,13 +137,20 @@ enum Readiness {
 pub(super) struct DelayRequest {
     inner: Mutex<Inner>,
     hosts_to_delay: Mutex<HashMap<CompactString, Instant>>,
+    read_timeout: Duration,
 }
 
 impl DelayRequest {
-    pub(super) fn new(num_request: NonZeroU64, per: Duration, client: reqwest::Client) -> Self {
+    pub(super) fn new(
+        num_request: NonZeroU64,
+        per: Duration,
+        client: reqwest::Client,
+        read_timeout: Duration,
+    ) -> Self {
         Self {
             inner: Mutex::new(Inner::new(num_request, per, client)),
             hosts_to_delay: Default::default(),
+            read_timeout,
         }
     }
 
@@ -219,10 +226,12 @@ impl DelayRequest {
         }
     }
 
-    pub(super) async fn call(&self, req: Request) -> RequestResult {
+    /// Send `req` once allowed by the rate limits, return `Err(Elapsed)` if
+    /// the response headers are not received within the read timeout.
+    pub(super) async fn call(&self, req: Request) -> Result<RequestResult, Elapsed> {
         // Put all variables in a block so that will be dropped before polling
         // the future returned by reqwest.
-        {
+        let future = {
             let mut counter = 0;
             // Use Option here so that we don't have to move entire `Request`
             // twice when calling `self.call_inner` while retain the ability to
@@ -239,7 +248,8 @@ impl DelayRequest {
                     ControlFlow::Break(future) => break future,
                 }
             }
-        }
-        .await
+        };
+
+        timeout(self.read_timeout, future).await
     }
 }
@@ -0,0 +1,65 @@
+use url::{Position, Url};
+
+/// Rewrite `url` to be downloaded from `mirror` if it is a GitHub release
+/// artifact, e.g. `https://github.com/{org}/{repo}/releases/download/{tag}/{file}`
+/// is rewritten to `{mirror}/{org}/{repo}/releases/download/{tag}/{file}`.
+///
+/// `mirror` can also contain a path, e.g. `https://ghproxy.com/https://github.com`
+/// for proxies which take the original url in their path.
+pub(super) fn rewrite_url(mirror: &Url, url: Url) -> Url {
+    if !is_github_release_artifact(&url) {
+        return url;
+    }
+
+    let mut rewritten = mirror.as_str().trim_end_matches('/').to_owned();
+    rewritten.push_str(&url[Position::BeforePath..]);
+
+    Url::parse(&rewritten).unwrap_or(url)
+}
+
+fn is_github_release_artifact(url: &Url) -> bool {
+    if url.scheme() != "https" || url.host_str() != Some("github.com") {
+        return false;
+    }
+
+    let Some(mut segments) = url.path_segments() else {
+        return false;
+    };
+
+    // Skip org and repo
+    segments.nth(1).is_some()
+        && segments.next() == Some("releases")
+        && segments.next() == Some("download")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_url() {
+        let url = |s| Url::parse(s).unwrap();
+        let artifact = url(
+            "https://github.com/cargo-bins/cargo-binstall/releases/download/v1.0.0/a.tgz?raw=1",
+        );
+
+        assert_eq!(
+            rewrite_url(&url("https://mirror.example.com/github/"), artifact.clone()).as_str(),
+            "https://mirror.example.com/github/cargo-bins/cargo-binstall/releases/download/v1.0.0/a.tgz?raw=1"
+        );
+        assert_eq!(
+            rewrite_url(&url("https://ghproxy.com/https://github.com"), artifact.clone()).as_str(),
+            "https://ghproxy.com/https://github.com/cargo-bins/cargo-binstall/releases/download/v1.0.0/a.tgz?raw=1"
+        );
+
+        let mirror = url("https://mirror.example.com");
+        for s in [
+            "https://github.com/cargo-bins/cargo-binstall",
+            "https://github.com/cargo-bins/cargo-binstall/releases/latest",
+            "https://api.github.com/repos/cargo-bins/cargo-binstall/releases/download/a",
+            "https://gitlab.com/a/b/releases/download/v1.0.0/a.tgz",
+        ] {
+            assert_eq!(rewrite_url(&mirror, url(s)), url(s));
+        }
+    }
+}
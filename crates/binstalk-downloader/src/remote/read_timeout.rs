@@ -0,0 +1,95 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use tokio::time::{sleep, Duration, Instant, Sleep};
+
+use super::{Error, Url};
+
+/// Stream that fails if no chunk is received within the read timeout, so
+/// that a stalled download would not hang forever.
+pub(super) struct ReadTimeoutStream<S> {
+    stream: S,
+    read_timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+    url: Option<Box<Url>>,
+}
+
+impl<S> ReadTimeoutStream<S> {
+    pub(super) fn new(stream: S, read_timeout: Duration, url: Url) -> Self {
+        Self {
+            stream,
+            read_timeout,
+            sleep: Box::pin(sleep(read_timeout)),
+            url: Some(Box::new(url)),
+        }
+    }
+}
+
+impl<S> Stream for ReadTimeoutStream<S>
+where
+    S: Stream<Item = Result<Bytes, Error>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        // The stream has already timed out.
+        if this.url.is_none() {
+            return Poll::Ready(None);
+        }
+
+        if let Poll::Ready(item) = Pin::new(&mut this.stream).poll_next(cx) {
+            let deadline = Instant::now() + this.read_timeout;
+            this.sleep.as_mut().reset(deadline);
+
+            return Poll::Ready(item);
+        }
+
+        ready!(this.sleep.as_mut().poll(cx));
+
+        Poll::Ready(this.url.take().map(|url| Err(Error::ReadTimeout(url))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures_util::{stream, StreamExt};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_timeout() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let read_timeout = Duration::from_secs(10);
+
+        // Chunks received in time are passed through.
+        let chunks = stream::iter([Ok(Bytes::from_static(b"a")), Ok(Bytes::from_static(b"b"))])
+            .then(|chunk| async move {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                chunk
+            });
+        let chunks: Vec<_> = ReadTimeoutStream::new(Box::pin(chunks), read_timeout, url.clone())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(Result::is_ok));
+
+        // A stalled stream fails once and then ends.
+        let mut stalled = ReadTimeoutStream::new(
+            stream::pending::<Result<Bytes, Error>>(),
+            read_timeout,
+            url.clone(),
+        );
+        assert!(matches!(
+            stalled.next().await,
+            Some(Err(Error::ReadTimeout(timed_out))) if *timed_out == url
+        ));
+        assert!(stalled.next().await.is_none());
+    }
+}
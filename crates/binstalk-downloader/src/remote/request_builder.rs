@@ -1,10 +1,11 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
-use bytes::Bytes;
-use futures_util::{Stream, StreamExt};
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt, TryStreamExt};
 use reqwest::Method;
+use tokio::sync::OwnedSemaphorePermit;
 
-use super::{header, Client, Error, HttpError, StatusCode, Url};
+use super::{header, Client, Error, HttpError, ReadTimeoutStream, StatusCode, Url};
 
 pub use reqwest::Body;
 
@@ -42,9 +43,12 @@ impl RequestBuilder {
     pub async fn send(self, error_for_status: bool) -> Result<Response, Error> {
         let request = self.inner.build()?;
         let method = request.method().clone();
+        let permit = self.client.acquire_connection(request.url()).await;
         Ok(Response {
             inner: self.client.send_request(request, error_for_status).await?,
             method,
+            permit,
+            read_timeout: self.client.inner.read_timeout,
         })
     }
 }
@@ -53,18 +57,32 @@ impl RequestBuilder {
 pub struct Response {
     inner: reqwest::Response,
     method: Method,
+    /// Connection permit of the host, held until the body is received.
+    permit: Option<OwnedSemaphorePermit>,
+    read_timeout: Duration,
 }
 
 impl Response {
     pub async fn bytes(self) -> Result<Bytes, Error> {
-        self.inner.bytes().await.map_err(Error::from)
+        self.bytes_stream()
+            .try_fold(BytesMut::new(), |mut buffer, bytes| async move {
+                buffer.extend_from_slice(&bytes);
+                Ok(buffer)
+            })
+            .await
+            .map(BytesMut::freeze)
     }
 
+    /// Return a stream of the response body, which fails if no data is
+    /// received within the read timeout.
     pub fn bytes_stream(self) -> impl Stream<Item = Result<Bytes, Error>> {
         let url = Box::new(self.inner.url().clone());
+        let read_timeout_url = Url::clone(&url);
         let method = self.method;
+        let permit = self.permit;
 
-        self.inner.bytes_stream().map(move |res| {
+        let stream = self.inner.bytes_stream().map(move |res| {
+            let _permit = &permit;
             res.map_err(|err| {
                 Error::Http(Box::new(HttpError {
                     method: method.clone(),
@@ -72,7 +90,9 @@ impl Response {
                     err,
                 }))
             })
-        })
+        });
+
+        ReadTimeoutStream::new(stream, self.read_timeout, read_timeout_url)
     }
 
     pub fn status(&self) -> StatusCode {
@@ -1,6 +1,9 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering::Relaxed},
-    Once,
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Once,
+    },
 };
 
 use binstalk_downloader::gh_api_client::{GhReleaseArtifact, HasReleaseArtifact};
@@ -12,13 +15,27 @@ pub(super) use binstalk_downloader::{
 pub(super) use binstalk_types::cargo_toml_binstall::{PkgFmt, PkgMeta};
 pub(super) use compact_str::CompactString;
 pub(super) use tokio::task::JoinHandle;
+use tracing::Instrument;
 pub(super) use tracing::{debug, instrument, warn};
 
 use crate::FetchError;
 
+/// Spawn `future` in the current span, so that its logs are attributed to
+/// the crate being resolved.
+pub(super) fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future.in_current_span())
+}
+
+/// Check whether `url` exists, using the GitHub API (and its cache)
+/// for GitHub release artifacts and falling back to a plain request.
+///
 /// This function returns a future where its size should be at most size of
 /// 2-4 pointers.
-pub(super) async fn does_url_exist(
+pub async fn does_url_exist(
     client: Client,
     gh_api_client: GhApiClient,
     url: &Url,
@@ -58,3 +75,22 @@ pub(super) async fn does_url_exist(
 
     Ok(Box::pin(client.remote_gettable(url.clone())).await?)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tracing::{info_span, subscriber, Span};
+    use tracing_subscriber::registry;
+
+    #[tokio::test]
+    async fn test_spawn_in_current_span() {
+        let _guard = subscriber::set_default(registry());
+
+        let span = info_span!("fetch", crate_name = "foo");
+        let handle = span.in_scope(|| spawn(async { Span::current().id() }));
+
+        assert!(span.id().is_some());
+        assert_eq!(handle.await.unwrap(), span.id());
+    }
+}
@@ -1,19 +1,28 @@
-use std::{future::Future, pin::Pin};
+use std::{cell::Cell, future::Future, iter, pin::Pin};
 use tokio::sync::mpsc;
 
+use crate::common::spawn;
+
 /// Given multiple futures with output = `Result<Option<T>, E>`,
-/// returns the the first one that returns either `Err(_)` or
+/// returns the the first one pushed that returns either `Err(_)` or
 /// `Ok(Some(_))`.
+///
+/// All futures run concurrently, the order they are pushed in is their
+/// priority.
 pub struct FuturesResolver<T, E> {
-    rx: mpsc::Receiver<Result<T, E>>,
-    tx: mpsc::Sender<Result<T, E>>,
+    rx: mpsc::UnboundedReceiver<(usize, Option<Result<T, E>>)>,
+    tx: mpsc::UnboundedSender<(usize, Option<Result<T, E>>)>,
+    len: Cell<usize>,
 }
 
 impl<T, E> Default for FuturesResolver<T, E> {
     fn default() -> Self {
-        // We only need the first one, so the channel is of size 1.
-        let (tx, rx) = mpsc::channel(1);
-        Self { tx, rx }
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tx,
+            rx,
+            len: Cell::new(0),
+        }
     }
 }
 
@@ -26,16 +35,20 @@ impl<T: Send + 'static, E: Send + 'static> FuturesResolver<T, E> {
     {
         let tx = self.tx.clone();
 
-        tokio::spawn(async move {
+        let index = self.len.get();
+        self.len.set(index + 1);
+
+        spawn(async move {
             tokio::pin!(fut);
 
-            Self::spawn_inner(fut, tx).await;
+            Self::spawn_inner(fut, tx, index).await;
         });
     }
 
     async fn spawn_inner(
         fut: Pin<&mut (dyn Future<Output = Result<Option<T>, E>> + Send)>,
-        tx: mpsc::Sender<Result<T, E>>,
+        tx: mpsc::UnboundedSender<(usize, Option<Result<T, E>>)>,
+        index: usize,
     ) {
         let res = tokio::select! {
             biased;
@@ -44,16 +57,9 @@ impl<T: Send + 'static, E: Send + 'static> FuturesResolver<T, E> {
             res = fut => res,
         };
 
-        if let Some(res) = res.transpose() {
-            // try_send can only fail due to being full or being closed.
-            //
-            // In both cases, this could means some other future has
-            // completed first.
-            //
-            // For closed, it could additionally means that the task
-            // is cancelled.
-            tx.try_send(res).ok();
-        }
+        // send can only fail due to being closed, which means the
+        // resolution is already done or the task is cancelled.
+        tx.send((index, res.transpose())).ok();
     }
 
     /// Insert multiple futures into this resolver, they will start running
@@ -67,10 +73,85 @@ impl<T: Send + 'static, E: Send + 'static> FuturesResolver<T, E> {
     }
 
     /// Return the resolution.
+    ///
+    /// It returns as soon as the result of every future pushed before the
+    /// winning one is known, without waiting for the rest.
     pub fn resolve(self) -> impl Future<Output = Result<Option<T>, E>> {
         let mut rx = self.rx;
         drop(self.tx);
 
-        async move { rx.recv().await.transpose() }
+        // `None` if the future is still pending.
+        let mut results: Vec<Option<Option<Result<T, E>>>> =
+            iter::repeat_with(|| None).take(self.len.get()).collect();
+
+        async move {
+            // Index of the first future that might still return
+            // `Err(_)` or `Ok(Some(_))`.
+            let mut first = 0;
+
+            while let Some((index, res)) = rx.recv().await {
+                results[index] = Some(res);
+
+                while let Some(slot) = results.get_mut(first) {
+                    match slot.take() {
+                        // Still pending
+                        None => break,
+                        Some(None) => first += 1,
+                        Some(Some(res)) => return res.map(Some),
+                    }
+                }
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_first_pushed_wins() {
+        let resolver = FuturesResolver::<u32, ()>::default();
+        let (tx, rx) = oneshot::channel();
+
+        // The first future only finishes after the second one.
+        resolver.push(async move {
+            rx.await.unwrap();
+            Ok(Some(1))
+        });
+        resolver.push(async move {
+            tx.send(()).unwrap();
+            Ok(Some(2))
+        });
+        resolver.push(async { Ok(Some(3)) });
+
+        assert_eq!(resolver.resolve().await, Ok(Some(1)));
+    }
+
+    #[tokio::test]
+    async fn test_skip_none() {
+        let resolver = FuturesResolver::<u32, ()>::default();
+        let (tx, rx) = oneshot::channel();
+
+        resolver.push(async move {
+            rx.await.unwrap();
+            Ok(None)
+        });
+        resolver.push(async move {
+            tx.send(()).unwrap();
+            Ok(Some(2))
+        });
+        resolver.push(async { Err(()) });
+
+        assert_eq!(resolver.resolve().await, Ok(Some(2)));
+
+        let resolver = FuturesResolver::<u32, ()>::default();
+        resolver.extend((0..2).map(|_| async { Ok(None) }));
+
+        assert_eq!(resolver.resolve().await, Ok(None));
     }
 }
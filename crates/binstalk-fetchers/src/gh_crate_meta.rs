@@ -1,5 +1,6 @@
 use std::{borrow::Cow, fmt, iter, path::Path, sync::Arc};
 
+use binstalk_types::cargo_toml_binstall::Templates;
 use compact_str::{CompactString, ToCompactString};
 use either::Either;
 use leon::Template;
@@ -9,8 +10,8 @@ use tracing::{debug, info, trace, warn};
 use url::Url;
 
 use crate::{
-    common::*, futures_resolver::FuturesResolver, Data, FetchError, InvalidPkgFmtError, RepoInfo,
-    SignaturePolicy, SignatureVerifier, TargetDataErased,
+    common::*, futures_resolver::FuturesResolver, Data, FetchError, RepoInfo, SignaturePolicy,
+    SignatureVerifier, Strategy, TargetDataErased,
 };
 
 pub(crate) mod hosting;
@@ -22,23 +23,71 @@ pub struct GhCrateMeta {
     target_data: Arc<TargetDataErased>,
     signature_policy: SignaturePolicy,
     resolution: OnceCell<Resolved>,
+    detected_pkg_fmt: OnceCell<PkgFmt>,
 }
 
 #[derive(Debug)]
 struct Resolved {
     url: Url,
-    pkg_fmt: PkgFmt,
+    /// `None` if it has to be detected from the downloaded package.
+    pkg_fmt: Option<PkgFmt>,
     archive_suffix: Option<String>,
     repo: Option<String>,
     subcrate: Option<String>,
+    /// Index of the pkg-url template the package is found with.
+    pkg_url_index: usize,
 }
 
 impl GhCrateMeta {
+    /// Return the pkg-fmt to try `pkg_url` with, `None` for all of them,
+    /// and whether it has to be detected from the downloaded package.
+    fn pkg_fmt_of(&self, pkg_url: &str, template: &Template<'_>) -> (Option<PkgFmt>, bool) {
+        let pkg_fmt = self.target_data.meta.pkg_fmt;
+
+        if pkg_fmt.is_some()
+            || template.has_any_of_keys(&["format", "archive-format", "archive-suffix"])
+        {
+            return (pkg_fmt, false);
+        }
+
+        // The crate does not specify the pkg-fmt, yet its pkg-url
+        // template doesn't contains format, archive-format or
+        // archive-suffix which is required for automatically
+        // deducing the pkg-fmt.
+        //
+        // We will attempt to guess the pkg-fmt there, but this is
+        // just a best-effort, if it fails then the pkg-fmt is
+        // detected from the magic bytes of the downloaded package.
+        let pkg_fmt = PkgFmt::guess_pkg_format(pkg_url);
+
+        let crate_name = &self.data.name;
+        let version = &self.data.version;
+        let target = &self.target_data.target;
+
+        if let Some(pkg_fmt) = pkg_fmt {
+            warn!(
+                "Crate {crate_name}@{version} on target {target} does not specify pkg-fmt \
+                but its pkg-url also does not contain key format, archive-format or \
+                archive-suffix.\nbinstall was able to guess that from pkg-url, but \
+                just note that it could be wrong:\npkg-fmt=\"{pkg_fmt}\", pkg-url=\"{pkg_url}\"",
+            );
+            (Some(pkg_fmt), false)
+        } else {
+            warn!(
+                "Crate {crate_name}@{version} on target {target} does not specify pkg-fmt \
+                and binstall cannot guess it from its pkg-url, it will be detected \
+                from the downloaded package instead:\npkg-url=\"{pkg_url}\"",
+            );
+            (None, true)
+        }
+    }
+
     fn launch_baseline_find_tasks(
         &self,
         futures_resolver: &FuturesResolver<Resolved, FetchError>,
-        pkg_fmt: PkgFmt,
+        pkg_fmt: Option<PkgFmt>,
         pkg_url: &Template<'_>,
+        pkg_url_index: usize,
         repo: Option<&str>,
         subcrate: Option<&str>,
     ) {
@@ -62,7 +111,9 @@ impl GhCrateMeta {
 
         let is_windows = self.target_data.target.contains("windows");
 
-        let urls = if pkg_url.has_any_of_keys(&["format", "archive-format", "archive-suffix"]) {
+        let urls = if let Some(pkg_fmt) = pkg_fmt
+            .filter(|_| pkg_url.has_any_of_keys(&["format", "archive-format", "archive-suffix"]))
+        {
             // build up list of potential URLs
             Either::Left(
                 pkg_fmt
@@ -91,6 +142,7 @@ impl GhCrateMeta {
                         repo,
                         subcrate,
                         archive_suffix,
+                        pkg_url_index,
                     }))
             }
         }));
@@ -113,61 +165,28 @@ impl super::Fetcher for GhCrateMeta {
             target_data,
             signature_policy,
             resolution: OnceCell::new(),
+            detected_pkg_fmt: OnceCell::new(),
         })
     }
 
     fn find(self: Arc<Self>) -> JoinHandle<Result<bool, FetchError>> {
-        tokio::spawn(async move {
+        spawn(async move {
             let info = self.data.get_repo_info(&self.client).await?.as_ref();
 
             let repo = info.map(|info| &info.repo);
             let subcrate = info.and_then(|info| info.subcrate.as_deref());
 
-            let mut pkg_fmt = self.target_data.meta.pkg_fmt;
-
-            let pkg_urls = if let Some(pkg_url) = self.target_data.meta.pkg_url.as_deref() {
-                let template = Template::parse(pkg_url)?;
-
-                if pkg_fmt.is_none()
-                    && !template.has_any_of_keys(&["format", "archive-format", "archive-suffix"])
-                {
-                    // The crate does not specify the pkg-fmt, yet its pkg-url
-                    // template doesn't contains format, archive-format or
-                    // archive-suffix which is required for automatically
-                    // deducing the pkg-fmt.
-                    //
-                    // We will attempt to guess the pkg-fmt there, but this is
-                    // just a best-effort
-                    pkg_fmt = PkgFmt::guess_pkg_format(pkg_url);
-
-                    let crate_name = &self.data.name;
-                    let version = &self.data.version;
-                    let target = &self.target_data.target;
-
-                    if pkg_fmt.is_none() {
-                        return Err(InvalidPkgFmtError {
-                            crate_name: crate_name.clone(),
-                            version: version.clone(),
-                            target: target.into(),
-                            pkg_url: pkg_url.into(),
-                            reason:
-                                &"pkg-fmt is not specified, yet pkg-url does not contain format, \
-                                archive-format or archive-suffix which is required for automatically \
-                                deducing pkg-fmt",
-                        }
-                        .into());
-                    }
-
-                    warn!(
-                        "Crate {crate_name}@{version} on target {target} does not specify pkg-fmt \
-                        but its pkg-url also does not contain key format, archive-format or \
-                        archive-suffix.\nbinstall was able to guess that from pkg-url, but \
-                        just note that it could be wrong:\npkg-fmt=\"{pkg_fmt}\", pkg-url=\"{pkg_url}\"",
-                        pkg_fmt = pkg_fmt.unwrap(),
-                    );
-                }
-
-                Either::Left(iter::once(template))
+            let pkg_urls = if let Some(pkg_urls) = &self.target_data.meta.pkg_url {
+                let pkg_urls = pkg_urls
+                    .iter()
+                    .map(|pkg_url| {
+                        let template = Template::parse(pkg_url)?;
+                        let (pkg_fmt, detect_pkg_fmt) = self.pkg_fmt_of(pkg_url, &template);
+                        Ok((template, pkg_fmt, detect_pkg_fmt))
+                    })
+                    .collect::<Result<Vec<_>, FetchError>>()?;
+
+                Either::Left(pkg_urls.into_iter())
             } else if let Some(RepoInfo {
                 repo,
                 repository_host,
@@ -177,13 +196,16 @@ impl super::Fetcher for GhCrateMeta {
                 if let Some(pkg_urls) = repository_host.get_default_pkg_url_template() {
                     let has_subcrate = subcrate.is_some();
 
+                    let pkg_fmt = self.target_data.meta.pkg_fmt;
+
                     Either::Right(
                         pkg_urls
                             .map(Template::cast)
                             // If subcrate is Some, then all templates will be included.
                             // Otherwise, only templates without key "subcrate" will be
                             // included.
-                            .filter(move |template| has_subcrate || !template.has_key("subcrate")),
+                            .filter(move |template| has_subcrate || !template.has_key("subcrate"))
+                            .map(move |template| (template, pkg_fmt, false)),
                     )
                 } else {
                     warn!(
@@ -215,27 +237,44 @@ impl super::Fetcher for GhCrateMeta {
             // launch_baseline_find_tasks which moves `this`
             let this = &self;
 
-            let pkg_fmts = if let Some(pkg_fmt) = pkg_fmt {
-                Either::Left(iter::once(pkg_fmt))
-            } else {
-                Either::Right(PkgFmt::iter())
-            };
-
             let resolver = FuturesResolver::default();
 
             // Iterate over pkg_urls first to avoid String::clone.
-            for pkg_url in pkg_urls {
-                //             Clone iter pkg_fmts to ensure all pkg_fmts is
-                //             iterated over for each pkg_url, which is
-                //             basically cartesian product.
-                //             |
-                for pkg_fmt in pkg_fmts.clone() {
-                    this.launch_baseline_find_tasks(&resolver, pkg_fmt, &pkg_url, repo, subcrate);
+            //
+            // Tasks are pushed in the order of pkg_urls, so that the first
+            // pkg_url found wins even though all of them are probed at once.
+            for (pkg_url_index, (pkg_url, pkg_fmt, detect_pkg_fmt)) in pkg_urls.enumerate() {
+                let pkg_fmts = if pkg_fmt.is_some() || detect_pkg_fmt {
+                    Either::Left(iter::once(pkg_fmt))
+                } else {
+                    Either::Right(pkg_fmts_of(&pkg_url).map(Some))
+                };
+
+                for pkg_fmt in pkg_fmts {
+                    this.launch_baseline_find_tasks(
+                        &resolver,
+                        pkg_fmt,
+                        &pkg_url,
+                        pkg_url_index,
+                        repo,
+                        subcrate,
+                    );
                 }
             }
 
             if let Some(resolved) = resolver.resolve().await? {
                 debug!(?resolved, "Winning URL found!");
+
+                if let Some(Templates::Many(pkg_urls)) = &self.target_data.meta.pkg_url {
+                    info!(
+                        "Found package of {}@{} for target {} with pkg-url \"{}\"",
+                        self.data.name,
+                        self.data.version,
+                        self.target_data.target,
+                        pkg_urls[resolved.pkg_url_index],
+                    );
+                }
+
                 self.resolution.set(resolved).unwrap(); // find() is called first
                 Ok(true)
             } else {
@@ -290,13 +329,21 @@ impl super::Fetcher for GhCrateMeta {
             "Downloading package",
         );
         let mut data_verifier = verifier.data_verifier()?;
-        let files = Download::new_with_data_verifier(
+        let download = Download::new_with_data_verifier(
             self.client.clone(),
             resolved.url.clone(),
             data_verifier.as_mut(),
         )
-        .and_extract(resolved.pkg_fmt, dst)
-        .await?;
+        .with_sha256();
+        let files = if let Some(pkg_fmt) = resolved.pkg_fmt {
+            download.and_extract(pkg_fmt, dst).await?
+        } else {
+            let (pkg_fmt, files) = download.and_extract_detected(None, dst).await?;
+            debug!("Detected pkg-fmt {pkg_fmt} for {}", resolved.url);
+            // fetch_and_extract is only called once
+            self.detected_pkg_fmt.set(pkg_fmt).ok();
+            files
+        };
         trace!("validating signature (if any)");
         if data_verifier.validate() {
             if let Some(info) = verifier.info() {
@@ -312,7 +359,14 @@ impl super::Fetcher for GhCrateMeta {
     }
 
     fn pkg_fmt(&self) -> PkgFmt {
-        self.resolution.get().unwrap().pkg_fmt
+        self.resolution
+            .get()
+            .unwrap()
+            .pkg_fmt
+            .or_else(|| self.detected_pkg_fmt.get().copied())
+            // The package has not been downloaded yet, e.g. in dry-run mode,
+            // assume it is a raw binary since its pkg-url has no known extension.
+            .unwrap_or(PkgFmt::Bin)
     }
 
     fn target_meta(&self) -> PkgMeta {
@@ -336,6 +390,10 @@ impl super::Fetcher for GhCrateMeta {
             .unwrap_or_else(|| "invalid url".into())
     }
 
+    fn package_url(&self) -> Option<&Url> {
+        self.resolution.get().map(|resolved| &resolved.url)
+    }
+
     fn fetcher_name(&self) -> &'static str {
         "GhCrateMeta"
     }
@@ -344,6 +402,10 @@ impl super::Fetcher for GhCrateMeta {
         false
     }
 
+    fn strategy(&self) -> Strategy {
+        Strategy::CrateMetaData
+    }
+
     fn target(&self) -> &str {
         &self.target_data.target
     }
@@ -353,6 +415,17 @@ impl super::Fetcher for GhCrateMeta {
     }
 }
 
+/// Return the pkg-fmts a pkg-url template without a fixed pkg-fmt can be
+/// found with.
+///
+/// A template containing `binary-ext` names a single binary, possibly
+/// compressed, so archives are not probed for it.
+fn pkg_fmts_of(pkg_url: &Template<'_>) -> impl Iterator<Item = PkgFmt> + Clone {
+    let is_bin = pkg_url.has_key("binary-ext");
+
+    PkgFmt::iter().filter(move |pkg_fmt| !is_bin || pkg_fmt.is_bin())
+}
+
 /// Template for constructing download paths
 #[derive(Clone)]
 struct Context<'c> {
@@ -477,8 +550,11 @@ impl<'c> Context<'c> {
 
 #[cfg(test)]
 mod test {
-    use super::{super::Data, Context};
+    use super::{super::Data, pkg_fmts_of, Context};
+    use binstalk_types::cargo_toml_binstall::PkgFmt;
     use compact_str::ToCompactString;
+    use leon::Template;
+    use strum::IntoEnumIterator;
     use url::Url;
 
     const DEFAULT_PKG_URL: &str = "{ repo }/releases/download/v{ version }/{ name }-{ target }-v{ version }.{ archive-format }";
@@ -596,4 +672,15 @@ mod test {
             "https://github.com/watchexec/cargo-watch/releases/download/v9.0.0/cargo-watch-v9.0.0-aarch64-pc-windows-msvc.exe"
         );
     }
+
+    #[test]
+    fn pkg_fmts_of_template() {
+        let archive = Template::parse("{ name }-{ target }{ archive-suffix }").unwrap();
+        assert!(pkg_fmts_of(&archive).eq(PkgFmt::iter()));
+
+        let bin = Template::parse("{ name }-{ target }{ binary-ext }{ archive-suffix }").unwrap();
+        assert!(pkg_fmts_of(&bin).eq(PkgFmt::iter().filter(|pkg_fmt| pkg_fmt.is_bin())));
+        assert!(pkg_fmts_of(&bin).any(|pkg_fmt| pkg_fmt == PkgFmt::Gz));
+        assert!(!pkg_fmts_of(&bin).any(|pkg_fmt| pkg_fmt == PkgFmt::Tgz));
+    }
 }
@@ -5,7 +5,7 @@ use std::{path::Path, sync::Arc};
 use binstalk_downloader::{
     download::DownloadError, gh_api_client::GhApiError, remote::Error as RemoteError,
 };
-use binstalk_types::cargo_toml_binstall::SigningAlgorithm;
+use binstalk_types::cargo_toml_binstall::{SigningAlgorithm, Strategy};
 use thiserror::Error as ThisError;
 use tokio::sync::OnceCell;
 pub use url::ParseError as UrlParseError;
@@ -21,6 +21,8 @@ pub use quickinstall::*;
 mod common;
 use common::*;
 
+pub use common::does_url_exist;
+
 mod signing;
 use signing::*;
 
@@ -28,16 +30,6 @@ mod futures_resolver;
 
 use gh_crate_meta::hosting::RepositoryHost;
 
-#[derive(Debug, ThisError)]
-#[error("Invalid pkg-url {pkg_url} for {crate_name}@{version} on {target}: {reason}")]
-pub struct InvalidPkgFmtError {
-    pub crate_name: CompactString,
-    pub version: CompactString,
-    pub target: CompactString,
-    pub pkg_url: Box<str>,
-    pub reason: &'static &'static str,
-}
-
 #[derive(Debug, ThisError, miette::Diagnostic)]
 #[non_exhaustive]
 #[cfg_attr(feature = "miette", derive(miette::Diagnostic))]
@@ -56,9 +48,6 @@ pub enum FetchError {
     #[error("Failed to render template: {0}")]
     GhApi(#[from] GhApiError),
 
-    #[error(transparent)]
-    InvalidPkgFmt(Box<InvalidPkgFmtError>),
-
     #[error("Failed to parse url: {0}")]
     UrlParse(#[from] UrlParseError),
 
@@ -78,12 +67,6 @@ impl From<RemoteError> for FetchError {
     }
 }
 
-impl From<InvalidPkgFmtError> for FetchError {
-    fn from(e: InvalidPkgFmtError) -> Self {
-        Self::InvalidPkgFmt(Box::new(e))
-    }
-}
-
 #[async_trait::async_trait]
 pub trait Fetcher: Send + Sync {
     /// Create a new fetcher from some data
@@ -111,8 +94,11 @@ pub trait Fetcher: Send + Sync {
     /// fatal conditions only.
     fn find(self: Arc<Self>) -> JoinHandle<Result<bool, FetchError>>;
 
-    /// Report to upstream that cargo-binstall tries to use this fetcher.
+    /// Report to upstream that cargo-binstall tries to use this fetcher,
+    /// without waiting for the report to be sent.
     /// Currently it is only overriden by [`quickinstall::QuickInstall`].
+    ///
+    /// It must only be called if the user opted in to sending reports.
     fn report_to_upstream(self: Arc<Self>) {}
 
     /// Return the package format
@@ -124,6 +110,15 @@ pub trait Fetcher: Send + Sync {
     /// A short human-readable name or descriptor for the package source
     fn source_name(&self) -> CompactString;
 
+    /// Return the url of the package to download.
+    ///
+    /// It must return `Some` once [`Fetcher::find`] returns `true`.
+    ///
+    /// The default implementation returns `None`.
+    fn package_url(&self) -> Option<&Url> {
+        None
+    }
+
     /// A short human-readable name, must contains only characters
     /// and numbers and it also must be unique.
     ///
@@ -134,6 +129,19 @@ pub trait Fetcher: Send + Sync {
     /// Should return true if the remote is from a third-party source
     fn is_third_party(&self) -> bool;
 
+    /// Return the strategy used by this fetcher, it can be disabled
+    /// by the crate via `disabled-strategies`.
+    ///
+    /// The default implementation returns [`Strategy::QuickInstall`] for
+    /// third-party fetchers and [`Strategy::CrateMetaData`] otherwise.
+    fn strategy(&self) -> Strategy {
+        if self.is_third_party() {
+            Strategy::QuickInstall
+        } else {
+            Strategy::CrateMetaData
+        }
+    }
+
     /// Return the target for this fetcher
     fn target(&self) -> &str;
 
@@ -179,6 +187,21 @@ impl Data {
         }
     }
 
+    /// Name of the crate to fetch.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Resolved version of the crate to fetch.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Repository url specified in the crate manifest, if any.
+    pub fn repo(&self) -> Option<&str> {
+        self.repo.as_deref()
+    }
+
     #[instrument(level = "debug")]
     async fn get_repo_info(&self, client: &Client) -> Result<&Option<RepoInfo>, FetchError> {
         self.repo_info
@@ -1,18 +1,20 @@
-use std::{borrow::Cow, path::Path, sync::Arc};
+use std::{borrow::Cow, path::Path, sync::Arc, time::Duration};
 
-use binstalk_downloader::remote::Method;
+use binstalk_downloader::remote::Error as RemoteError;
 use binstalk_types::cargo_toml_binstall::{PkgFmt, PkgMeta, PkgSigning};
 use tokio::sync::OnceCell;
 use tracing::{error, info, trace};
 use url::Url;
 
 use crate::{
-    common::*, Data, FetchError, SignaturePolicy, SignatureVerifier, SigningAlgorithm,
+    common::*, Data, FetchError, SignaturePolicy, SignatureVerifier, SigningAlgorithm, Strategy,
     TargetDataErased,
 };
 
 const BASE_URL: &str = "https://github.com/cargo-bins/cargo-quickinstall/releases/download";
 const STATS_URL: &str = "https://warehouse-clerk-tmp.vercel.app/api/crate";
+/// The report is best-effort, it must not keep binstall running for long.
+const STATS_TIMEOUT: Duration = Duration::from_secs(5);
 
 const QUICKINSTALL_SIGN_KEY: Cow<'static, str> =
     Cow::Borrowed("RWTdnnab2pAka9OdwgCMYyOE66M/BlQoFWaJ/JjwcPV+f3n24IRTj97t");
@@ -113,19 +115,23 @@ impl super::Fetcher for QuickInstall {
     }
 
     fn find(self: Arc<Self>) -> JoinHandle<Result<bool, FetchError>> {
-        tokio::spawn(async move {
+        spawn(async move {
             if !self.is_supported().await? {
                 return Ok(false);
             }
 
             if self.signature_policy == SignaturePolicy::Require {
-                does_url_exist(
+                let has_signature = does_url_exist(
                     self.client.clone(),
                     self.gh_api_client.clone(),
                     &self.signature_url,
                 )
                 .await
                 .map_err(|_| FetchError::MissingSignature)?;
+
+                if !has_signature {
+                    return Err(FetchError::MissingSignature);
+                }
             }
 
             does_url_exist(
@@ -148,9 +154,9 @@ Quickinstall does not support these targets, it only supports targets supported
 by rust officially."#,
             );
         } else if self.is_supported_v.get().copied() != Some(false) {
-            tokio::spawn(async move {
+            spawn(async move {
                 if let Err(err) = self.report().await {
-                    warn!(
+                    debug!(
                         "Failed to send quickinstall report for package {}: {err}",
                         self.package
                     )
@@ -196,6 +202,7 @@ by rust officially."#,
             self.package_url.clone(),
             data_verifier.as_mut(),
         )
+        .with_sha256()
         .and_extract(self.pkg_fmt(), dst)
         .await?;
         trace!("validating signature (if any)");
@@ -216,7 +223,7 @@ by rust officially."#,
     fn target_meta(&self) -> PkgMeta {
         let mut meta = self.target_data.meta.clone();
         meta.pkg_fmt = Some(self.pkg_fmt());
-        meta.bin_dir = Some("{ bin }{ binary-ext }".to_string());
+        meta.bin_dir = Some("{ bin }{ binary-ext }".into());
         meta
     }
 
@@ -224,6 +231,10 @@ by rust officially."#,
         CompactString::from("QuickInstall")
     }
 
+    fn package_url(&self) -> Option<&Url> {
+        Some(&self.package_url)
+    }
+
     fn fetcher_name(&self) -> &'static str {
         "QuickInstall"
     }
@@ -232,6 +243,10 @@ by rust officially."#,
         true
     }
 
+    fn strategy(&self) -> Strategy {
+        Strategy::QuickInstall
+    }
+
     fn target(&self) -> &str {
         &self.target_data.target
     }
@@ -255,7 +270,16 @@ impl QuickInstall {
         let url = self.stats_url.clone();
         debug!("Sending installation report to quickinstall ({url})");
 
-        self.client.request(Method::HEAD, url).send(true).await?;
+        // Bypass the rate limit of `Client`, so that the report never delays
+        // the requests needed to install the crate.
+        self.client
+            .get_inner()
+            .head(url)
+            .timeout(STATS_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(RemoteError::from)?;
 
         Ok(())
     }
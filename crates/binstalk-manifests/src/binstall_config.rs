@@ -0,0 +1,154 @@
+//! Binstall's `config.toml`
+//!
+//! This manifest stores user-level defaults for the options of
+//! `cargo-binstall`, e.g. in `~/.config/binstall/config.toml`.
+//!
+//! Options specified on the command line take precedence over it.
+
+use std::{
+    fmt,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use compact_str::CompactString;
+use fs_lock::FileLock;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{cargo_config::ConfigLoadError, cargo_toml_binstall::Strategy};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BinstallConfig {
+    /// Strategies to use, in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategies: Option<Vec<Strategy>>,
+    /// Strategies to remove from `strategies`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_strategies: Option<Vec<Strategy>>,
+    /// Targets to look for binaries, in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub targets: Option<Vec<CompactString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<CompactString>,
+    /// Proxy for https connections, used if `HTTPS_PROXY` is not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<CompactString>,
+    /// Installation root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+    /// Install without asking for confirmation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_confirm: Option<bool>,
+    /// Hosts to trust to download packages from, in addition to github.com
+    /// and gitlab.com.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_hosts: Option<Vec<CompactString>>,
+    /// Mirror to download GitHub release artifacts from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github_mirror: Option<Url>,
+    /// Send stats of crates looked up on quickinstall, off by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_quickinstall_stats: Option<bool>,
+}
+
+impl BinstallConfig {
+    /// * `dir` - path to the dir where the config.toml is located, relative
+    ///           `root` is joined with it.
+    pub fn load_from_reader<R: io::Read>(
+        mut reader: R,
+        dir: &Path,
+    ) -> Result<Self, ConfigLoadError> {
+        fn inner(reader: &mut dyn io::Read, dir: &Path) -> Result<BinstallConfig, ConfigLoadError> {
+            let mut vec = Vec::new();
+            reader.read_to_end(&mut vec)?;
+
+            if vec.is_empty() {
+                Ok(Default::default())
+            } else {
+                let mut config: BinstallConfig = toml_edit::de::from_slice(&vec)?;
+
+                if let Some(root) = config.root.as_mut() {
+                    if root.is_relative() {
+                        *root = dir.join(&root);
+                    }
+                }
+
+                Ok(config)
+            }
+        }
+
+        inner(&mut reader, dir)
+    }
+
+    /// Return the default config if `path` does not exist.
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, ConfigLoadError> {
+        fn inner(path: &Path) -> Result<BinstallConfig, ConfigLoadError> {
+            match File::open(path) {
+                Ok(file) => {
+                    let file = FileLock::new_shared(file)?;
+                    // Any regular file must have a parent dir
+                    BinstallConfig::load_from_reader(file, path.parent().unwrap())
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        inner(path.as_ref())
+    }
+}
+
+/// Format the config as toml.
+impl fmt::Display for BinstallConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&toml_edit::ser::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    const CONFIG: &str = r#"
+strategies = ["crate-meta-data", "compile"]
+targets = ["x86_64-unknown-linux-musl"]
+github-token = "ghp_xxx"
+proxy = "host:port"
+root = "relative-root"
+no-confirm = true
+trusted-hosts = ["example.com"]
+github-mirror = "https://mirror.example.com/github"
+send-quickinstall-stats = true
+    "#;
+
+    #[test]
+    fn test_loading() {
+        let config =
+            BinstallConfig::load_from_reader(Cursor::new(&CONFIG), Path::new("dir")).unwrap();
+
+        assert_eq!(
+            config,
+            BinstallConfig {
+                strategies: Some(vec![Strategy::CrateMetaData, Strategy::Compile]),
+                disable_strategies: None,
+                targets: Some(vec!["x86_64-unknown-linux-musl".into()]),
+                github_token: Some("ghp_xxx".into()),
+                proxy: Some("host:port".into()),
+                root: Some(Path::new("dir").join("relative-root")),
+                no_confirm: Some(true),
+                trusted_hosts: Some(vec!["example.com".into()]),
+                github_mirror: Some(Url::parse("https://mirror.example.com/github").unwrap()),
+                send_quickinstall_stats: Some(true),
+            }
+        );
+
+        let config = config.to_string();
+        assert!(config.contains(r#"strategies = ["crate-meta-data", "compile"]"#));
+        assert!(!config.contains("disable-strategies"));
+    }
+}
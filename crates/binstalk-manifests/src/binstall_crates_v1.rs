@@ -72,6 +72,35 @@ pub fn write_to(file: &mut FileLock, iter: &mut dyn Iterator<Item = Data>) -> Re
     Ok(())
 }
 
+/// Load records from `path` without modifying it, under a shared lock so
+/// that it can be read while other processes are reading it as well.
+///
+/// Return empty records if `path` does not exist.
+pub fn read_from_path(path: impl AsRef<Path>) -> Result<BTreeSet<Data>, Error> {
+    let mut data = BTreeSet::default();
+
+    match fs::File::open(path.as_ref()) {
+        Ok(file) => read_to(&mut FileLock::new_shared(file)?, &mut data)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+        Err(err) => return Err(err.into()),
+    }
+
+    Ok(data)
+}
+
+fn read_to(file: &mut FileLock, data: &mut BTreeSet<Data>) -> Result<(), Error> {
+    let reader = io::BufReader::with_capacity(BUFFER_SIZE, file);
+    let stream_deser = serde_json::Deserializer::from_reader(reader).into_iter();
+
+    for res in stream_deser {
+        let item = res?;
+
+        data.replace(item);
+    }
+
+    Ok(())
+}
+
 pub fn default_path() -> Result<PathBuf, Error> {
     let dir = cargo_home()?.join("binstall");
 
@@ -151,25 +180,12 @@ pub struct Records {
 }
 
 impl Records {
-    fn load_impl(&mut self) -> Result<(), Error> {
-        let reader = io::BufReader::with_capacity(BUFFER_SIZE, &mut self.file);
-        let stream_deser = serde_json::Deserializer::from_reader(reader).into_iter();
-
-        for res in stream_deser {
-            let item = res?;
-
-            self.data.replace(item);
-        }
-
-        Ok(())
-    }
-
     pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
         let mut this = Self {
             file: FileLock::new_exclusive(create_if_not_exist(path.as_ref())?)?,
             data: BTreeSet::default(),
         };
-        this.load_impl()?;
+        read_to(&mut this.file, &mut this.data)?;
         Ok(this)
     }
 
@@ -270,6 +286,10 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into(), "2".into()],
+                package_sha256: None,
+                bins_sha256: Default::default(),
+                package_source: None,
+                install_time: None,
             },
             CrateInfo {
                 name: "b".into(),
@@ -278,6 +298,10 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into(), "2".into()],
+                package_sha256: None,
+                bins_sha256: Default::default(),
+                package_source: None,
+                install_time: None,
             },
             CrateInfo {
                 name: "a".into(),
@@ -286,6 +310,10 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into()],
+                package_sha256: None,
+                bins_sha256: Default::default(),
+                package_source: None,
+                install_time: None,
             },
         ];
 
@@ -316,11 +344,59 @@ mod test {
             source: CrateSource::cratesio_registry(),
             target,
             bins: vec!["1".into(), "2".into()],
+            package_sha256: None,
+            bins_sha256: Default::default(),
+            package_source: None,
+            install_time: None,
         };
         append_to_path(path, [new_metadata.clone()]).unwrap();
         metadata_set.insert(new_metadata);
 
         let records = Records::load_from_path(path).unwrap();
         assert_records_eq!(&records, &metadata_set);
+        drop(records);
+
+        assert_records_eq!(&read_from_path(path).unwrap(), &metadata_set);
+    }
+
+    #[test]
+    fn url_source_test() {
+        let url = url::Url::parse("https://example.com/tool-v1.2.3.tar.gz").unwrap();
+
+        let metadata = CrateInfo {
+            name: "tool".into(),
+            version_req: "1.2.3".into(),
+            current_version: Version::new(1, 2, 3),
+            source: CrateSource::url(url.clone()),
+            target: TARGET.into(),
+            bins: vec!["tool".into()],
+            package_sha256: None,
+            bins_sha256: Default::default(),
+            package_source: None,
+            install_time: None,
+        };
+
+        let json = serde_json::to_value(&metadata).unwrap();
+        // Stored as its own source type, so that it is never mistaken for
+        // a registry.
+        assert_eq!(json["source"]["source_type"], "Url");
+        assert_eq!(
+            json["source"]["url"],
+            "https://example.com/tool-v1.2.3.tar.gz"
+        );
+
+        let metadata: CrateInfo = serde_json::from_value(json).unwrap();
+        assert!(!metadata.source.is_cargo_source());
+        assert_eq!(*metadata.source.url, url);
+    }
+
+    #[test]
+    fn read_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("binstall/crates-v1.json");
+
+        assert!(read_from_path(&path).unwrap().is_empty());
+        // The file is not created
+        assert!(!path.exists());
     }
 }
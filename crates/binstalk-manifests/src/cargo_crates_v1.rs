@@ -29,7 +29,7 @@ use crate::helpers::create_if_not_exist;
 use super::crate_info::CrateInfo;
 
 mod crate_version_source;
-use crate_version_source::*;
+pub(crate) use crate_version_source::*;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct CratesToml<'a> {
@@ -121,9 +121,13 @@ impl CratesToml<'_> {
             let mut c1 = CratesToml::load_from_reader(&mut *file)?;
 
             for metadata in iter {
+                // Cargo fails to parse sources it does not understand.
+                let source = match Source::from_crate_source(&metadata.source) {
+                    Some(source) => source,
+                    None => continue,
+                };
                 let name = &metadata.name;
                 let version = &metadata.current_version;
-                let source = Source::from(&metadata.source);
 
                 c1.remove(name);
                 c1.v1.push((
@@ -235,6 +239,10 @@ mod tests {
                 source: CrateSource::cratesio_registry(),
                 target: TARGET.into(),
                 bins: vec!["cargo-binstall".into()],
+                package_sha256: None,
+                bins_sha256: Default::default(),
+                package_source: None,
+                install_time: None,
             }],
         )
         .unwrap();
@@ -261,6 +269,10 @@ mod tests {
                 source: CrateSource::cratesio_registry(),
                 target: TARGET.into(),
                 bins: vec!["cargo-binstall".into()],
+                package_sha256: None,
+                bins_sha256: Default::default(),
+                package_source: None,
+                install_time: None,
             }],
         )
         .unwrap();
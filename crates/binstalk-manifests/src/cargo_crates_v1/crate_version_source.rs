@@ -17,21 +17,24 @@ pub struct CrateVersionSource {
     pub source: Source<'static>,
 }
 
-impl From<&CrateInfo> for CrateVersionSource {
-    fn from(metadata: &CrateInfo) -> Self {
+impl CrateVersionSource {
+    /// Return `None` if the source of `metadata` is not understood by
+    /// cargo, see [`CrateSource::is_cargo_source`].
+    pub fn from_crate_info(metadata: &CrateInfo) -> Option<Self> {
         use SourceType::*;
 
         let url = metadata.source.url.clone();
 
-        super::CrateVersionSource {
+        Some(Self {
             name: metadata.name.clone(),
             version: metadata.current_version.clone(),
             source: match metadata.source.source_type {
                 Git => Source::Git(url),
                 Path => Source::Path(url),
                 Registry => Source::Registry(url),
+                SourceType::Url => return None,
             },
-        }
+        })
     }
 }
 
@@ -48,16 +51,19 @@ impl Source<'static> {
     }
 }
 
-impl<'a> From<&'a CrateSource> for Source<'a> {
-    fn from(source: &'a CrateSource) -> Self {
+impl<'a> Source<'a> {
+    /// Return `None` if `source` is not understood by cargo, see
+    /// [`CrateSource::is_cargo_source`].
+    pub fn from_crate_source(source: &'a CrateSource) -> Option<Self> {
         use SourceType::*;
 
         let url = MaybeOwned::Borrowed(source.url.as_ref());
 
         match source.source_type {
-            Git => Self::Git(url),
-            Path => Self::Path(url),
-            Registry => Self::Registry(url),
+            Git => Some(Self::Git(url)),
+            Path => Some(Self::Path(url)),
+            Registry => Some(Self::Registry(url)),
+            SourceType::Url => None,
         }
     }
 }
@@ -0,0 +1,218 @@
+//! Cargo's `.crates2.json` manifest.
+//!
+//! This manifest is the successor of [`.crates.toml`][crate::cargo_crates_v1], it records more
+//! information about the installed crates, such as the target and the version requirement.
+//!
+//! Cargo keeps it in sync with `.crates.toml`, but Binstall writes to it as well when installing
+//! a crate so that the extra information is accurate.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Seek},
+    iter::IntoIterator,
+    path::{Path, PathBuf},
+};
+
+use compact_str::CompactString;
+use fs_lock::FileLock;
+use home::cargo_home;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    cargo_crates_v1::CrateVersionSource, crate_info::CrateInfo, helpers::create_if_not_exist,
+};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CratesJson {
+    installs: BTreeMap<String, InstallInfo>,
+
+    /// Preserve fields unknown to binstall.
+    #[serde(flatten)]
+    other: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct InstallInfo {
+    version_req: Option<CompactString>,
+    bins: Vec<CompactString>,
+    features: Vec<CompactString>,
+    all_features: bool,
+    no_default_features: bool,
+    profile: CompactString,
+    target: Option<CompactString>,
+    rustc: Option<CompactString>,
+
+    /// Preserve fields unknown to binstall.
+    #[serde(flatten)]
+    other: BTreeMap<String, Value>,
+}
+
+impl CratesJson {
+    pub fn default_path() -> Result<PathBuf, CratesJsonParseError> {
+        Ok(cargo_home()?.join(".crates2.json"))
+    }
+
+    pub fn load_from_reader<R: io::Read>(mut reader: R) -> Result<Self, CratesJsonParseError> {
+        fn inner(reader: &mut dyn io::Read) -> Result<CratesJson, CratesJsonParseError> {
+            let mut vec = Vec::new();
+            reader.read_to_end(&mut vec)?;
+
+            if vec.is_empty() {
+                Ok(CratesJson::default())
+            } else {
+                Ok(serde_json::from_slice(&vec)?)
+            }
+        }
+
+        inner(&mut reader)
+    }
+
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, CratesJsonParseError> {
+        let file = FileLock::new_shared(File::open(path)?)?;
+        Self::load_from_reader(file)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.installs.retain(|s, _info| {
+            s.split_once(' ')
+                .map(|(crate_name, _rest)| crate_name != name)
+                .unwrap_or_default()
+        });
+    }
+
+    pub fn write_to_file(&self, file: &mut File) -> Result<(), CratesJsonParseError> {
+        serde_json::to_writer(&mut *file, self)?;
+        let pos = file.stream_position()?;
+        file.set_len(pos)?;
+
+        Ok(())
+    }
+
+    pub fn append_to_file<'a, Iter>(file: &mut File, iter: Iter) -> Result<(), CratesJsonParseError>
+    where
+        Iter: IntoIterator<Item = &'a CrateInfo>,
+    {
+        fn inner(
+            file: &mut File,
+            iter: &mut dyn Iterator<Item = &CrateInfo>,
+        ) -> Result<(), CratesJsonParseError> {
+            let mut c2 = CratesJson::load_from_reader(&mut *file)?;
+
+            for metadata in iter {
+                // Cargo fails to parse sources it does not understand.
+                let cvs = match CrateVersionSource::from_crate_info(metadata) {
+                    Some(cvs) => cvs,
+                    None => continue,
+                };
+
+                c2.remove(&metadata.name);
+                c2.installs.insert(
+                    cvs.to_string(),
+                    InstallInfo {
+                        version_req: Some(metadata.version_req.clone()),
+                        bins: metadata.bins.clone(),
+                        profile: "release".into(),
+                        target: Some(metadata.target.clone()),
+                        ..Default::default()
+                    },
+                );
+            }
+
+            file.rewind()?;
+            c2.write_to_file(file)?;
+
+            Ok(())
+        }
+
+        inner(file, &mut iter.into_iter())
+    }
+
+    pub fn append_to_path<'a, Iter>(
+        path: impl AsRef<Path>,
+        iter: Iter,
+    ) -> Result<(), CratesJsonParseError>
+    where
+        Iter: IntoIterator<Item = &'a CrateInfo>,
+    {
+        let mut file = FileLock::new_exclusive(create_if_not_exist(path.as_ref())?)?;
+        Self::append_to_file(&mut file, iter)
+    }
+
+    pub fn append<'a, Iter>(iter: Iter) -> Result<(), CratesJsonParseError>
+    where
+        Iter: IntoIterator<Item = &'a CrateInfo>,
+    {
+        Self::append_to_path(Self::default_path()?, iter)
+    }
+}
+
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum CratesJsonParseError {
+    #[error("I/O Error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to (de)serialize json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crate_info::CrateSource;
+
+    use detect_targets::TARGET;
+    use semver::Version;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_empty() {
+        let tempdir = TempDir::new().unwrap();
+        let path = tempdir.path().join(".crates2.json");
+
+        let crate_info = |version| CrateInfo {
+            name: "cargo-binstall".into(),
+            version_req: "*".into(),
+            current_version: version,
+            source: CrateSource::cratesio_registry(),
+            target: TARGET.into(),
+            bins: vec!["cargo-binstall".into()],
+            package_sha256: None,
+            bins_sha256: Default::default(),
+            package_source: None,
+            install_time: None,
+        };
+
+        CratesJson::append_to_path(&path, &[crate_info(Version::new(0, 11, 1))]).unwrap();
+
+        // Update
+        CratesJson::append_to_path(&path, &[crate_info(Version::new(0, 12, 0))]).unwrap();
+
+        let crates = CratesJson::load_from_path(&path).unwrap();
+
+        assert_eq!(crates.installs.len(), 1);
+
+        let (cvs, info) = crates.installs.into_iter().next().unwrap();
+        assert_eq!(
+            cvs,
+            "cargo-binstall 0.12.0 (registry+https://github.com/rust-lang/crates.io-index)"
+        );
+        assert_eq!(info.target.as_deref(), Some(TARGET));
+    }
+
+    #[test]
+    fn test_loading() {
+        let raw_data = br#"{"installs":{"ripgrep 13.0.0 (registry+https://github.com/rust-lang/crates.io-index)":{"version_req":null,"bins":["rg"],"features":[],"all_features":false,"no_default_features":false,"profile":"release","target":"x86_64-unknown-linux-gnu","rustc":"rustc 1.70.0 (90c541806 2023-05-31)\nbinary: rustc\n"}}}"#;
+
+        let crates = CratesJson::load_from_reader(raw_data.as_slice()).unwrap();
+        assert_eq!(crates.installs.len(), 1);
+
+        let mut buffer = Vec::new();
+        serde_json::to_writer(&mut buffer, &crates).unwrap();
+        assert_eq!(buffer, raw_data);
+    }
+}
@@ -2,7 +2,7 @@ use std::{
     collections::BTreeMap,
     fs,
     io::{self, Seek},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use fs_lock::FileLock;
@@ -12,6 +12,7 @@ use thiserror::Error as ThisError;
 use crate::{
     binstall_crates_v1::{Error as BinstallCratesV1Error, Records as BinstallCratesV1Records},
     cargo_crates_v1::{CratesToml, CratesTomlParseError},
+    cargo_crates_v2::{CratesJson, CratesJsonParseError},
     crate_info::CrateInfo,
     CompactString, Version,
 };
@@ -27,6 +28,10 @@ pub enum ManifestsError {
     #[diagnostic(transparent)]
     CargoManifestV1(#[from] CratesTomlParseError),
 
+    #[error("failed to parse cargo v2 manifest: {0}")]
+    #[diagnostic(transparent)]
+    CargoManifestV2(#[from] CratesJsonParseError),
+
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 }
@@ -34,6 +39,35 @@ pub enum ManifestsError {
 pub struct Manifests {
     binstall: BinstallCratesV1Records,
     cargo_crates_v1: FileLock,
+    cargo_crates_v2_path: PathBuf,
+}
+
+/// Advisory lock on a cargo root, held while installing binaries to it and
+/// updating its manifests, so that binstall processes sharing the same root
+/// do not race with each other.
+#[derive(Debug)]
+pub struct InstallLock {
+    _lock: FileLock,
+}
+
+impl InstallLock {
+    /// Block until the lock is acquired, it is released on drop.
+    ///
+    /// Note that this operation is blocking, and should not be called in async contexts.
+    pub fn acquire(cargo_roots: &Path) -> Result<Self, ManifestsError> {
+        let lock_path = cargo_roots.join("binstall/.install-lock");
+        fs::create_dir_all(lock_path.parent().unwrap())?;
+
+        let file = fs::File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path)?;
+
+        Ok(Self {
+            _lock: FileLock::new_exclusive(file)?,
+        })
+    }
 }
 
 impl Manifests {
@@ -57,6 +91,7 @@ impl Manifests {
         Ok(Self {
             binstall,
             cargo_crates_v1,
+            cargo_crates_v2_path: cargo_roots.join(".crates2.json"),
         })
     }
 
@@ -81,7 +116,15 @@ impl Manifests {
     pub fn update(mut self, metadata_vec: Vec<CrateInfo>) -> Result<(), ManifestsError> {
         self.rewind_cargo_crates_v1()?;
 
-        CratesToml::append_to_file(&mut self.cargo_crates_v1, &metadata_vec)?;
+        // Cargo fails to parse the manifests with sources it does not understand.
+        let cargo_metadata = metadata_vec
+            .iter()
+            .filter(|metadata| metadata.source.is_cargo_source());
+
+        CratesToml::append_to_file(&mut self.cargo_crates_v1, cargo_metadata.clone())?;
+        // Exclusive lock on .crates.toml is still held, so it is safe
+        // to update .crates2.json.
+        CratesJson::append_to_path(&self.cargo_crates_v2_path, cargo_metadata)?;
 
         for metadata in metadata_vec {
             self.binstall.replace(metadata);
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, path::Path};
 
 use base16::{decode as decode_base16, encode_lower as encode_base16};
 use binstalk_downloader::{
@@ -6,7 +6,7 @@ use binstalk_downloader::{
     download::{DataVerifier, Download},
     remote::{Client, Url},
 };
-use binstalk_types::cargo_toml_binstall::{Meta, TarBasedFmt};
+use binstalk_types::cargo_toml_binstall::{Meta, PkgFmt, TarBasedFmt};
 use cargo_toml_workspace::cargo_toml::Manifest;
 use compact_str::{format_compact, CompactString, ToCompactString};
 use leon::{Template, Values};
@@ -83,6 +83,35 @@ pub(super) async fn parse_manifest(
     }
 }
 
+/// Download the crate tarball at `crate_url` and extract it into `dst`.
+///
+/// Return SHA256 checksum of the tarball, encoded in base16.
+#[instrument(skip(dst))]
+pub(super) async fn download_crate(
+    client: Client,
+    crate_url: Url,
+    MatchedVersion { cksum, .. }: MatchedVersion,
+    dst: &Path,
+) -> Result<CompactString, RegistryError> {
+    debug!("Downloading crate from: {crate_url} to {}", dst.display());
+
+    let extracted_files = Download::new(client, crate_url)
+        .with_sha256()
+        .and_extract(PkgFmt::Tgz, dst)
+        .await?;
+
+    // The checksum is computed since `Download::with_sha256` is called
+    let actual = extracted_files.sha256().unwrap();
+    if !actual.eq_ignore_ascii_case(&cksum) {
+        Err(RegistryError::UnmatchedChecksum {
+            expected: cksum.into(),
+            actual: actual.into(),
+        })
+    } else {
+        Ok(actual.into())
+    }
+}
+
 /// Return components of crate prefix
 pub(super) fn crate_prefix_components(
     crate_name: &str,
@@ -167,6 +196,7 @@ impl MatchedVersion {
     pub(super) fn find(
         it: &mut dyn Iterator<Item = Result<RegistryIndexEntry, JsonError>>,
         version_req: &VersionReq,
+        include_prereleases: bool,
     ) -> Result<Self, RegistryError> {
         let mut ret = Option::<(Self, Version)>::None;
 
@@ -185,7 +215,11 @@ impl MatchedVersion {
             };
 
             // Filter by version match
-            if !version_req.matches(&ver) {
+            let matched = version_req.matches(&ver)
+                || (include_prereleases
+                    && !ver.pre.is_empty()
+                    && version_req.matches(&Version::new(ver.major, ver.minor, ver.patch)));
+            if !matched {
                 continue;
             }
 
@@ -209,3 +243,37 @@ impl MatchedVersion {
             })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INDEX: &str = r#"
+{"name":"foo","vers":"1.0.0","yanked":false,"cksum":"a"}
+{"name":"foo","vers":"1.1.0","yanked":false,"cksum":"b"}
+{"name":"foo","vers":"1.2.0","yanked":true,"cksum":"c"}
+{"name":"foo","vers":"2.0.0-beta.1","yanked":false,"cksum":"d"}
+"#;
+
+    fn find(version_req: &str, include_prereleases: bool) -> Option<CompactString> {
+        MatchedVersion::find(
+            &mut serde_json::Deserializer::from_str(INDEX).into_iter(),
+            &version_req.parse().unwrap(),
+            include_prereleases,
+        )
+        .ok()
+        .map(|matched| matched.version)
+    }
+
+    #[test]
+    fn test_find_matched_version() {
+        assert_eq!(find("*", false).as_deref(), Some("1.1.0"));
+        assert_eq!(find("*", true).as_deref(), Some("2.0.0-beta.1"));
+        assert_eq!(find("^1", true).as_deref(), Some("1.1.0"));
+        assert_eq!(find("=1.2.0", false), None);
+        assert_eq!(
+            find("=2.0.0-beta.1", false).as_deref(),
+            Some("2.0.0-beta.1")
+        );
+    }
+}
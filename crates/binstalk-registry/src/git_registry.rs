@@ -79,6 +79,7 @@ impl GitRegistry {
         crate_name: &str,
         (c1, c2): &(CompactString, Option<CompactString>),
         version_req: &VersionReq,
+        include_prereleases: bool,
     ) -> Result<MatchedVersion, RegistryError> {
         let mut path = PathBuf::with_capacity(128);
         path.push(&**c1);
@@ -94,16 +95,18 @@ impl GitRegistry {
         MatchedVersion::find(
             &mut JsonDeserializer::from_slice(&crate_versions).into_iter(),
             version_req,
+            include_prereleases,
         )
     }
 
-    #[instrument]
-    pub async fn fetch_crate_matched(
+    /// Find the version of crate `name` matching `version_req` and the url
+    /// to download it.
+    pub(super) async fn find_crate_dl_url(
         &self,
-        client: Client,
         name: &str,
         version_req: &VersionReq,
-    ) -> Result<Manifest<Meta>, RegistryError> {
+        include_prereleases: bool,
+    ) -> Result<(MatchedVersion, Url), RegistryError> {
         let crate_prefix = crate_prefix_components(name)?;
         let crate_name = name.to_compact_string();
         let version_req = version_req.clone();
@@ -113,7 +116,7 @@ impl GitRegistry {
         // Cancel git operation if the future is cancelled (dropped).
         let cancel_on_drop = cancellation_token.clone().cancel_on_drop();
 
-        let (matched_version, dl_url) = spawn_blocking(move || {
+        let ret = spawn_blocking(move || {
             let GitIndex {
                 _tempdir: _,
                 repo,
@@ -123,8 +126,13 @@ impl GitRegistry {
                 .git_index
                 .get_or_try_init(|| GitIndex::new(this.0.url.clone(), cancellation_token))?;
 
-            let matched_version =
-                Self::find_crate_matched_ver(repo, &crate_name, &crate_prefix, &version_req)?;
+            let matched_version = Self::find_crate_matched_ver(
+                repo,
+                &crate_name,
+                &crate_prefix,
+                &version_req,
+                include_prereleases,
+            )?;
 
             let url = Url::parse(&render_dl_template(
                 dl_template,
@@ -140,6 +148,21 @@ impl GitRegistry {
         // Git operation done, disarm it
         cancel_on_drop.disarm();
 
+        Ok(ret)
+    }
+
+    #[instrument]
+    pub async fn fetch_crate_matched(
+        &self,
+        client: Client,
+        name: &str,
+        version_req: &VersionReq,
+        include_prereleases: bool,
+    ) -> Result<Manifest<Meta>, RegistryError> {
+        let (matched_version, dl_url) = self
+            .find_crate_dl_url(name, version_req, include_prereleases)
+            .await?;
+
         parse_manifest(client, name, dl_url, matched_version).await
     }
 }
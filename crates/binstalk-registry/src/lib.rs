@@ -1,6 +1,6 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
-use std::{io, str::FromStr, sync::Arc};
+use std::{io, path::Path, str::FromStr, sync::Arc};
 
 use base16::DecodeError as Base16DecodeError;
 use binstalk_downloader::{
@@ -12,7 +12,7 @@ use cargo_toml_workspace::cargo_toml::{Error as CargoTomlError, Manifest};
 use compact_str::CompactString;
 use leon::{ParseError, RenderError};
 use miette::Diagnostic;
-use semver::VersionReq;
+use semver::{Comparator, Op, Version, VersionReq};
 use serde_json::Error as JsonError;
 use thiserror::Error as ThisError;
 use tokio::task;
@@ -175,26 +175,70 @@ impl Registry {
 
     /// Fetch the latest crate with `crate_name` and with version matching
     /// `version_req`.
+    ///
+    /// Yanked versions are skipped. Pre-releases are only considered if
+    /// `version_req` explicitly matches them, or if `include_prereleases`
+    /// is `true`, in which case a pre-release is considered if the version
+    /// it precedes matches `version_req`.
     pub async fn fetch_crate_matched(
         &self,
         client: Client,
         crate_name: &str,
         version_req: &VersionReq,
+        include_prereleases: bool,
     ) -> Result<Manifest<Meta>, RegistryError> {
         match self {
             Self::Sparse(sparse_registry) => {
                 sparse_registry
-                    .fetch_crate_matched(client, crate_name, version_req)
+                    .fetch_crate_matched(client, crate_name, version_req, include_prereleases)
                     .await
             }
             #[cfg(feature = "git")]
             Self::Git(git_registry) => {
                 git_registry
-                    .fetch_crate_matched(client, crate_name, version_req)
+                    .fetch_crate_matched(client, crate_name, version_req, include_prereleases)
                     .await
             }
         }
     }
+
+    /// Download the source of crate `crate_name` of `version` and extract it
+    /// into `dst`, i.e. into `{dst}/{crate_name}-{version}`.
+    ///
+    /// Return SHA256 checksum of the crate tarball, encoded in base16.
+    pub async fn download_crate(
+        &self,
+        client: Client,
+        crate_name: &str,
+        version: &Version,
+        dst: &Path,
+    ) -> Result<CompactString, RegistryError> {
+        let version_req = VersionReq {
+            comparators: vec![Comparator {
+                op: Op::Exact,
+                major: version.major,
+                minor: Some(version.minor),
+                patch: Some(version.patch),
+                pre: version.pre.clone(),
+            }],
+        };
+
+        let (matched_version, dl_url) = match self {
+            Self::Sparse(sparse_registry) => {
+                sparse_registry
+                    .find_crate_dl_url(&client, crate_name, &version_req, true)
+                    .await?
+            }
+            #[cfg(feature = "git")]
+            Self::Git(git_registry) => {
+                git_registry
+                    .find_crate_dl_url(crate_name, &version_req, true)
+                    .await?
+            }
+        };
+
+        download_crate(client, dl_url, matched_version, dst).await
+    }
 }
 
 impl FromStr for Registry {
@@ -243,7 +287,7 @@ mod test {
         let crate_name = "cargo-binstall";
         let version_req = &VersionReq::parse("=1.0.0").unwrap();
         let manifest_from_sparse = sparse_registry
-            .fetch_crate_matched(client.clone(), crate_name, version_req)
+            .fetch_crate_matched(client.clone(), crate_name, version_req, false)
             .await
             .unwrap();
 
@@ -277,12 +321,12 @@ mod test {
         let crate_name = "cargo-binstall";
         let version_req = &VersionReq::parse("=1.0.0").unwrap();
         let manifest_from_git = git_registry
-            .fetch_crate_matched(client.clone(), crate_name, version_req)
+            .fetch_crate_matched(client.clone(), crate_name, version_req, false)
             .await
             .unwrap();
 
         let manifest_from_cratesio_api = Registry::default()
-            .fetch_crate_matched(client, crate_name, version_req)
+            .fetch_crate_matched(client, crate_name, version_req, false)
             .await
             .unwrap();
 
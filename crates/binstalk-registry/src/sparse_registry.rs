@@ -1,11 +1,14 @@
-use binstalk_downloader::remote::{Client, Error as RemoteError};
+use binstalk_downloader::{
+    bytes::Bytes,
+    remote::{header::ETAG, Client, Error as RemoteError, StatusCode},
+};
 use binstalk_types::cargo_toml_binstall::Meta;
 use cargo_toml_workspace::cargo_toml::Manifest;
 use compact_str::CompactString;
 use semver::VersionReq;
 use serde_json::Deserializer as JsonDeserializer;
 use tokio::sync::OnceCell;
-use tracing::instrument;
+use tracing::{debug, instrument};
 use url::Url;
 
 use crate::{
@@ -49,6 +52,7 @@ impl SparseRegistry {
         crate_name: &str,
         (c1, c2): &(CompactString, Option<CompactString>),
         version_req: &VersionReq,
+        include_prereleases: bool,
     ) -> Result<MatchedVersion, RegistryError> {
         {
             let mut path = url.path_segments_mut().unwrap();
@@ -61,38 +65,73 @@ impl SparseRegistry {
             path.push(&crate_name.to_lowercase());
         }
 
-        let body = client
-            .get(url)
-            .send(true)
+        let body = Self::fetch_index_file(client, url)
             .await
             .map_err(|e| match e {
                 RemoteError::Http(e) if e.is_status() => RegistryError::NotFound(crate_name.into()),
                 e => e.into(),
-            })?
-            .bytes()
-            .await
-            .map_err(RegistryError::from)?;
+            })?;
         MatchedVersion::find(
             &mut JsonDeserializer::from_slice(&body).into_iter(),
             version_req,
+            include_prereleases,
         )
     }
 
-    #[instrument]
-    pub async fn fetch_crate_matched(
+    /// Fetch the index file at `url`, reusing the copy in the download
+    /// cache of `client` if it is not modified since it was cached.
+    async fn fetch_index_file(client: &Client, url: Url) -> Result<Bytes, RemoteError> {
+        let cache = client.download_cache();
+        let cached = match cache {
+            Some(cache) => cache.get_etag_response(&url).await,
+            None => None,
+        };
+
+        let mut request_builder = client.get(url.clone());
+        if let Some((etag, _body)) = &cached {
+            request_builder = request_builder.header("If-None-Match", etag);
+        }
+
+        let response = request_builder.send(false).await?;
+
+        if let (StatusCode::NOT_MODIFIED, Some((_etag, body))) = (response.status(), cached) {
+            debug!("Using cached index file '{url}'");
+            return Ok(body);
+        }
+
+        let response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(CompactString::from);
+        let body = response.bytes().await?;
+
+        if let (Some(cache), Some(etag)) = (cache, etag) {
+            cache.put_etag_response(&url, &etag, &body).await;
+        }
+
+        Ok(body)
+    }
+
+    /// Find the version of crate `crate_name` matching `version_req` and
+    /// the url to download it.
+    pub(super) async fn find_crate_dl_url(
         &self,
-        client: Client,
+        client: &Client,
         crate_name: &str,
         version_req: &VersionReq,
-    ) -> Result<Manifest<Meta>, RegistryError> {
+        include_prereleases: bool,
+    ) -> Result<(MatchedVersion, Url), RegistryError> {
         let crate_prefix = crate_prefix_components(crate_name)?;
-        let dl_template = self.get_dl_template(&client).await?;
+        let dl_template = self.get_dl_template(client).await?;
         let matched_version = Self::find_crate_matched_ver(
-            &client,
+            client,
             self.url.clone(),
             crate_name,
             &crate_prefix,
             version_req,
+            include_prereleases,
         )
         .await?;
         let dl_url = Url::parse(&render_dl_template(
@@ -102,6 +141,21 @@ impl SparseRegistry {
             &matched_version,
         )?)?;
 
+        Ok((matched_version, dl_url))
+    }
+
+    #[instrument]
+    pub async fn fetch_crate_matched(
+        &self,
+        client: Client,
+        crate_name: &str,
+        version_req: &VersionReq,
+        include_prereleases: bool,
+    ) -> Result<Manifest<Meta>, RegistryError> {
+        let (matched_version, dl_url) = self
+            .find_crate_dl_url(&client, crate_name, version_req, include_prereleases)
+            .await?;
+
         parse_manifest(client, crate_name, dl_url, matched_version).await
     }
 }
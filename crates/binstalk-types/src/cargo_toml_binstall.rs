@@ -2,9 +2,13 @@
 //!
 //! This manifest defines how a particular binary crate may be installed by Binstall.
 
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt, slice,
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 mod package_formats;
 #[doc(inline)]
@@ -19,26 +23,63 @@ pub struct Meta {
     pub binstall: Option<PkgMeta>,
 }
 
+/// Latest version of the metadata schema, see [`PkgMeta::meta_version`].
+pub const META_VERSION: u32 = 1;
+
+/// Strategies to use for binary discovery
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Attempt to download official pre-built artifacts using
+    /// information provided in `Cargo.toml`.
+    CrateMetaData,
+    /// Query third-party QuickInstall for the crates.
+    QuickInstall,
+    /// Build the crates from source using `cargo-build`.
+    Compile,
+}
+
 /// Metadata for binary installation use.
 ///
 /// Exposed via `[package.metadata]` in `Cargo.toml`
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", default)]
 pub struct PkgMeta {
+    /// Version of the metadata schema, [`META_VERSION`] if not specified.
+    ///
+    /// Metadata of a newer version is not understood by this version of
+    /// binstall and must not be used.
+    pub meta_version: Option<u32>,
+
     /// URL template for package downloads
-    pub pkg_url: Option<String>,
+    ///
+    /// A list of templates can be specified to try them in order.
+    pub pkg_url: Option<Templates>,
 
     /// Format for package downloads
     pub pkg_fmt: Option<PkgFmt>,
 
     /// Path template for binary files in packages
-    pub bin_dir: Option<String>,
+    ///
+    /// Set to `auto` to search the package for the binaries.
+    ///
+    /// A list of templates can be specified to use the first one matching
+    /// the binaries in the package.
+    pub bin_dir: Option<Templates>,
 
     /// Package signing configuration
     pub signing: Option<PkgSigning>,
 
+    /// Strategies to disable
+    pub disabled_strategies: Option<Box<[Strategy]>>,
+
     /// Target specific overrides
     pub overrides: BTreeMap<String, PkgOverride>,
+
+    /// Keys not known to this version of binstall, e.g. typos such as
+    /// `pkg_url`, which are otherwise ignored.
+    #[serde(flatten, skip_serializing, deserialize_with = "deserialize_keys")]
+    pub unknown_keys: BTreeSet<String>,
 }
 
 impl PkgMeta {
@@ -53,6 +94,20 @@ impl PkgMeta {
         if let Some(o) = &pkg_override.bin_dir {
             self.bin_dir = Some(o.clone());
         }
+        if let Some(o) = &pkg_override.signing {
+            self.signing = Some(o.clone());
+        }
+        if let Some(o) = &pkg_override.disabled_strategies {
+            self.disabled_strategies = Some(o.clone());
+        }
+    }
+
+    /// Return true if `strategy` is disabled in the metadata.
+    pub fn is_strategy_disabled(&self, strategy: Strategy) -> bool {
+        self.disabled_strategies
+            .as_deref()
+            .map(|disabled_strategies| disabled_strategies.contains(&strategy))
+            .unwrap_or(false)
     }
 
     /// Merge configuration overrides into object
@@ -63,6 +118,8 @@ impl PkgMeta {
         It: IntoIterator<Item = &'a PkgOverride> + Clone,
     {
         Self {
+            meta_version: self.meta_version,
+
             pkg_url: pkg_overrides
                 .clone()
                 .into_iter()
@@ -82,32 +139,124 @@ impl PkgMeta {
                 .or_else(|| self.bin_dir.clone()),
 
             signing: pkg_overrides
+                .clone()
                 .into_iter()
                 .find_map(|pkg_override| pkg_override.signing.clone())
                 .or_else(|| self.signing.clone()),
 
+            disabled_strategies: pkg_overrides
+                .into_iter()
+                .find_map(|pkg_override| pkg_override.disabled_strategies.clone())
+                .or_else(|| self.disabled_strategies.clone()),
+
             overrides: Default::default(),
+            unknown_keys: Default::default(),
         }
     }
 }
 
 /// Target specific overrides for binary installation
 ///
-/// Exposed via `[package.metadata.TARGET]` in `Cargo.toml`
+/// Exposed via `[package.metadata.binstall.overrides.TARGET]` in `Cargo.toml`,
+/// and merged over the base metadata when resolving for `TARGET`.
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", default)]
 pub struct PkgOverride {
     /// URL template override for package downloads
-    pub pkg_url: Option<String>,
+    pub pkg_url: Option<Templates>,
 
     /// Format override for package downloads
     pub pkg_fmt: Option<PkgFmt>,
 
     /// Path template override for binary files in packages
-    pub bin_dir: Option<String>,
+    pub bin_dir: Option<Templates>,
 
     /// Package signing configuration
     pub signing: Option<PkgSigning>,
+
+    /// Strategies to disable
+    pub disabled_strategies: Option<Box<[Strategy]>>,
+
+    /// Keys not known to this version of binstall, see
+    /// [`PkgMeta::unknown_keys`].
+    #[serde(flatten, skip_serializing, deserialize_with = "deserialize_keys")]
+    pub unknown_keys: BTreeSet<String>,
+}
+
+/// Collect the keys of a table, ignoring their values.
+fn deserialize_keys<'de, D>(deserializer: D) -> Result<BTreeSet<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct KeysVisitor;
+
+    impl<'de> de::Visitor<'de> for KeysVisitor {
+        type Value = BTreeSet<String>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a table")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::MapAccess<'de>,
+        {
+            let mut keys = BTreeSet::new();
+            while let Some((key, de::IgnoredAny)) = map.next_entry::<String, de::IgnoredAny>()? {
+                keys.insert(key);
+            }
+            Ok(keys)
+        }
+    }
+
+    deserializer.deserialize_map(KeysVisitor)
+}
+
+/// A template, or a list of templates to try in order.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Templates {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Templates {
+    /// Iterate over the templates in order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> + Clone {
+        let templates = match self {
+            Templates::One(template) => slice::from_ref(template),
+            Templates::Many(templates) => templates,
+        };
+
+        templates.iter().map(String::as_str)
+    }
+
+    /// Return the first template, if any.
+    pub fn first(&self) -> Option<&str> {
+        self.iter().next()
+    }
+}
+
+/// Format a single template as is, a list as `["a", "b"]`.
+impl fmt::Display for Templates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Templates::One(template) => f.write_str(template),
+            Templates::Many(templates) => write!(f, "{templates:?}"),
+        }
+    }
+}
+
+impl From<String> for Templates {
+    fn from(template: String) -> Self {
+        Templates::One(template)
+    }
+}
+
+impl From<&str> for Templates {
+    fn from(template: &str) -> Self {
+        Templates::One(template.to_owned())
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
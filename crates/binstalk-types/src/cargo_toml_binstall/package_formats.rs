@@ -22,6 +22,14 @@ pub enum PkgFmt {
     Zip,
     /// Download format is raw / binary
     Bin,
+    /// Download format is raw / binary compressed with Bzip2
+    Bz2,
+    /// Download format is raw / binary compressed with GZip
+    Gz,
+    /// Download format is raw / binary compressed with XZ
+    Xz,
+    /// Download format is raw / binary compressed with Zstd
+    Zstd,
 }
 
 impl Default for PkgFmt {
@@ -40,10 +48,22 @@ impl PkgFmt {
             PkgFmt::Txz => PkgFmtDecomposed::Tar(TarBasedFmt::Txz),
             PkgFmt::Tzstd => PkgFmtDecomposed::Tar(TarBasedFmt::Tzstd),
             PkgFmt::Bin => PkgFmtDecomposed::Bin,
+            PkgFmt::Bz2 => PkgFmtDecomposed::CompressedBin(CompressedBinFmt::Bz2),
+            PkgFmt::Gz => PkgFmtDecomposed::CompressedBin(CompressedBinFmt::Gz),
+            PkgFmt::Xz => PkgFmtDecomposed::CompressedBin(CompressedBinFmt::Xz),
+            PkgFmt::Zstd => PkgFmtDecomposed::CompressedBin(CompressedBinFmt::Zstd),
             PkgFmt::Zip => PkgFmtDecomposed::Zip,
         }
     }
 
+    /// Return true if the package is a single binary, possibly compressed.
+    pub fn is_bin(self) -> bool {
+        matches!(
+            self.decompose(),
+            PkgFmtDecomposed::Bin | PkgFmtDecomposed::CompressedBin(_)
+        )
+    }
+
     /// List of possible file extensions for the format
     /// (with prefix `.`).
     ///
@@ -63,6 +83,10 @@ impl PkgFmt {
                     &[".bin", ""]
                 }
             }
+            PkgFmt::Bz2 => &[".bz2"],
+            PkgFmt::Gz => &[".gz"],
+            PkgFmt::Xz => &[".xz"],
+            PkgFmt::Zstd => &[".zst", ".zstd"],
             PkgFmt::Zip => &[".zip"],
         }
     }
@@ -70,21 +94,34 @@ impl PkgFmt {
     /// Given the pkg-url template, guess the possible pkg-fmt.
     pub fn guess_pkg_format(pkg_url: &str) -> Option<Self> {
         let mut it = pkg_url.rsplitn(3, '.');
+        let ext = it.next()?;
+
+        // Return `tar_fmt` if the extension is preceded by `.tar`,
+        // otherwise `fmt` for compressed single binary.
+        let mut tar_or = |tar_fmt, fmt| {
+            if it.clone().next() == Some("tar") {
+                it.next();
+                Some(tar_fmt)
+            } else {
+                Some(fmt)
+            }
+        };
 
-        let guess = match it.next()? {
+        let guess = match ext {
             "tar" => Some(PkgFmt::Tar),
 
             "tbz2" => Some(PkgFmt::Tbz2),
-            "bz2" if it.next() == Some("tar") => Some(PkgFmt::Tbz2),
+            "bz2" => tar_or(PkgFmt::Tbz2, PkgFmt::Bz2),
 
             "tgz" => Some(PkgFmt::Tgz),
-            "gz" if it.next() == Some("tar") => Some(PkgFmt::Tgz),
+            "gz" => tar_or(PkgFmt::Tgz, PkgFmt::Gz),
 
             "txz" => Some(PkgFmt::Txz),
-            "xz" if it.next() == Some("tar") => Some(PkgFmt::Txz),
+            "xz" => tar_or(PkgFmt::Txz, PkgFmt::Xz),
 
             "tzstd" | "tzst" => Some(PkgFmt::Tzstd),
-            "zst" if it.next() == Some("tar") => Some(PkgFmt::Tzstd),
+            "zst" => tar_or(PkgFmt::Tzstd, PkgFmt::Zstd),
+            "zstd" => Some(PkgFmt::Zstd),
 
             "exe" | "bin" => Some(PkgFmt::Bin),
             "zip" => Some(PkgFmt::Zip),
@@ -104,9 +141,22 @@ impl PkgFmt {
 pub enum PkgFmtDecomposed {
     Tar(TarBasedFmt),
     Bin,
+    CompressedBin(CompressedBinFmt),
     Zip,
 }
 
+#[derive(Debug, Display, Copy, Clone, Eq, PartialEq)]
+pub enum CompressedBinFmt {
+    /// Binary compressed with Bzip2
+    Bz2,
+    /// Binary compressed with GZip
+    Gz,
+    /// Binary compressed with XZ
+    Xz,
+    /// Binary compressed with Zstd
+    Zstd,
+}
+
 #[derive(Debug, Display, Copy, Clone, Eq, PartialEq)]
 pub enum TarBasedFmt {
     /// Download format is TAR (uncompressed)
@@ -132,3 +182,55 @@ impl From<TarBasedFmt> for PkgFmt {
         }
     }
 }
+
+impl From<CompressedBinFmt> for PkgFmt {
+    fn from(fmt: CompressedBinFmt) -> Self {
+        match fmt {
+            CompressedBinFmt::Bz2 => PkgFmt::Bz2,
+            CompressedBinFmt::Gz => PkgFmt::Gz,
+            CompressedBinFmt::Xz => PkgFmt::Xz,
+            CompressedBinFmt::Zstd => PkgFmt::Zstd,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PkgFmt;
+
+    #[test]
+    fn test_guess_pkg_format() {
+        for (pkg_url, pkg_fmt) in [
+            ("{ name }.tar", Some(PkgFmt::Tar)),
+            ("{ name }.tbz2", Some(PkgFmt::Tbz2)),
+            ("{ name }.tar.bz2", Some(PkgFmt::Tbz2)),
+            ("{ name }.bz2", Some(PkgFmt::Bz2)),
+            ("{ name }.tgz", Some(PkgFmt::Tgz)),
+            ("{ name }.tar.gz", Some(PkgFmt::Tgz)),
+            ("{ name }.gz", Some(PkgFmt::Gz)),
+            ("{ name }.txz", Some(PkgFmt::Txz)),
+            ("{ name }.tar.xz", Some(PkgFmt::Txz)),
+            ("{ name }.xz", Some(PkgFmt::Xz)),
+            ("{ name }.tzstd", Some(PkgFmt::Tzstd)),
+            ("{ name }.tzst", Some(PkgFmt::Tzstd)),
+            ("{ name }.tar.zst", Some(PkgFmt::Tzstd)),
+            ("{ name }.zst", Some(PkgFmt::Zstd)),
+            ("{ name }.zstd", Some(PkgFmt::Zstd)),
+            ("{ name }.exe", Some(PkgFmt::Bin)),
+            ("{ name }.bin", Some(PkgFmt::Bin)),
+            ("{ name }.zip", Some(PkgFmt::Zip)),
+            (
+                "{ repo }/releases/download/v{ version }/{ name }-v{ version }.tar.gz",
+                Some(PkgFmt::Tgz),
+            ),
+            // Unknown suffix
+            ("{ name }.deb", None),
+            ("{ name }", None),
+            // Nothing before the suffix
+            ("zip", None),
+            ("tar.gz", None),
+        ] {
+            assert_eq!(PkgFmt::guess_pkg_format(pkg_url), pkg_fmt, "{pkg_url}");
+        }
+    }
+}
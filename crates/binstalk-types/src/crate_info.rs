@@ -1,6 +1,6 @@
 //! Common structure for crate information for post-install manifests.
 
-use std::{borrow, cmp, hash};
+use std::{borrow, cmp, collections::BTreeMap, hash};
 
 use compact_str::CompactString;
 use maybe_owned::MaybeOwned;
@@ -24,6 +24,20 @@ pub struct CrateInfo {
     pub source: CrateSource,
     pub target: CompactString,
     pub bins: Vec<CompactString>,
+    /// SHA256 of the package downloaded, hex-encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_sha256: Option<CompactString>,
+    /// SHA256 of the binaries installed keyed by their names in `bins`,
+    /// hex-encoded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bins_sha256: BTreeMap<CompactString, CompactString>,
+    /// Where the package is downloaded from, e.g. `github.com` or
+    /// `QuickInstall`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_source: Option<CompactString>,
+    /// When the crate is installed, in seconds since the unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_time: Option<u64>,
 }
 
 impl borrow::Borrow<str> for CrateInfo {
@@ -65,6 +79,11 @@ pub enum SourceType {
     Git,
     Path,
     Registry,
+    /// A package downloaded from an url directly, not a crate.
+    ///
+    /// It is not understood by cargo, so it is only recorded in the
+    /// manifest of binstall.
+    Url,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -80,4 +99,17 @@ impl CrateSource {
             url: MaybeOwned::Borrowed(cratesio_url()),
         }
     }
+
+    pub fn url(url: Url) -> CrateSource {
+        Self {
+            source_type: SourceType::Url,
+            url: MaybeOwned::Owned(url),
+        }
+    }
+
+    /// Return true if cargo understands the source, i.e. it can be recorded
+    /// in `.crates.toml` and `.crates2.json`.
+    pub fn is_cargo_source(&self) -> bool {
+        !matches!(self.source_type, SourceType::Url)
+    }
 }
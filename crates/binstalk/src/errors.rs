@@ -5,7 +5,9 @@ use std::{
 };
 
 use binstalk_downloader::{
-    download::DownloadError, gh_api_client::GhApiError, remote::Error as RemoteError,
+    download::DownloadError,
+    gh_api_client::GhApiError,
+    remote::{Error as RemoteError, StatusCode},
 };
 use binstalk_fetchers::FetchError;
 use compact_str::CompactString;
@@ -40,17 +42,134 @@ pub struct CrateContextError {
     err: BinstallError,
 }
 
-#[derive(Debug, Error)]
-#[error("Invalid pkg-url {pkg_url} for {crate_name}@{version} on {target}: {reason}")]
-pub struct InvalidPkgFmtError {
-    pub crate_name: CompactString,
-    pub version: CompactString,
-    pub target: String,
-    pub pkg_url: String,
-    pub reason: &'static str,
+/// Categories of errors that scripts usually need to tell apart, e.g. to
+/// fall back to `cargo install` when there is no pre-built package, or to
+/// retry when the network is down.
+///
+/// Errors of these categories always exit with the code of the category,
+/// regardless of the [`BinstallError`] variant they are reported as.
+/// Other errors exit with the code documented on their variant.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The installation was cancelled by the user.
+    ///
+    /// - Exit: 32
+    UserAbort,
+
+    /// The checksum of a crate downloaded does not match the one in the
+    /// registry, or a package does not match the SHA256 specified.
+    ///
+    /// - Exit: 42
+    ChecksumMismatch,
+
+    /// Failed to connect to a server, the server timed out or responded
+    /// with a server error status, retrying later might succeed.
+    ///
+    /// - Exit: 69
+    Network,
+
+    /// An I/O error occurred, e.g. the disk is full.
+    ///
+    /// - Exit: 74
+    Io,
+
+    /// The crate, or a version matching the requirement, does not exist in
+    /// the registry.
+    ///
+    /// - Exit: 91
+    CrateNotFound,
+
+    /// No compatible pre-built package is found and building from source
+    /// is disabled.
+    ///
+    /// - Exit: 94
+    NoCompatibleArtifact,
+
+    /// Any other error.
+    ///
+    /// - Exit: the code documented on the [`BinstallError`] variant
+    Other,
+}
+
+impl ErrorCategory {
+    /// Return the exit code of the category, `None` for
+    /// [`ErrorCategory::Other`].
+    pub fn exit_number(self) -> Option<u8> {
+        match self {
+            Self::UserAbort => Some(32),
+            Self::ChecksumMismatch => Some(42),
+            Self::Network => Some(69),
+            Self::Io => Some(74),
+            Self::CrateNotFound => Some(91),
+            Self::NoCompatibleArtifact => Some(94),
+            Self::Other => None,
+        }
+    }
+
+    fn of_remote(err: &RemoteError) -> Self {
+        match err {
+            RemoteError::Reqwest(_) | RemoteError::ReadTimeout(_) => Self::Network,
+            RemoteError::Http(err) => match err.status() {
+                // The server rejected the request, retrying won't help.
+                Some(status)
+                    if status.is_client_error()
+                        && status != StatusCode::REQUEST_TIMEOUT
+                        && status != StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    Self::Other
+                }
+                _ => Self::Network,
+            },
+            _ => Self::Other,
+        }
+    }
+
+    fn of_download(err: &DownloadError) -> Self {
+        match err {
+            DownloadError::Remote(err) => Self::of_remote(err),
+            DownloadError::Io(_) => Self::Io,
+            _ => Self::Other,
+        }
+    }
+
+    fn of_registry(err: &RegistryError) -> Self {
+        match err {
+            RegistryError::NotFound(_) | RegistryError::VersionMismatch { .. } => {
+                Self::CrateNotFound
+            }
+            RegistryError::UnmatchedChecksum { .. } => Self::ChecksumMismatch,
+            RegistryError::Remote(err) => Self::of_remote(err),
+            RegistryError::Download(err) => Self::of_download(err),
+            RegistryError::Io(_) => Self::Io,
+            _ => Self::Other,
+        }
+    }
+
+    fn of_gh_api(err: &GhApiError) -> Self {
+        match err {
+            GhApiError::Remote(err) => Self::of_remote(err),
+            GhApiError::Io(_) => Self::Io,
+            GhApiError::Context(context) => std::error::Error::source(&**context)
+                .and_then(|err| err.downcast_ref())
+                .map_or(Self::Other, Self::of_gh_api),
+            _ => Self::Other,
+        }
+    }
+
+    fn of_fetch(err: &FetchError) -> Self {
+        match err {
+            FetchError::Download(err) => Self::of_download(err),
+            FetchError::GhApi(err) => Self::of_gh_api(err),
+            _ => Self::Other,
+        }
+    }
 }
 
 /// Error kinds emitted by cargo-binstall.
+///
+/// See [`ErrorCategory`] for the exit codes of errors that scripts usually
+/// need to tell apart.
 #[derive(Error, Diagnostic, Debug)]
 #[non_exhaustive]
 pub enum BinstallError {
@@ -118,7 +237,7 @@ pub enum BinstallError {
     /// Failed to fetch pre-built binaries.
     ///
     /// - Code: `binstall::fetch`
-    /// - Exit: 68
+    /// - Exit: the code of its [`ErrorCategory`], 68 for [`ErrorCategory::Other`]
     #[error(transparent)]
     #[diagnostic(severity(error), code(binstall::fetch))]
     #[source_code(transparent)]
@@ -128,14 +247,14 @@ pub enum BinstallError {
     /// Failed to download or failed to decode the body.
     ///
     /// - Code: `binstall::download`
-    /// - Exit: 68
+    /// - Exit: the code of its [`ErrorCategory`], 68 for [`ErrorCategory::Other`]
     #[error(transparent)]
     #[diagnostic(severity(error), code(binstall::download))]
     Download(#[from] DownloadError),
 
     /// A subprocess failed.
     ///
-    /// This is often about cargo-install calls.
+    /// This is often about `cargo build` calls.
     ///
     /// - Code: `binstall::subprocess`
     /// - Exit: 70
@@ -167,7 +286,7 @@ pub enum BinstallError {
     /// This could either be a "not found" or a server/transport error.
     ///
     /// - Code: `binstall::cargo_registry`
-    /// - Exit: 76
+    /// - Exit: the code of its [`ErrorCategory`], 76 for [`ErrorCategory::Other`]
     #[error(transparent)]
     #[diagnostic(transparent)]
     RegistryError(#[from] Box<RegistryError>),
@@ -291,26 +410,18 @@ pub enum BinstallError {
     #[diagnostic(severity(error), code(binstall::SourceFilePath))]
     DuplicateSourceFilePath { path: PathBuf },
 
-    /// Fallback to `cargo-install` is disabled.
+    /// Fallback to building from source is disabled.
     ///
     /// - Code: `binstall::no_fallback_to_cargo_install`
     /// - Exit: 94
-    #[error("Fallback to cargo-install is disabled")]
+    #[error("Fallback to building from source is disabled")]
     #[diagnostic(severity(error), code(binstall::no_fallback_to_cargo_install))]
     NoFallbackToCargoInstall,
 
-    /// Fallback to `cargo-install` is disabled.
-    ///
-    /// - Code: `binstall::invalid_pkg_fmt`
-    /// - Exit: 95
-    #[error(transparent)]
-    #[diagnostic(severity(error), code(binstall::invalid_pkg_fmt))]
-    InvalidPkgFmt(Box<InvalidPkgFmtError>),
-
     /// Request to GitHub API failed
     ///
     /// - Code: `binstall::gh_api_failure`
-    /// - Exit: 96
+    /// - Exit: the code of its [`ErrorCategory`], 96 for [`ErrorCategory::Other`]
     #[error("Request to GitHub API failed: {0}")]
     #[diagnostic(severity(error), code(binstall::gh_api_failure))]
     GhApiErr(#[source] Box<GhApiError>),
@@ -340,6 +451,56 @@ pub enum BinstallError {
     #[diagnostic(severity(error), code(binstall::load_manifest_from_workspace))]
     LoadManifestFromWSError(#[from] Box<LoadManifestFromWSError>),
 
+    /// The binary selected via `--bin` is not provided by the crate.
+    ///
+    /// - Code: `binstall::resolve::bin_not_found`
+    /// - Exit: 100
+    #[error("binary {0} is not provided by the crate")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::resolve::bin_not_found),
+        help("Check the binaries the crate provides, they are listed in its Cargo.toml.")
+    )]
+    BinNotFound(CompactString),
+
+    /// The package would be downloaded from a host that is not trusted
+    /// while `--only-trusted-hosts` is on.
+    ///
+    /// - Code: `binstall::resolve::untrusted_host`
+    /// - Exit: 101
+    #[error("package url {0} is not on a trusted host")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::resolve::untrusted_host),
+        help("Add the host to --trusted-hosts if you trust it.")
+    )]
+    UntrustedHost(CompactString),
+
+    /// The package downloaded from `--url` does not match the SHA256
+    /// specified.
+    ///
+    /// - Code: `binstall::resolve::checksum_mismatch`
+    /// - Exit: 42
+    #[error("package {url} has SHA256 {found}, expected {expected}")]
+    #[diagnostic(severity(error), code(binstall::resolve::checksum_mismatch))]
+    PackageChecksumMismatch {
+        url: CompactString,
+        expected: CompactString,
+        found: CompactString,
+    },
+
+    /// The name specified for `--url` is already installed as a crate.
+    ///
+    /// - Code: `binstall::resolve::installed_as_crate`
+    /// - Exit: 102
+    #[error("{0} is already installed as a crate")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::resolve::installed_as_crate),
+        help("Use --force to replace it with the package at the url.")
+    )]
+    InstalledAsCrate(CompactString),
+
     /// A wrapped error providing the context of which crate the error is about.
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -347,8 +508,30 @@ pub enum BinstallError {
 }
 
 impl BinstallError {
+    /// Return the category of the error.
+    pub fn category(&self) -> ErrorCategory {
+        use BinstallError::*;
+        match self {
+            UserAbort => ErrorCategory::UserAbort,
+            Io(_) => ErrorCategory::Io,
+            NoFallbackToCargoInstall => ErrorCategory::NoCompatibleArtifact,
+            PackageChecksumMismatch { .. } => ErrorCategory::ChecksumMismatch,
+            Download(err) => ErrorCategory::of_download(err),
+            FetchError(err) => ErrorCategory::of_fetch(err),
+            RegistryError(err) => ErrorCategory::of_registry(err),
+            GhApiErr(err) => ErrorCategory::of_gh_api(err),
+            CrateContext(context) => context.err.category(),
+            _ => ErrorCategory::Other,
+        }
+    }
+
     fn exit_number(&self) -> u8 {
         use BinstallError::*;
+
+        if let Some(code) = self.category().exit_number() {
+            return code;
+        }
+
         let code: u8 = match self {
             TaskJoinError(_) => 17,
             UserAbort => 32,
@@ -373,12 +556,15 @@ impl BinstallError {
             CargoTomlMissingPackage(_) => 89,
             DuplicateSourceFilePath { .. } => 90,
             NoFallbackToCargoInstall => 94,
-            InvalidPkgFmt(..) => 95,
             GhApiErr(..) => 96,
             TargetTripleParseError(..) => 97,
             #[cfg(feature = "git")]
             GitError(_) => 98,
             LoadManifestFromWSError(_) => 99,
+            BinNotFound(_) => 100,
+            UntrustedHost(_) => 101,
+            PackageChecksumMismatch { .. } => 42,
+            InstalledAsCrate(_) => 102,
             CrateContext(context) => context.err.exit_number(),
         };
 
@@ -388,7 +574,8 @@ impl BinstallError {
         code
     }
 
-    /// The recommended exit code for this error.
+    /// The recommended exit code for this error, which is the exit code of
+    /// its [`ErrorCategory`] if it has one.
     ///
     /// This will never output:
     /// - 0 (success)
@@ -410,14 +597,14 @@ impl BinstallError {
 
 impl Termination for BinstallError {
     fn report(self) -> ExitCode {
-        let code = self.exit_code();
+        let exit_code = self.exit_number();
         if let BinstallError::UserAbort = self {
-            warn!("Installation cancelled");
+            warn!(exit_code, "Installation cancelled");
         } else {
-            error!("Fatal error:\n{:?}", Report::new(self));
+            error!(exit_code, "Fatal error:\n{:?}", Report::new(self));
         }
 
-        code
+        exit_code.into()
     }
 }
 
@@ -461,12 +648,6 @@ impl From<CargoTomlError> for BinstallError {
     }
 }
 
-impl From<InvalidPkgFmtError> for BinstallError {
-    fn from(e: InvalidPkgFmtError) -> Self {
-        BinstallError::InvalidPkgFmt(Box::new(e))
-    }
-}
-
 impl From<GhApiError> for BinstallError {
     fn from(e: GhApiError) -> Self {
         BinstallError::GhApiErr(Box::new(e))
@@ -502,3 +683,32 @@ impl From<FetchError> for BinstallError {
         BinstallError::FetchError(Box::new(e))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exit_number() {
+        let not_found = BinstallError::from(RegistryError::NotFound("foo".into()));
+        assert_eq!(not_found.category(), ErrorCategory::CrateNotFound);
+        assert_eq!(not_found.crate_context("foo").exit_number(), 91);
+
+        let io = BinstallError::from(io::Error::new(io::ErrorKind::Other, "disk full"));
+        assert_eq!(io.exit_number(), 74);
+
+        assert_eq!(BinstallError::NoFallbackToCargoInstall.exit_number(), 94);
+        assert_eq!(BinstallError::UserAbort.exit_number(), 32);
+
+        // Errors without category keep their own exit code
+        assert_eq!(
+            BinstallError::NoViableTargets.category(),
+            ErrorCategory::Other
+        );
+        assert_eq!(BinstallError::NoViableTargets.exit_number(), 87);
+        assert_eq!(
+            BinstallError::from(DownloadError::UnknownFormat).exit_number(),
+            68
+        );
+    }
+}
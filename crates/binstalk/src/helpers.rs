@@ -3,7 +3,7 @@ pub mod remote;
 pub(crate) mod target_triple;
 pub mod tasks;
 
-pub(crate) use binstalk_downloader::download;
+pub use binstalk_downloader::download;
 pub use binstalk_downloader::gh_api_client;
 
 pub(crate) use cargo_toml_workspace::{self, cargo_toml};
@@ -6,6 +6,7 @@ use std::{
 };
 
 use tokio::task::JoinHandle;
+use tracing::Instrument;
 
 use crate::errors::BinstallError;
 
@@ -22,11 +23,13 @@ impl<T> AutoAbortJoinHandle<T>
 where
     T: Send + 'static,
 {
+    /// Spawn `future` in the current span, so that its logs are attributed
+    /// to the span, e.g. the crate being resolved.
     pub fn spawn<F>(future: F) -> Self
     where
         F: Future<Output = T> + Send + 'static,
     {
-        Self(tokio::spawn(future))
+        Self(tokio::spawn(future.in_current_span()))
     }
 }
 
@@ -68,3 +71,22 @@ where
         self.await?.map_err(Into::into)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tracing::{info_span, subscriber, Span};
+    use tracing_subscriber::registry;
+
+    #[tokio::test]
+    async fn test_spawn_in_current_span() {
+        let _guard = subscriber::set_default(registry());
+
+        let span = info_span!("resolve", crate_name = "foo");
+        let handle = span.in_scope(|| AutoAbortJoinHandle::spawn(async { Span::current().id() }));
+
+        assert!(span.id().is_some());
+        assert_eq!(handle.await.unwrap(), span.id());
+    }
+}
@@ -1,5 +1,25 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+//! The binstall toolkit, used by `cargo-binstall` and usable by other
+//! tools that want to install prebuilt binaries of crates.
+//!
+//! Installing a crate programmatically consists of:
+//!
+//!  1. Creating [`ops::Options`], which holds all the settings, the http
+//!     clients and the fetchers to use.
+//!  2. Calling [`ops::resolve::resolve`] for every crate, which looks up the
+//!     crate, then finds, downloads and extracts a prebuilt package for it.
+//!  3. Calling [`ops::resolve::ResolutionFetch::install`] on the returned
+//!     [`ops::resolve::Resolution`], or [`ops::resolve::ResolutionSource::build`]
+//!     followed by [`ops::resolve::BuiltSource::install`] if the crate
+//!     has to be built from source.
+//!
+//! None of these functions read from stdin, asking for confirmation
+//! is left to the caller, and progress is reported using [`tracing`].
+//! The [`manifests::crate_info::CrateInfo`] returned by installing a
+//! [`ops::resolve::ResolutionFetch`] or [`ops::resolve::BuiltSource`]
+//! can be used to update the install manifests.
+
 pub mod errors;
 pub mod helpers;
 pub mod ops;
@@ -2,12 +2,16 @@
 
 use std::{path::PathBuf, sync::Arc};
 
+use compact_str::CompactString;
 use semver::VersionReq;
 
 use crate::{
     fetchers::{Data, Fetcher, SignaturePolicy, TargetDataErased},
     helpers::{
-        self, gh_api_client::GhApiClient, jobserver_client::LazyJobserverClient, remote::Client,
+        self,
+        gh_api_client::GhApiClient,
+        jobserver_client::LazyJobserverClient,
+        remote::{Client, Url},
     },
     manifests::cargo_toml_binstall::PkgOverride,
     registry::Registry,
@@ -15,7 +19,14 @@ use crate::{
 };
 
 pub mod resolve;
+pub mod validate;
+pub mod verify;
 
+/// Constructor of a [`Fetcher`], e.g. `GhCrateMeta::new as Resolver`.
+///
+/// Downstream users can register their own artifact sources by
+/// implementing [`Fetcher`] and adding its constructor to
+/// [`Options::resolvers`].
 pub type Resolver =
     fn(Client, GhApiClient, Arc<Data>, Arc<TargetDataErased>, SignaturePolicy) -> Arc<dyn Fetcher>;
 
@@ -25,6 +36,18 @@ pub enum CargoTomlFetchOverride {
     #[cfg(feature = "git")]
     Git(helpers::git::GitUrl),
     Path(PathBuf),
+    /// Install the package at the url directly, for which there is no crate.
+    ///
+    /// The name of the crate is used as the name of its only binary. The
+    /// version installed is the lowest one matching the version requirement,
+    /// e.g. `1.2.0` for `1.2`, or the version in the file name of the url,
+    /// e.g. `1.2.3` for `tool-v1.2.3.tar.gz`, if there is no requirement.
+    ///
+    /// The package must match `sha256` if specified.
+    Url {
+        url: Url,
+        sha256: Option<CompactString>,
+    },
 }
 
 #[derive(Debug)]
@@ -35,12 +58,25 @@ pub struct Options {
     pub quiet: bool,
     pub locked: bool,
     pub no_track: bool,
+    /// Error out instead of overwriting binaries not installed by binstall
+    /// or cargo.
+    pub strict: bool,
+    /// Do not check that the binaries downloaded are built for the target,
+    /// nor that gnu binaries do not require a newer glibc than the host has.
+    pub no_verify_arch: bool,
 
     pub version_req: Option<VersionReq>,
+    /// Consider pre-releases when resolving the latest version.
+    pub include_prereleases: bool,
+    /// Only install these binaries, instead of all binaries of the crate.
+    pub bins: Option<Vec<CompactString>>,
     pub cargo_toml_fetch_override: Option<CargoTomlFetchOverride>,
     pub cli_overrides: PkgOverride,
 
     pub desired_targets: DesiredTargets,
+    /// Fetchers to try, in order of priority.
+    ///
+    /// For each target, the first fetcher that finds the package wins.
     pub resolvers: Vec<Resolver>,
     pub cargo_install_fallback: bool,
 
@@ -54,4 +90,15 @@ pub struct Options {
     pub registry: Registry,
 
     pub signature_policy: SignaturePolicy,
+    /// Report the crates, versions and targets looked up to the fetchers'
+    /// upstream, e.g. quickinstall, so that missing packages get built.
+    pub report_to_upstream: bool,
+    /// Hosts packages can be downloaded from, in addition to github.com
+    /// and gitlab.com.
+    ///
+    /// Subdomains of them are trusted as well.
+    pub trusted_hosts: Vec<CompactString>,
+    /// Error out instead of warning if a package is downloaded from a host
+    /// not in `trusted_hosts`.
+    pub only_trusted_hosts: bool,
 }
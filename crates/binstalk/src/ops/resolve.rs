@@ -1,5 +1,4 @@
 use std::{
-    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     iter, mem,
     path::Path,
@@ -8,10 +7,11 @@ use std::{
 };
 
 use compact_str::{CompactString, ToCompactString};
+use detect_targets::detect_glibc_version;
 use itertools::Itertools;
 use leon::Template;
 use maybe_owned::MaybeOwned;
-use semver::{Version, VersionReq};
+use semver::{Op, Version, VersionReq};
 use tempfile::TempDir;
 use tokio::task::spawn_blocking;
 use tracing::{debug, error, info, instrument, warn};
@@ -21,12 +21,19 @@ use crate::{
     errors::{BinstallError, VersionParseError},
     fetchers::{Data, Fetcher, TargetData},
     helpers::{
-        self, cargo_toml::Manifest, cargo_toml_workspace::load_manifest_from_workspace,
-        download::ExtractedFiles, remote::Client, target_triple::TargetTriple,
+        self,
+        cargo_toml::Manifest,
+        cargo_toml_workspace::load_manifest_from_workspace,
+        download::ExtractedFiles,
+        remote::{Client, Url},
+        target_triple::TargetTriple,
         tasks::AutoAbortJoinHandle,
     },
-    manifests::cargo_toml_binstall::{Meta, PkgMeta, PkgOverride},
-    ops::{CargoTomlFetchOverride, Options},
+    manifests::{
+        cargo_toml_binstall::{Meta, PkgMeta, PkgOverride, Strategy, META_VERSION},
+        crate_info::CrateSource,
+    },
+    ops::{validate::validate_meta, CargoTomlFetchOverride, Options},
 };
 
 mod crate_name;
@@ -39,9 +46,20 @@ pub use version_ext::VersionReqExt;
 
 mod resolution;
 #[doc(inline)]
-pub use resolution::{Resolution, ResolutionFetch, ResolutionSource};
+pub use resolution::{BuiltSource, Resolution, ResolutionFetch, ResolutionSource};
+
+mod trusted_host;
 
-#[instrument(skip_all)]
+mod inspect;
+#[doc(inline)]
+pub use inspect::{inspect, FetcherReport, FetcherStatus, PackageReport};
+
+/// Resolve `crate_name` to a prebuilt package, or to installing it
+/// from source if allowed by [`Options::cargo_install_fallback`].
+///
+/// `curr_version` is the currently installed version, if any, and is used
+/// to return [`Resolution::AlreadyUpToDate`] when no upgrade is available.
+#[instrument(skip_all, fields(crate_name = %crate_name.name))]
 pub async fn resolve(
     opts: Arc<Options>,
     crate_name: CrateName,
@@ -70,6 +88,7 @@ async fn resolve_inner(
     };
 
     let version_req_str = version_req.to_compact_string();
+    let is_upgrade = curr_version.is_some();
 
     let Some(package_info) = PackageInfo::resolve(
         &opts,
@@ -83,59 +102,45 @@ async fn resolve_inner(
         return Ok(Resolution::AlreadyUpToDate);
     };
 
-    let desired_targets = opts
-        .desired_targets
-        .get()
-        .await
-        .iter()
-        .map(|target| TargetTriple::from_str(target).map(|triple| (triple, target)))
-        .collect::<Result<Vec<_>, _>>()?;
-    let resolvers = &opts.resolvers;
-
-    let mut handles: Vec<(Arc<dyn Fetcher>, _)> =
-        Vec::with_capacity(desired_targets.len() * resolvers.len());
-
-    let data = Arc::new(Data::new(
-        package_info.name.clone(),
-        package_info.version_str.clone(),
-        package_info.repo.clone(),
-    ));
-
-    handles.extend(
-        desired_targets
-            .into_iter()
-            .map(|(triple, target)| {
-                debug!("Building metadata for target: {target}");
-
-                let target_meta = package_info.meta.merge_overrides(
-                    iter::once(&opts.cli_overrides).chain(package_info.overrides.get(target)),
+    let handles: Vec<_> = create_fetchers(&opts, &package_info)
+        .await?
+        .into_iter()
+        .filter_map(|(fetcher, is_disabled)| {
+            if is_disabled {
+                debug!(
+                    "Fetcher {} is disabled by the crate for target {}",
+                    fetcher.fetcher_name(),
+                    fetcher.target()
                 );
+                None
+            } else {
+                Some((fetcher.clone(), AutoAbortJoinHandle::new(fetcher.find())))
+            }
+        })
+        .collect();
 
-                debug!("Found metadata: {target_meta:?}");
-
-                Arc::new(TargetData {
-                    target: target.clone(),
-                    meta: target_meta,
-                    target_related_info: triple,
-                })
-            })
-            .cartesian_product(resolvers)
-            .map(|(target_data, f)| {
-                let fetcher = f(
-                    opts.client.clone(),
-                    opts.gh_api_client.clone(),
-                    data.clone(),
-                    target_data,
-                    opts.signature_policy,
-                );
-                (fetcher.clone(), AutoAbortJoinHandle::new(fetcher.find()))
-            }),
-    );
+    // Only detected once a gnu package needs to be verified.
+    let mut host_glibc = None;
 
     for (fetcher, handle) in handles {
-        fetcher.clone().report_to_upstream();
+        if opts.report_to_upstream {
+            fetcher.clone().report_to_upstream();
+        }
         match handle.flattened_join().await {
             Ok(true) => {
+                if let Some(url) = fetcher.package_url() {
+                    if !trusted_host::is_trusted_url(url, &opts.trusted_hosts) {
+                        if opts.only_trusted_hosts {
+                            return Err(BinstallError::UntrustedHost(url.as_str().into()));
+                        }
+
+                        warn!(
+                            "Package url {url} is not on a trusted host, \
+                            add the host to --trusted-hosts if you trust it"
+                        );
+                    }
+                }
+
                 // Generate temporary binary path
                 let bin_path = opts.temp_dir.join(format!(
                     "bin-{}-{}-{}",
@@ -144,16 +149,49 @@ async fn resolve_inner(
                     fetcher.fetcher_name()
                 ));
 
+                if opts.dry_run {
+                    // Do not download anything in dry-run mode, the binaries
+                    // are assumed to be present in the package at the
+                    // location specified by the metadata.
+                    let bin_files = collect_bin_files(
+                        fetcher.as_ref(),
+                        &package_info,
+                        fetcher.target_meta(),
+                        &bin_path,
+                        &opts.install_path,
+                        opts.no_symlinks,
+                        None,
+                    )?;
+
+                    return Ok(Resolution::Fetch(Box::new(ResolutionFetch {
+                        fetcher,
+                        new_version: package_info.version,
+                        name: package_info.name,
+                        version_req: version_req_str,
+                        bin_files,
+                        is_upgrade,
+                        package_sha256: None,
+                        source: package_info.source,
+                    })));
+                }
+
+                let is_gnu = fetcher.target().contains("-linux-gnu");
+                if is_gnu && !opts.no_verify_arch && host_glibc.is_none() {
+                    host_glibc = Some(detect_glibc_version().await);
+                }
+
                 match download_extract_and_verify(
                     fetcher.as_ref(),
                     &bin_path,
                     &package_info,
                     &opts.install_path,
                     opts.no_symlinks,
+                    !opts.no_verify_arch,
+                    host_glibc.flatten().filter(|_| is_gnu),
                 )
                 .await
                 {
-                    Ok(bin_files) => {
+                    Ok((bin_files, package_sha256)) => {
                         if !bin_files.is_empty() {
                             return Ok(Resolution::Fetch(Box::new(ResolutionFetch {
                                 fetcher,
@@ -161,6 +199,9 @@ async fn resolve_inner(
                                 name: package_info.name,
                                 version_req: version_req_str,
                                 bin_files,
+                                is_upgrade,
+                                package_sha256,
+                                source: package_info.source,
                             })));
                         } else {
                             warn!(
@@ -171,7 +212,9 @@ async fn resolve_inner(
                         }
                     }
                     Err(err) => {
-                        if let BinstallError::UserAbort = err {
+                        if let BinstallError::UserAbort
+                        | BinstallError::PackageChecksumMismatch { .. } = err
+                        {
                             return Err(err);
                         }
                         warn!(
@@ -193,11 +236,28 @@ async fn resolve_inner(
         }
     }
 
-    if opts.cargo_install_fallback {
-        Ok(Resolution::InstallFromSource(ResolutionSource {
+    if opts.cargo_install_fallback && !package_info.meta.is_strategy_disabled(Strategy::Compile) {
+        let target = opts
+            .desired_targets
+            .get()
+            .await
+            .first()
+            .ok_or(BinstallError::NoViableTargets)?
+            .to_compact_string();
+
+        Ok(Resolution::InstallFromSource(Box::new(ResolutionSource {
+            bins: package_info
+                .binaries
+                .into_iter()
+                .map(|bin| bin.name.into())
+                .collect(),
             name: package_info.name,
-            version: package_info.version_str,
-        }))
+            new_version: package_info.version,
+            version_req: version_req_str,
+            target,
+            is_upgrade,
+            source: package_info.source,
+        })))
     } else {
         Err(BinstallError::NoFallbackToCargoInstall)
     }
@@ -207,21 +267,47 @@ async fn resolve_inner(
 ///
 /// Can return empty Vec if all `BinFile` is optional and does not exist
 /// in the archive downloaded.
+///
+/// If `verify_arch` is true, the bin files are checked to be built for the
+/// target of `fetcher`, and to not require a glibc newer than `host_glibc`,
+/// so that the next fetcher, e.g. for the musl target, is used instead.
+///
+/// Return the bin files along with SHA256 of the package downloaded.
 async fn download_extract_and_verify(
     fetcher: &dyn Fetcher,
     bin_path: &Path,
     package_info: &PackageInfo,
     install_path: &Path,
     no_symlinks: bool,
-) -> Result<Vec<bins::BinFile>, BinstallError> {
+    verify_arch: bool,
+    host_glibc: Option<(u32, u32)>,
+) -> Result<(Vec<bins::BinFile>, Option<CompactString>), BinstallError> {
     // Download and extract it.
     // If that fails, then ignore this fetcher.
     let extracted_files = fetcher.fetch_and_extract(bin_path).await?;
     debug!("extracted_files = {extracted_files:#?}");
 
+    let package_sha256 = extracted_files.sha256().map(CompactString::from);
+
+    if let Some(expected) = &package_info.expected_sha256 {
+        let found = package_sha256.as_deref().unwrap_or_default();
+        if !found.eq_ignore_ascii_case(expected) {
+            return Err(BinstallError::PackageChecksumMismatch {
+                url: fetcher.source_name(),
+                expected: expected.clone(),
+                found: found.into(),
+            });
+        }
+    }
+
     // Build final metadata
     let meta = fetcher.target_meta();
 
+    // Search for the binaries if `bin-dir` is inferred or set to auto.
+    let search_bins = meta.bin_dir.as_ref().map_or(true, |bin_dirs| {
+        bin_dirs.iter().any(|bin_dir| bin_dir == bins::AUTO_BIN_DIR)
+    });
+
     // Verify that all non-optional bin_files exist
     let bin_files = collect_bin_files(
         fetcher,
@@ -230,7 +316,7 @@ async fn download_extract_and_verify(
         bin_path,
         install_path,
         no_symlinks,
-        &extracted_files,
+        Some(&extracted_files),
     )?;
 
     let name = &package_info.name;
@@ -239,7 +325,11 @@ async fn download_extract_and_verify(
         .binaries
         .iter()
         .zip(bin_files)
-        .filter_map(|(bin, bin_file)| {
+        .filter_map(|(bin, mut bin_file)| {
+            if search_bins && !extracted_files.has_file(&bin_file.archive_source_path) {
+                bin_file.search_source(bin_path, extracted_files.files());
+            }
+
             match bin_file.check_source_exists(&mut |p| extracted_files.has_file(p)) {
                 Ok(()) => Some(Ok(bin_file)),
 
@@ -268,10 +358,84 @@ But since it requies features {features}, this bin is ignored."
                 }
             }
         })
+        .map(|res| {
+            let bin_file = res?;
+            if verify_arch {
+                // Refuse mis-tagged release assets, which would otherwise
+                // fail at runtime with a cryptic exec error.
+                bin_file.verify_arch(fetcher.target())?;
+
+                // Refuse gnu binaries built against a newer glibc, which
+                // would fail at runtime with "GLIBC_2.xx not found".
+                if let Some(host_glibc) = host_glibc {
+                    bin_file.verify_glibc(host_glibc)?;
+                }
+            }
+            Ok(bin_file)
+        })
         .collect::<Result<Vec<bins::BinFile>, bins::Error>>()
+        .map(|bin_files| (bin_files, package_sha256))
         .map_err(BinstallError::from)
 }
 
+/// Create a fetcher for every desired target and resolver, in the order
+/// they are tried, along with whether the crate disables it.
+async fn create_fetchers(
+    opts: &Options,
+    package_info: &PackageInfo,
+) -> Result<Vec<(Arc<dyn Fetcher>, bool)>, BinstallError> {
+    let desired_targets = opts
+        .desired_targets
+        .get()
+        .await
+        .iter()
+        .map(|target| TargetTriple::from_str(target).map(|triple| (triple, target)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let resolvers = &opts.resolvers;
+
+    let data = Arc::new(Data::new(
+        package_info.name.clone(),
+        package_info.version_str.clone(),
+        package_info.repo.clone(),
+    ));
+
+    Ok(desired_targets
+        .into_iter()
+        .map(|(triple, target)| {
+            debug!("Building metadata for target: {target}");
+
+            let target_meta = package_info.meta.merge_overrides(
+                iter::once(&opts.cli_overrides).chain(package_info.overrides.get(target)),
+            );
+
+            debug!("Found metadata: {target_meta:?}");
+
+            Arc::new(TargetData {
+                target: target.clone(),
+                meta: target_meta,
+                target_related_info: triple,
+            })
+        })
+        .cartesian_product(resolvers)
+        .map(|(target_data, f)| {
+            let fetcher = f(
+                opts.client.clone(),
+                opts.gh_api_client.clone(),
+                data.clone(),
+                target_data,
+                opts.signature_policy,
+            );
+
+            let is_disabled = fetcher
+                .target_data()
+                .meta
+                .is_strategy_disabled(fetcher.strategy());
+
+            (fetcher, is_disabled)
+        })
+        .collect())
+}
+
 fn collect_bin_files(
     fetcher: &dyn Fetcher,
     package_info: &PackageInfo,
@@ -279,8 +443,10 @@ fn collect_bin_files(
     bin_path: &Path,
     install_path: &Path,
     no_symlinks: bool,
-    extracted_files: &ExtractedFiles,
+    // `None` if the package is not downloaded, e.g. in dry-run mode.
+    extracted_files: Option<&ExtractedFiles>,
 ) -> Result<Vec<bins::BinFile>, BinstallError> {
+    let has_file = |p: &Path| extracted_files.map_or(false, |files| files.has_file(p));
     // List files to be installed
     // based on those found via Cargo.toml
     let bin_data = bins::Data {
@@ -294,23 +460,62 @@ fn collect_bin_files(
         target_related_info: &fetcher.target_data().target_related_info,
     };
 
-    let bin_dir = bin_data
+    let new_bin_files = |bin_dir: &str| -> Result<Vec<bins::BinFile>, BinstallError> {
+        let template = Template::parse(bin_dir)?;
+
+        package_info
+            .binaries
+            .iter()
+            .map(|bin| bins::BinFile::new(&bin_data, bin.name.as_str(), &template, no_symlinks))
+            .collect::<Result<Vec<_>, bins::Error>>()
+            .map_err(BinstallError::from)
+    };
+
+    let mut bin_dirs = bin_data
         .meta
         .bin_dir
-        .as_deref()
-        .map(Cow::Borrowed)
-        .unwrap_or_else(|| {
-            bins::infer_bin_dir_template(&bin_data, &mut |p| extracted_files.get_dir(p).is_some())
-        });
-
-    let template = Template::parse(&bin_dir)?;
+        .iter()
+        .flat_map(|bin_dirs| bin_dirs.iter())
+        .filter(|bin_dir| *bin_dir != bins::AUTO_BIN_DIR)
+        .peekable();
 
     // Create bin_files
-    let bin_files = package_info
-        .binaries
-        .iter()
-        .map(|bin| bins::BinFile::new(&bin_data, bin.name.as_str(), &template, no_symlinks))
-        .collect::<Result<Vec<_>, bins::Error>>()?;
+    let bin_files = if bin_dirs.peek().is_none() {
+        new_bin_files(&bins::infer_bin_dir_template(&bin_data, &mut |p| {
+            extracted_files.map_or(false, |files| files.get_dir(p).is_some())
+        }))?
+    } else {
+        // Use the first bin-dir all required binaries are found with,
+        // or the first one if there is no such bin-dir.
+        let mut first = None;
+        let mut found = None;
+
+        for bin_dir in bin_dirs {
+            let bin_files = new_bin_files(bin_dir)?;
+
+            let is_found = package_info
+                .binaries
+                .iter()
+                .zip(&bin_files)
+                .all(|(bin, bin_file)| {
+                    !bin.required_features.is_empty() || has_file(&bin_file.archive_source_path)
+                });
+
+            if is_found {
+                debug!(
+                    "Found binaries of {} with bin-dir {bin_dir}",
+                    package_info.name
+                );
+                found = Some(bin_files);
+                break;
+            }
+
+            first.get_or_insert(bin_files);
+        }
+
+        // bin_dirs is not empty, so either of them must be Some.
+        found.or(first).unwrap()
+    };
 
     let mut source_set = BTreeSet::new();
 
@@ -333,6 +538,9 @@ struct PackageInfo {
     version: Version,
     repo: Option<String>,
     overrides: BTreeMap<String, PkgOverride>,
+    source: CrateSource,
+    /// SHA256 the package downloaded must match.
+    expected_sha256: Option<CompactString>,
 }
 
 struct Bin {
@@ -353,6 +561,9 @@ impl PackageInfo {
 
         // Fetch crate via crates.io, git, or use a local manifest path
         let manifest = match opts.cargo_toml_fetch_override.as_ref() {
+            Some(Url { url, sha256 }) => {
+                return Self::from_url(name, url, sha256.as_ref(), curr_version, version_req)
+            }
             Some(Path(manifest_path)) => {
                 let manifest_path = manifest_path.clone();
                 let name = name.clone();
@@ -383,10 +594,12 @@ impl PackageInfo {
                 ret
             }
             None => {
-                Box::pin(
-                    opts.registry
-                        .fetch_crate_matched(client, &name, version_req),
-                )
+                Box::pin(opts.registry.fetch_crate_matched(
+                    client,
+                    &name,
+                    version_req,
+                    opts.include_prereleases,
+                ))
                 .await?
             }
         };
@@ -417,7 +630,7 @@ impl PackageInfo {
             }
         }
 
-        let (mut meta, binaries): (_, Vec<Bin>) = (
+        let (mut meta, mut binaries): (_, Vec<Bin>) = (
             package
                 .metadata
                 .take()
@@ -435,6 +648,34 @@ impl PackageInfo {
                 .collect(),
         );
 
+        for issue in validate_meta(&meta) {
+            warn!(
+                "Found an issue in the binstall metadata of {name}:\n{:?}",
+                miette::Report::new(issue)
+            );
+        }
+
+        if meta.meta_version > Some(META_VERSION) {
+            // Newer metadata might not mean the same to this version, do not
+            // guess the packages from it.
+            meta = PkgMeta {
+                disabled_strategies: Some(Box::new([Strategy::CrateMetaData])),
+                ..Default::default()
+            };
+        }
+
+        // Only keep the binaries selected by the user
+        if let Some(bins) = &opts.bins {
+            if let Some(bin) = bins
+                .iter()
+                .find(|bin| !binaries.iter().any(|b| b.name == bin.as_str()))
+            {
+                return Err(BinstallError::BinNotFound(bin.clone()));
+            }
+
+            binaries.retain(|b| bins.iter().any(|bin| b.name == bin.as_str()));
+        }
+
         // Check binaries
         if binaries.is_empty() {
             Err(BinstallError::UnspecifiedBinaries)
@@ -447,9 +688,104 @@ impl PackageInfo {
                 version_str: new_version_str,
                 version: new_version,
                 repo: package.repository().map(ToString::to_string),
+                source: CrateSource::cratesio_registry(),
+                expected_sha256: None,
             }))
         }
     }
+
+    /// Package of the binary `name` downloaded from `url` directly, without
+    /// a `Cargo.toml`.
+    ///
+    /// `curr_version` is only specified for crates installed by cargo or
+    /// binstall, since packages installed from urls are not recorded in
+    /// the manifests of cargo, so it is an error unless `--force` is on.
+    fn from_url(
+        name: CompactString,
+        url: &Url,
+        sha256: Option<&CompactString>,
+        curr_version: Option<Version>,
+        version_req: &VersionReq,
+    ) -> Result<Option<Self>, BinstallError> {
+        if curr_version.is_some() {
+            return Err(BinstallError::InstalledAsCrate(name));
+        }
+
+        let version = version_of_url(url, version_req)?;
+
+        // Braces in the url are not placeholders.
+        let pkg_url = url.as_str().replace('{', "\\{").replace('}', "\\}");
+
+        Ok(Some(Self {
+            meta: PkgMeta {
+                pkg_url: Some(pkg_url.into()),
+                // The layout of the package is unknown, search it for the binary.
+                bin_dir: Some(bins::AUTO_BIN_DIR.into()),
+                disabled_strategies: Some(Box::new([Strategy::QuickInstall, Strategy::Compile])),
+                ..Default::default()
+            },
+            binaries: vec![Bin {
+                name: name.to_string(),
+                required_features: Vec::new(),
+            }],
+            name,
+            version_str: version.to_compact_string(),
+            version,
+            repo: None,
+            overrides: BTreeMap::new(),
+            source: CrateSource::url(url.clone()),
+            expected_sha256: sha256.cloned(),
+        }))
+    }
+}
+
+/// Return the version of the package at `url`.
+///
+/// There is no registry to resolve `version_req` against, so the lowest
+/// version matching it is used, e.g. `1.2.0` for `1.2`. Without a version
+/// requirement, the version is taken from the file name of the url, e.g.
+/// `1.2.3` for `tool-v1.2.3-x86_64-unknown-linux-musl.tar.gz`, and
+/// `0.0.0` if there is none.
+fn version_of_url(url: &Url, version_req: &VersionReq) -> Result<Version, BinstallError> {
+    if *version_req == VersionReq::STAR {
+        let file_name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default();
+
+        return Ok(file_name
+            .split(['-', '_'])
+            .find_map(|part| {
+                let part = part.strip_prefix('v').unwrap_or(part);
+                let mut numbers = part.splitn(4, '.').map(|n| n.parse().ok());
+                Some(Version::new(
+                    numbers.next()??,
+                    numbers.next()??,
+                    numbers.next()??,
+                ))
+            })
+            .unwrap_or(Version::new(0, 0, 0)));
+    }
+
+    match &version_req.comparators[..] {
+        [comparator] if matches!(comparator.op, Op::Exact | Op::Caret | Op::Tilde) => {
+            let mut version = Version::new(
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            );
+            version.pre = comparator.pre.clone();
+            Ok(version)
+        }
+        // Ranges do not specify a version to install.
+        _ => {
+            let v = version_req.to_compact_string();
+            Err(match Version::parse(&v) {
+                Err(err) => Box::new(VersionParseError { v, err }).into(),
+                Ok(_) => BinstallError::SuperfluousVersionOption,
+            })
+        }
+    }
 }
 
 /// Load binstall metadata from the crate `Cargo.toml` at the provided path
@@ -474,3 +810,39 @@ pub fn load_manifest_path<P: AsRef<Path>, N: AsRef<str>>(
 
     inner(manifest_path.as_ref(), name.as_ref())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_of_url() {
+        let version_of = |url: &str, version_req: &str| {
+            version_of_url(
+                &Url::parse(url).unwrap(),
+                &VersionReq::parse(version_req).unwrap(),
+            )
+        };
+
+        let url = "https://example.com/v9.9.9/tool-v1.2.3-x86_64-unknown-linux-musl.tar.gz";
+        assert_eq!(version_of(url, "*").unwrap(), Version::new(1, 2, 3));
+        assert_eq!(
+            version_of("https://example.com/tool_1.2.3.zip", "*").unwrap(),
+            Version::new(1, 2, 3)
+        );
+        assert_eq!(
+            version_of("https://example.com/tool.tar.gz", "*").unwrap(),
+            Version::new(0, 0, 0)
+        );
+
+        assert_eq!(version_of(url, "2.0.1").unwrap(), Version::new(2, 0, 1));
+        assert_eq!(version_of(url, "=2.0.1").unwrap(), Version::new(2, 0, 1));
+        assert_eq!(version_of(url, "~2.1").unwrap(), Version::new(2, 1, 0));
+        assert_eq!(
+            version_of(url, "2.0.0-rc.1").unwrap(),
+            Version::parse("2.0.0-rc.1").unwrap()
+        );
+
+        assert!(version_of(url, ">=1, <2").is_err());
+    }
+}
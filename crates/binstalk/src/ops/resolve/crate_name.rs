@@ -76,6 +76,37 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| s.parse::<CrateName>().unwrap();
+
+        assert_eq!(
+            parse("just"),
+            CrateName {
+                name: "just".into(),
+                version_req: None
+            }
+        );
+        assert_eq!(
+            parse("just@1.14.0"),
+            CrateName {
+                name: "just".into(),
+                version_req: Some("=1.14.0".parse().unwrap())
+            }
+        );
+        assert_eq!(
+            parse("ripgrep@^13"),
+            CrateName {
+                name: "ripgrep".into(),
+                version_req: Some("^13".parse().unwrap())
+            }
+        );
+        assert_eq!(parse("ripgrep@^13").to_string(), "ripgrep@^13");
+
+        assert!("ripgrep@".parse::<CrateName>().is_err());
+        assert!("ripgrep@not-a-version".parse::<CrateName>().is_err());
+    }
+
     #[test]
     fn test_dedup() {
         // Base case 0: Empty input
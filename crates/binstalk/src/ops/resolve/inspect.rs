@@ -0,0 +1,341 @@
+//! Inspect the binstall metadata of a crate and look for its packages,
+//! without downloading or installing anything.
+
+use std::sync::Arc;
+
+use compact_str::CompactString;
+use semver::VersionReq;
+
+use super::{create_fetchers, CrateName, PackageInfo};
+use crate::{
+    errors::BinstallError,
+    helpers::{remote::Url, tasks::AutoAbortJoinHandle},
+    manifests::cargo_toml_binstall::PkgMeta,
+    ops::Options,
+};
+
+#[derive(Debug)]
+pub struct PackageReport {
+    pub name: CompactString,
+    pub version: CompactString,
+    pub repo: Option<String>,
+    /// Metadata declared by the crate, without the target overrides.
+    pub meta: PkgMeta,
+    /// Names of the binaries along with the features they require.
+    pub binaries: Vec<(String, Vec<String>)>,
+    /// Fetchers in the order they are tried.
+    pub fetchers: Vec<FetcherReport>,
+}
+
+#[derive(Debug)]
+pub struct FetcherReport {
+    pub target: CompactString,
+    pub fetcher_name: &'static str,
+    /// Metadata used for the target, with the overrides merged.
+    pub meta: PkgMeta,
+    pub status: FetcherStatus,
+}
+
+#[derive(Debug)]
+pub enum FetcherStatus {
+    /// Disabled by the crate via `disabled-strategies`.
+    Disabled,
+    /// The package is available at the url.
+    Found(Url),
+    NotFound,
+    Failed(BinstallError),
+}
+
+/// Fetch the manifest of `crate_name` and look for its packages with every
+/// fetcher for every desired target.
+///
+/// Packages are only probed, e.g. via HEAD requests, none is downloaded.
+pub async fn inspect(
+    opts: Arc<Options>,
+    crate_name: CrateName,
+) -> Result<PackageReport, BinstallError> {
+    let version_req = match (crate_name.version_req, &opts.version_req) {
+        (Some(_), Some(_)) => return Err(BinstallError::SuperfluousVersionOption),
+        (Some(version_req), None) => version_req,
+        (None, version_req) => version_req.clone().unwrap_or(VersionReq::STAR),
+    };
+
+    let name = crate_name.name;
+    let package_info =
+        PackageInfo::resolve(&opts, name.clone(), None, &version_req, opts.client.clone())
+            .await
+            .map_err(|err| err.crate_context(name))?
+            // `None` is only returned if `curr_version` is specified.
+            .unwrap();
+
+    // Look for the packages with all fetchers at once.
+    let handles: Vec<_> = create_fetchers(&opts, &package_info)
+        .await?
+        .into_iter()
+        .map(|(fetcher, is_disabled)| {
+            let handle = (!is_disabled).then(|| AutoAbortJoinHandle::new(fetcher.clone().find()));
+            (fetcher, handle)
+        })
+        .collect();
+
+    let mut fetchers = Vec::with_capacity(handles.len());
+
+    for (fetcher, handle) in handles {
+        let status = match handle {
+            None => FetcherStatus::Disabled,
+            Some(handle) => match handle.flattened_join().await {
+                Ok(true) => fetcher
+                    .package_url()
+                    .cloned()
+                    .map_or(FetcherStatus::NotFound, FetcherStatus::Found),
+                Ok(false) => FetcherStatus::NotFound,
+                Err(err) => FetcherStatus::Failed(err),
+            },
+        };
+
+        fetchers.push(FetcherReport {
+            target: fetcher.target().into(),
+            fetcher_name: fetcher.fetcher_name(),
+            meta: fetcher.target_data().meta.clone(),
+            status,
+        });
+    }
+
+    Ok(PackageReport {
+        name: package_info.name,
+        version: package_info.version_str,
+        repo: package_info.repo,
+        meta: package_info.meta,
+        binaries: package_info
+            .binaries
+            .into_iter()
+            .map(|bin| (bin.name, bin.required_features))
+            .collect(),
+        fetchers,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        num::{NonZeroU16, NonZeroU64},
+        path::Path,
+    };
+
+    use compact_str::ToCompactString;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::{
+        fetchers::{Data, FetchError, Fetcher, SignaturePolicy, TargetDataErased},
+        get_desired_targets,
+        helpers::{
+            download::ExtractedFiles, gh_api_client::GhApiClient,
+            jobserver_client::LazyJobserverClient, remote::Client,
+        },
+        manifests::cargo_toml_binstall::{PkgFmt, PkgOverride},
+        ops::CargoTomlFetchOverride,
+    };
+
+    const FOUND_TARGET: &str = "x86_64-unknown-linux-gnu";
+    const NOT_FOUND_TARGET: &str = "aarch64-unknown-linux-gnu";
+
+    /// Finds the package only for [`FOUND_TARGET`].
+    struct MockFetcher {
+        url: Url,
+        target_data: Arc<TargetDataErased>,
+    }
+
+    #[async_trait::async_trait]
+    impl Fetcher for MockFetcher {
+        fn new(
+            _client: Client,
+            _gh_api_client: GhApiClient,
+            data: Arc<Data>,
+            target_data: Arc<TargetDataErased>,
+            _signature_policy: SignaturePolicy,
+        ) -> Arc<dyn Fetcher> {
+            let url = format!(
+                "https://example.com/{}-{}-{}.tgz",
+                data.name(),
+                data.version(),
+                target_data.target
+            );
+
+            Arc::new(Self {
+                url: Url::parse(&url).unwrap(),
+                target_data,
+            })
+        }
+
+        async fn fetch_and_extract(&self, _dst: &Path) -> Result<ExtractedFiles, FetchError> {
+            unreachable!("packages are never downloaded by inspect")
+        }
+
+        fn find(self: Arc<Self>) -> JoinHandle<Result<bool, FetchError>> {
+            tokio::spawn(async move { Ok(self.target_data.target == FOUND_TARGET) })
+        }
+
+        fn pkg_fmt(&self) -> PkgFmt {
+            self.target_data.meta.pkg_fmt.unwrap_or_default()
+        }
+
+        fn target_meta(&self) -> PkgMeta {
+            self.target_data.meta.clone()
+        }
+
+        fn source_name(&self) -> CompactString {
+            self.url.to_compact_string()
+        }
+
+        fn package_url(&self) -> Option<&Url> {
+            Some(&self.url)
+        }
+
+        fn fetcher_name(&self) -> &'static str {
+            "MockFetcher"
+        }
+
+        fn is_third_party(&self) -> bool {
+            false
+        }
+
+        fn target(&self) -> &str {
+            &self.target_data.target
+        }
+
+        fn target_data(&self) -> &Arc<TargetDataErased> {
+            &self.target_data
+        }
+    }
+
+    fn options(manifest_path: &Path, cli_overrides: PkgOverride) -> Options {
+        let client = Client::new(
+            "binstalk-test",
+            None,
+            NonZeroU16::new(10).unwrap(),
+            NonZeroU64::new(1).unwrap(),
+            [],
+        )
+        .unwrap();
+
+        Options {
+            no_symlinks: false,
+            dry_run: true,
+            force: false,
+            quiet: true,
+            locked: false,
+            no_track: true,
+            strict: false,
+            no_verify_arch: false,
+
+            version_req: None,
+            include_prereleases: false,
+            bins: None,
+            cargo_toml_fetch_override: Some(CargoTomlFetchOverride::Path(manifest_path.to_owned())),
+            cli_overrides,
+
+            desired_targets: get_desired_targets(Some(vec![
+                FOUND_TARGET.to_owned(),
+                NOT_FOUND_TARGET.to_owned(),
+            ])),
+            resolvers: vec![MockFetcher::new],
+            cargo_install_fallback: false,
+
+            temp_dir: manifest_path.to_owned(),
+            install_path: manifest_path.to_owned(),
+            cargo_root: None,
+
+            gh_api_client: GhApiClient::new(client.clone(), None),
+            client,
+            jobserver_client: LazyJobserverClient::new(),
+            registry: Default::default(),
+
+            signature_policy: SignaturePolicy::IfPresent,
+            report_to_upstream: false,
+            trusted_hosts: Vec::new(),
+            only_trusted_hosts: false,
+        }
+    }
+
+    fn write_crate(dir: &Path, binstall_metadata: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            format!(
+                r#"[package]
+name = "a"
+version = "0.1.0"
+
+[[bin]]
+name = "a-bin"
+path = "src/main.rs"
+
+[package.metadata.binstall]
+{binstall_metadata}
+"#
+            ),
+        )
+        .unwrap();
+    }
+
+    async fn inspect_crate(opts: Options) -> PackageReport {
+        let crate_name = CrateName {
+            name: "a".into(),
+            version_req: None,
+        };
+
+        inspect(Arc::new(opts), crate_name).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_inspect() {
+        let dir = tempfile::tempdir().unwrap();
+        write_crate(dir.path(), r#"pkg-fmt = "tgz""#);
+
+        let report = inspect_crate(options(
+            dir.path(),
+            PkgOverride {
+                pkg_fmt: Some(PkgFmt::Zip),
+                ..Default::default()
+            },
+        ))
+        .await;
+
+        assert_eq!(report.name, "a");
+        assert_eq!(report.version, "0.1.0");
+        assert_eq!(report.binaries, [("a-bin".to_owned(), Vec::new())]);
+        // The overrides are only merged into the metadata of the fetchers.
+        assert_eq!(report.meta.pkg_fmt, Some(PkgFmt::Tgz));
+
+        let [found, not_found] = &report.fetchers[..] else {
+            panic!("unexpected fetchers {:?}", report.fetchers);
+        };
+
+        assert_eq!(found.target, FOUND_TARGET);
+        assert_eq!(found.fetcher_name, "MockFetcher");
+        assert_eq!(found.meta.pkg_fmt, Some(PkgFmt::Zip));
+        assert!(
+            matches!(&found.status, FetcherStatus::Found(url) if url.as_str().ends_with(&format!("a-0.1.0-{FOUND_TARGET}.tgz")))
+        );
+
+        assert_eq!(not_found.target, NOT_FOUND_TARGET);
+        assert!(matches!(not_found.status, FetcherStatus::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_inspect_disabled_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        write_crate(dir.path(), r#"disabled-strategies = ["crate-meta-data"]"#);
+
+        let report = inspect_crate(options(dir.path(), PkgOverride::default())).await;
+
+        assert_eq!(report.fetchers.len(), 2);
+        assert!(report
+            .fetchers
+            .iter()
+            .all(|fetcher| matches!(fetcher.status, FetcherStatus::Disabled)));
+    }
+}
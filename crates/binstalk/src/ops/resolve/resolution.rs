@@ -1,19 +1,34 @@
-use std::{borrow::Cow, env, ffi::OsStr, fmt, iter, path::Path, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    env,
+    ffi::OsStr,
+    fmt, iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use command_group::AsyncCommandGroup;
 use compact_str::{CompactString, ToCompactString};
 use either::Either;
 use itertools::Itertools;
+use leon::Template;
 use semver::Version;
 use tokio::process::Command;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 use crate::{
     bins,
     errors::BinstallError,
     fetchers::Fetcher,
-    manifests::crate_info::{CrateInfo, CrateSource},
-    ops::Options,
+    helpers::target_triple::TargetTriple,
+    manifests::{
+        cargo_toml_binstall::PkgMeta,
+        crate_info::{CrateInfo, CrateSource},
+    },
+    ops::{CargoTomlFetchOverride, Options},
 };
 
 pub struct ResolutionFetch {
@@ -22,16 +37,49 @@ pub struct ResolutionFetch {
     pub name: CompactString,
     pub version_req: CompactString,
     pub bin_files: Vec<bins::BinFile>,
+    /// True if it upgrades a version of the crate tracked in the
+    /// installation root.
+    pub is_upgrade: bool,
+    /// SHA256 of the package downloaded, hex-encoded.
+    ///
+    /// `None` in dry-run mode, since nothing is downloaded.
+    pub package_sha256: Option<CompactString>,
+    pub source: CrateSource,
 }
 
 pub struct ResolutionSource {
     pub name: CompactString,
-    pub version: CompactString,
+    pub new_version: Version,
+    pub version_req: CompactString,
+    /// Target the crate is built for.
+    pub target: CompactString,
+    /// True if it upgrades a version of the crate tracked in the
+    /// installation root.
+    pub is_upgrade: bool,
+    /// Binaries of the crate to install.
+    pub bins: Vec<CompactString>,
+    pub source: CrateSource,
+}
+
+/// A crate built from source by [`ResolutionSource::build`], ready to be
+/// installed.
+pub struct BuiltSource {
+    pub name: CompactString,
+    pub new_version: Version,
+    pub version_req: CompactString,
+    pub target: CompactString,
+    pub bin_files: Vec<bins::BinFile>,
+    pub is_upgrade: bool,
+    /// SHA256 of the crate tarball downloaded, hex-encoded.
+    ///
+    /// `None` if the crate is built from `--manifest-path` or `--git`.
+    pub package_sha256: Option<CompactString>,
+    pub source: CrateSource,
 }
 
 pub enum Resolution {
     Fetch(Box<ResolutionFetch>),
-    InstallFromSource(ResolutionSource),
+    InstallFromSource(Box<ResolutionSource>),
     AlreadyUpToDate,
 }
 
@@ -50,41 +98,31 @@ impl Resolution {
 }
 
 impl ResolutionFetch {
+    #[instrument(skip_all, fields(crate_name = %self.name))]
     pub fn install(self, opts: &Options) -> Result<CrateInfo, BinstallError> {
-        type InstallFp = fn(&bins::BinFile) -> Result<(), bins::Error>;
-
-        let (install_bin, install_link): (InstallFp, InstallFp) = match (opts.no_track, opts.force)
-        {
-            (true, true) | (false, _) => (bins::BinFile::install_bin, bins::BinFile::install_link),
-            (true, false) => (
-                bins::BinFile::install_bin_noclobber,
-                bins::BinFile::install_link_noclobber,
-            ),
-        };
-
-        info!("Installing binaries...");
-        for file in &self.bin_files {
-            install_bin(file)?;
-        }
-
-        // Generate symlinks
-        if !opts.no_symlinks {
-            for file in &self.bin_files {
-                install_link(file)?;
-            }
-        }
+        let bins_sha256 = install_bin_files(
+            opts,
+            &self.name,
+            &self.new_version,
+            &self.bin_files,
+            self.is_upgrade,
+        )?;
 
         Ok(CrateInfo {
             name: self.name,
             version_req: self.version_req,
             current_version: self.new_version,
-            source: CrateSource::cratesio_registry(),
+            source: self.source,
             target: self.fetcher.target().to_compact_string(),
             bins: self
                 .bin_files
                 .into_iter()
                 .map(|bin| bin.base_name)
                 .collect(),
+            package_sha256: self.package_sha256,
+            bins_sha256,
+            package_source: Some(self.fetcher.source_name()),
+            install_time: now(),
         })
     }
 
@@ -100,8 +138,19 @@ impl ResolutionFetch {
             fetcher.source_name(),
         );
 
+        let action = if opts.dry_run {
+            "will be downloaded"
+        } else {
+            "has been downloaded"
+        };
+
         warn!(
-            "The package {name} v{new_version} ({target}) has been downloaded from {}{}",
+            crate_name = %name,
+            version = %new_version,
+            target_triple = target,
+            fetcher = fetcher.fetcher_name(),
+            url = fetcher.package_url().map(|url| url.as_str()),
+            "The package {name} v{new_version} ({target}) {action} from {}{}",
             if fetcher.is_third_party() {
                 "third-party source "
             } else {
@@ -110,6 +159,13 @@ impl ResolutionFetch {
             fetcher.source_name()
         );
 
+        if let Some(url) = fetcher.package_url() {
+            info!("Package url: {url}");
+        }
+        if let Some(sha256) = &self.package_sha256 {
+            info!("Package sha256: {sha256}");
+        }
+
         info!("This will install the following binaries:");
         for file in bin_files {
             info!("  - {}", file.preview_bin());
@@ -125,15 +181,26 @@ impl ResolutionFetch {
 }
 
 impl ResolutionSource {
-    pub async fn install(self, opts: Arc<Options>) -> Result<(), BinstallError> {
-        let target = if let Some(targets) = opts.desired_targets.get_initialized() {
-            Some(targets.first().ok_or(BinstallError::NoViableTargets)?)
-        } else {
-            None
-        };
-
+    /// Download the source of the crate and build it with `cargo build` in
+    /// [`Options::temp_dir`], so that neither the `target` dir nor the
+    /// install tracking of cargo is touched.
+    ///
+    /// Return `None` in dry-run mode, or if no binary is built, e.g. all of
+    /// them require features that are not enabled by default.
+    #[instrument(skip_all, fields(crate_name = %self.name))]
+    pub async fn build(self, opts: Arc<Options>) -> Result<Option<BuiltSource>, BinstallError> {
+        let target = &self.target;
         let name = &self.name;
-        let version = &self.version;
+        let version = &self.new_version;
+
+        if opts.dry_run {
+            info!("Dry-run: {name} v{version} ({target}) will be built from source with cargo");
+            return Ok(None);
+        }
+
+        let build_dir = opts.temp_dir.join(format!("build-{name}-{version}"));
+        let (manifest_path, package_sha256) =
+            download_source(&opts, name, version, &build_dir).await?;
 
         let cargo = env::var_os("CARGO")
             .map(Cow::Owned)
@@ -141,72 +208,358 @@ impl ResolutionSource {
 
         let mut cmd = Command::new(cargo);
 
-        cmd.arg("install")
-            .arg(name)
-            .arg("--version")
-            .arg(version)
+        cmd.arg("build")
+            .arg("--release")
+            .arg("--manifest-path")
+            .arg(&manifest_path)
+            .arg("--package")
+            .arg(name.as_str())
+            .arg("--target-dir")
+            .arg(build_dir.join("target"))
             .kill_on_drop(true);
 
-        if let Some(target) = target {
-            cmd.arg("--target").arg(target);
+        // Like `cargo install`, only pass `--target` if it is specified by
+        // the user, otherwise the crate is built for the host, along with
+        // its build scripts and proc-macros sharing the same config.
+        let out_dir = if opts.desired_targets.get_initialized().is_some() {
+            cmd.arg("--target").arg(target.as_str());
+            build_dir
+                .join("target")
+                .join(target.as_str())
+                .join("release")
+        } else {
+            build_dir.join("target").join("release")
+        };
+
+        match &opts.bins {
+            Some(bins) => {
+                for bin in bins {
+                    cmd.arg("--bin").arg(bin.as_str());
+                }
+            }
+            None => {
+                cmd.arg("--bins");
+            }
         }
 
         if opts.quiet {
             cmd.arg("--quiet");
         }
 
-        if opts.force {
-            cmd.arg("--force");
-        }
-
         if opts.locked {
             cmd.arg("--locked");
         }
 
-        if let Some(cargo_root) = &opts.cargo_root {
-            cmd.arg("--root").arg(cargo_root);
-        }
+        debug!("Running `{}`", format_cmd(&cmd));
 
-        if opts.no_track {
-            cmd.arg("--no-track");
-        }
+        let mut child = opts
+            .jobserver_client
+            .get()
+            .await?
+            .configure_and_run(&mut cmd, |cmd| cmd.group_spawn())?;
 
-        debug!("Running `{}`", format_cmd(&cmd),);
+        debug!("Spawned command pid={:?}", child.id());
 
-        if !opts.dry_run {
-            let mut child = opts
-                .jobserver_client
-                .get()
-                .await?
-                .configure_and_run(&mut cmd, |cmd| cmd.group_spawn())?;
+        let status = child.wait().await?;
+        if status.success() {
+            info!("Cargo finished successfully");
+        } else {
+            error!("Cargo errored! {status:?}");
+            return Err(BinstallError::SubProcess {
+                command: format_cmd(&cmd).to_string().into_boxed_str(),
+                status,
+            });
+        }
+
+        let target_related_info = TargetTriple::from_str(target)?;
+        let version_str = version.to_compact_string();
+
+        let bin_data = bins::Data {
+            name,
+            target,
+            version: &version_str,
+            repo: None,
+            meta: PkgMeta::default(),
+            bin_path: &out_dir,
+            install_path: &opts.install_path,
+            target_related_info: &target_related_info,
+        };
+        let template = Template::parse(BUILT_BIN_TEMPLATE)?;
 
-            debug!("Spawned command pid={:?}", child.id());
+        // Bins requiring features not enabled are skipped by cargo
+        let mut bin_files = Vec::new();
+        for bin in &self.bins {
+            let bin_file = bins::BinFile::new(&bin_data, bin, &template, opts.no_symlinks)?;
 
-            let status = child.wait().await?;
-            if status.success() {
-                info!("Cargo finished successfully");
-                Ok(())
+            if bin_file.source.is_file() {
+                bin_files.push(bin_file);
             } else {
-                error!("Cargo errored! {status:?}");
-                Err(BinstallError::SubProcess {
-                    command: format_cmd(&cmd).to_string().into_boxed_str(),
-                    status,
-                })
+                warn!("{name} bin {bin} is not built, it probably requires features not enabled by default");
             }
-        } else {
-            info!("Dry-run: running `{}`", format_cmd(&cmd));
-            Ok(())
         }
+
+        if bin_files.is_empty() {
+            warn!("No binary of {name} v{version} is built, nothing to install");
+            return Ok(None);
+        }
+
+        Ok(Some(BuiltSource {
+            name: self.name,
+            new_version: self.new_version,
+            version_req: self.version_req,
+            target: self.target,
+            bin_files,
+            is_upgrade: self.is_upgrade,
+            package_sha256,
+            source: self.source,
+        }))
+    }
+
+    /// Return the paths the binaries are to be installed to, in
+    /// `install_path`.
+    pub fn bin_dests<'a>(&'a self, install_path: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        let binary_ext = if self.target.contains("windows") {
+            ".exe"
+        } else {
+            ""
+        };
+
+        self.bins
+            .iter()
+            .map(move |bin| install_path.join(format!("{bin}{binary_ext}")))
     }
 
     pub fn print(&self) {
         warn!(
-            "The package {} v{} will be installed from source (with cargo)",
-            self.name, self.version
+            "The package {} v{} will be built from source (with cargo)",
+            self.name, self.new_version
         )
     }
 }
 
+impl BuiltSource {
+    #[instrument(skip_all, fields(crate_name = %self.name))]
+    pub fn install(self, opts: &Options) -> Result<CrateInfo, BinstallError> {
+        let bins_sha256 = install_bin_files(
+            opts,
+            &self.name,
+            &self.new_version,
+            &self.bin_files,
+            self.is_upgrade,
+        )?;
+
+        Ok(CrateInfo {
+            name: self.name,
+            version_req: self.version_req,
+            current_version: self.new_version,
+            source: self.source,
+            target: self.target,
+            bins: self
+                .bin_files
+                .into_iter()
+                .map(|bin| bin.base_name)
+                .collect(),
+            package_sha256: self.package_sha256,
+            bins_sha256,
+            package_source: Some("cargo build".into()),
+            install_time: now(),
+        })
+    }
+}
+
+/// Path of binaries relative to the output dir of `cargo build`.
+const BUILT_BIN_TEMPLATE: &str = "{ bin }{ binary-ext }";
+
+/// Put the source of crate `name` in `build_dir`, downloading it from the
+/// registry unless `--manifest-path` or `--git` is used.
+///
+/// Return path to the manifest to build along with SHA256 of the crate
+/// tarball downloaded.
+async fn download_source(
+    opts: &Options,
+    name: &str,
+    version: &Version,
+    build_dir: &Path,
+) -> Result<(PathBuf, Option<CompactString>), BinstallError> {
+    match opts.cargo_toml_fetch_override.as_ref() {
+        Some(CargoTomlFetchOverride::Path(manifest_path)) => {
+            let manifest_path = if manifest_path.is_dir() {
+                manifest_path.join("Cargo.toml")
+            } else {
+                manifest_path.clone()
+            };
+
+            Ok((manifest_path, None))
+        }
+        #[cfg(feature = "git")]
+        Some(CargoTomlFetchOverride::Git(git_url)) => {
+            use crate::helpers::git::{GitCancellationToken, Repository as GitRepository};
+
+            let git_url = git_url.clone();
+            let dir = build_dir.join("src");
+            let cancellation_token = GitCancellationToken::default();
+            // Cancel git operation if the future is cancelled (dropped).
+            let cancel_on_drop = cancellation_token.clone().cancel_on_drop();
+
+            let manifest_path = tokio::task::spawn_blocking(move || {
+                GitRepository::shallow_clone(git_url, &dir, Some(cancellation_token))?;
+                Ok::<_, BinstallError>(dir.join("Cargo.toml"))
+            })
+            .await??;
+
+            // Git operation done, disarm it
+            cancel_on_drop.disarm();
+
+            Ok((manifest_path, None))
+        }
+        // There is no source to build the package from.
+        Some(CargoTomlFetchOverride::Url { .. }) => Err(BinstallError::NoFallbackToCargoInstall),
+        None => {
+            let package_sha256 = opts
+                .registry
+                .download_crate(opts.client.clone(), name, version, build_dir)
+                .await?;
+
+            Ok((
+                build_dir
+                    .join(format!("{name}-{version}"))
+                    .join("Cargo.toml"),
+                Some(package_sha256),
+            ))
+        }
+    }
+}
+
+/// Install `bin_files` of crate `name`, rolling back the binaries already
+/// installed on failure, and create their symlinks.
+///
+/// Binaries found in the download cache are hard linked from it, so that
+/// installing the same binary to multiple roots does not copy it again.
+///
+/// Return SHA256 of each binary installed, keyed by its base name.
+fn install_bin_files(
+    opts: &Options,
+    name: &str,
+    new_version: &Version,
+    bin_files: &[bins::BinFile],
+    is_upgrade: bool,
+) -> Result<BTreeMap<CompactString, CompactString>, BinstallError> {
+    type InstallFp = fn(&bins::BinFile) -> Result<(), bins::Error>;
+    type HardLinkFp = fn(&bins::BinFile, &Path) -> Result<(), bins::Error>;
+
+    // Without tracking or in strict mode, existing binaries might not be
+    // installed by binstall or cargo, so they are not overwritten unless forced.
+    let noclobber = !opts.force && (opts.no_track || (opts.strict && !is_upgrade));
+
+    let (install_bin, install_bin_hard_link, install_link): (InstallFp, HardLinkFp, InstallFp) =
+        if noclobber {
+            (
+                bins::BinFile::install_bin_noclobber,
+                bins::BinFile::install_bin_hard_link_noclobber,
+                bins::BinFile::install_link_noclobber,
+            )
+        } else {
+            (
+                bins::BinFile::install_bin,
+                bins::BinFile::install_bin_hard_link,
+                bins::BinFile::install_link,
+            )
+        };
+
+    if let Err(err) = bins::remove_stale_files(&opts.install_path) {
+        warn!("Failed to remove stale files left by previous installations: {err}");
+    }
+
+    let download_cache = opts.client.download_cache();
+
+    // Backup existing binaries, so that a failed installation would not
+    // leave the crate half-installed.
+    let backups = bin_files
+        .iter()
+        .map(bins::BinFile::backup)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    info!("Installing binaries...");
+    let mut bins_sha256 = BTreeMap::new();
+    let res = bin_files.iter().try_for_each(|file| {
+        // If the same binary is installed already, e.g. to another root,
+        // hard link it from the cache instead of copying it.
+        let cached = download_cache.and_then(|download_cache| {
+            let sha256 = bins::sha256_file(&file.source).ok()?;
+            let cached_bin = download_cache.get_bin(&sha256)?;
+            Some((download_cache, sha256, cached_bin))
+        });
+
+        let sha256 = match cached {
+            Some((download_cache, sha256, cached_bin)) => {
+                match install_bin_hard_link(file, &cached_bin) {
+                    Ok(()) => {
+                        debug!("Hard linked {} from the cache", file.dest.display());
+                        if let Ok(metadata) = cached_bin.metadata() {
+                            download_cache.record_linked_bin(metadata.len());
+                        }
+                    }
+                    Err(err) => {
+                        debug!("Failed to hard link {}: {err}", file.dest.display());
+                        install_bin(file)?;
+                    }
+                }
+                sha256
+            }
+            None => {
+                install_bin(file)?;
+
+                let sha256 = bins::sha256_file(&file.dest)?;
+                if let Some(download_cache) = download_cache {
+                    download_cache.put_bin(&file.dest, &sha256);
+                }
+                sha256
+            }
+        };
+
+        info!(
+            crate_name = %name,
+            version = %new_version,
+            bin = %file.dest.display(),
+            sha256 = %sha256,
+            "Installed {} (sha256 {sha256})",
+            file.dest.display()
+        );
+        bins_sha256.insert(file.base_name.clone(), sha256);
+
+        Ok::<_, bins::Error>(())
+    });
+
+    if let Err(err) = res {
+        warn!("Failed to install {name}, rolling back installed binaries");
+
+        for backup in backups.into_iter().rev() {
+            if let Err(err) = backup.restore() {
+                error!("Failed to roll back binary: {err}");
+            }
+        }
+
+        return Err(err.into());
+    }
+    drop(backups);
+
+    // Generate symlinks
+    if !opts.no_symlinks {
+        for file in bin_files {
+            install_link(file)?;
+        }
+    }
+
+    Ok(bins_sha256)
+}
+
+fn now() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
 fn format_cmd(cmd: &Command) -> impl fmt::Display + '_ {
     let cmd = cmd.as_std();
 
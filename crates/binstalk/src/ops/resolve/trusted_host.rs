@@ -0,0 +1,66 @@
+use compact_str::CompactString;
+
+use crate::helpers::remote::Url;
+
+/// Hosts that are always trusted.
+const DEFAULT_TRUSTED_HOSTS: &[&str] = &["github.com", "gitlab.com"];
+
+/// Return `true` if `url` is on one of the default trusted hosts or
+/// `trusted_hosts`, or a subdomain of them.
+pub(super) fn is_trusted_url(url: &Url, trusted_hosts: &[CompactString]) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+
+    DEFAULT_TRUSTED_HOSTS
+        .iter()
+        .copied()
+        .chain(trusted_hosts.iter().map(CompactString::as_str))
+        .any(|trusted| {
+            // Host of special schemes like https is always lowercase
+            let trusted = trusted.trim_end_matches('.').to_ascii_lowercase();
+
+            host == trusted
+                || host
+                    .strip_suffix(&*trusted)
+                    .map_or(false, |subdomain| subdomain.ends_with('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_trusted(url: &str, trusted_hosts: &[&str]) -> bool {
+        let trusted_hosts: Vec<CompactString> = trusted_hosts
+            .iter()
+            .copied()
+            .map(CompactString::from)
+            .collect();
+        is_trusted_url(&Url::parse(url).unwrap(), &trusted_hosts)
+    }
+
+    #[test]
+    fn test_is_trusted_url() {
+        assert!(is_trusted(
+            "https://github.com/cargo-bins/cargo-binstall/releases/download/v1.0.0/a.tgz",
+            &[]
+        ));
+        assert!(is_trusted("https://gitlab.com/a/b/-/releases/a.tgz", &[]));
+        assert!(is_trusted("https://objects.github.com/a.tgz", &[]));
+
+        assert!(!is_trusted("https://example.com/a.tgz", &[]));
+        assert!(!is_trusted("https://evilgithub.com/a.tgz", &[]));
+        assert!(!is_trusted("https://github.com.evil.com/a.tgz", &[]));
+
+        assert!(is_trusted("https://example.com/a.tgz", &["example.com"]));
+        assert!(is_trusted(
+            "https://dl.example.com/a.tgz",
+            &["example.com", "example.org"]
+        ));
+        assert!(!is_trusted(
+            "https://example.org.com/a.tgz",
+            &["example.org"]
+        ));
+    }
+}
@@ -96,4 +96,31 @@ mod tests {
             .unwrap()
             .is_latest_compatible(&Version::parse("0.1.0-alpha").unwrap()));
     }
+
+    #[test]
+    fn test_parse_from_cli() {
+        let matches = |req: &str, version: &str| {
+            VersionReq::parse_from_cli(req)
+                .unwrap()
+                .matches(&Version::parse(version).unwrap())
+        };
+
+        // Bare version is treated as exact version
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("1.2.3", "1.2.4"));
+
+        // Caret requirement
+        assert!(matches("^1.2", "1.9.0"));
+        assert!(!matches("^1.2", "2.0.0"));
+
+        // Range
+        assert!(matches(">=0.10, <0.12", "0.11.5"));
+        assert!(!matches(">=0.10, <0.12", "0.12.0"));
+
+        // Wildcard
+        assert!(matches("1.*", "1.7.0"));
+        assert!(!matches("1.*", "2.0.0"));
+
+        assert!(VersionReq::parse_from_cli("not-a-version").is_err());
+    }
 }
@@ -0,0 +1,383 @@
+//! Validate the binstall metadata of a crate, so that mistakes are reported
+//! along with the offending key instead of failing later with generic
+//! template or parse errors.
+
+use std::{fs, path::Path};
+
+use compact_str::CompactString;
+use itertools::Itertools;
+use leon::Template;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::{
+    errors::BinstallError,
+    helpers::cargo_toml::Manifest,
+    manifests::cargo_toml_binstall::{Meta, PkgMeta, Templates, META_VERSION},
+};
+
+const META_PREFIX: &str = "package.metadata.binstall";
+
+/// Keys supported in `[package.metadata.binstall]`.
+const PKG_META_KEYS: &[&str] = &[
+    "meta-version",
+    "pkg-url",
+    "pkg-fmt",
+    "bin-dir",
+    "signing",
+    "disabled-strategies",
+    "overrides",
+];
+
+/// Keys supported in `[package.metadata.binstall.overrides.<target>]`.
+const PKG_OVERRIDE_KEYS: &[&str] = &[
+    "pkg-url",
+    "pkg-fmt",
+    "bin-dir",
+    "signing",
+    "disabled-strategies",
+];
+
+const TARGET_PLACEHOLDERS: &[&str] = &[
+    "target-family",
+    "target-arch",
+    "target-libc",
+    "target-vendor",
+];
+
+const PKG_URL_PLACEHOLDERS: &[&str] = &[
+    "name",
+    "version",
+    "repo",
+    "target",
+    "archive-format",
+    "archive-suffix",
+    "binary-ext",
+    "subcrate",
+    "format",
+];
+
+const BIN_DIR_PLACEHOLDERS: &[&str] = &[
+    "name",
+    "version",
+    "repo",
+    "target",
+    "bin",
+    "binary-ext",
+    "format",
+];
+
+const PKG_URL_EXAMPLE: &str =
+    "{ repo }/releases/download/v{ version }/{ name }-{ target }{ archive-suffix }";
+const BIN_DIR_EXAMPLE: &str = "{ name }-{ target }/{ bin }{ binary-ext }";
+const SIGNATURE_FILE_EXAMPLE: &str = "{ url }.sig";
+
+/// A mistake in the binstall metadata of a crate.
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+pub enum MetaIssue {
+    /// The metadata is of a newer version than supported, so the
+    /// `crate-meta-data` strategy is skipped for the crate.
+    #[error(
+        "`{key} = {version}` is not supported, only metadata version {} and older are",
+        META_VERSION
+    )]
+    #[diagnostic(
+        severity(error),
+        code(binstall::meta::version),
+        help("Upgrade cargo-binstall to use the metadata of this crate.")
+    )]
+    UnsupportedVersion { key: String, version: u32 },
+
+    /// The key is ignored.
+    #[error("Unknown key `{key}`, it is ignored")]
+    #[diagnostic(severity(warning), code(binstall::meta::unknown_key), help("{help}"))]
+    UnknownKey { key: String, help: String },
+
+    #[error("Invalid template in `{key}`")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::meta::template),
+        help("Placeholders are enclosed in braces, e.g. `\"{example}\"`.")
+    )]
+    InvalidTemplate {
+        key: String,
+        example: &'static str,
+        #[source]
+        #[diagnostic_source]
+        err: leon::ParseError,
+    },
+
+    #[error("Unknown placeholder `{placeholder}` in `{key}`")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::meta::placeholder),
+        help("Supported placeholders are: {supported}.")
+    )]
+    UnknownPlaceholder {
+        key: String,
+        placeholder: String,
+        supported: String,
+    },
+}
+
+/// Validate `meta`, including its target specific overrides.
+pub fn validate_meta(meta: &PkgMeta) -> Vec<MetaIssue> {
+    let mut issues = Vec::new();
+
+    if let Some(version) = meta.meta_version {
+        if version > META_VERSION {
+            issues.push(MetaIssue::UnsupportedVersion {
+                key: format!("{META_PREFIX}.meta-version"),
+                version,
+            });
+        }
+    }
+
+    check_unknown_keys(&mut issues, META_PREFIX, &meta.unknown_keys, PKG_META_KEYS);
+    check_templates(
+        &mut issues,
+        META_PREFIX,
+        meta.pkg_url.as_ref(),
+        meta.bin_dir.as_ref(),
+        meta.signing
+            .as_ref()
+            .and_then(|signing| signing.file.as_deref()),
+    );
+
+    for (target, pkg_override) in &meta.overrides {
+        let prefix = format!("{META_PREFIX}.overrides.{target}");
+
+        check_unknown_keys(
+            &mut issues,
+            &prefix,
+            &pkg_override.unknown_keys,
+            PKG_OVERRIDE_KEYS,
+        );
+        check_templates(
+            &mut issues,
+            &prefix,
+            pkg_override.pkg_url.as_ref(),
+            pkg_override.bin_dir.as_ref(),
+            pkg_override
+                .signing
+                .as_ref()
+                .and_then(|signing| signing.file.as_deref()),
+        );
+    }
+
+    issues
+}
+
+/// Load the `Cargo.toml` at `manifest_path`, or in it if it is a dir, and
+/// validate its binstall metadata.
+///
+/// Return the name of the crate along with the issues found.
+///
+/// This function uses blocking I/O.
+pub fn check_metadata(
+    manifest_path: &Path,
+) -> Result<(CompactString, Vec<MetaIssue>), BinstallError> {
+    let manifest_path = if manifest_path.is_dir() {
+        manifest_path.join("Cargo.toml")
+    } else {
+        manifest_path.to_owned()
+    };
+
+    let manifest = Manifest::<Meta>::from_slice_with_metadata(&fs::read(&manifest_path)?)?;
+
+    let Some(package) = manifest.package else {
+        return Err(BinstallError::CargoTomlMissingPackage(
+            manifest_path.display().to_string().into(),
+        ));
+    };
+
+    let issues = package
+        .metadata
+        .and_then(|metadata| metadata.binstall)
+        .map(|meta| validate_meta(&meta))
+        .unwrap_or_default();
+
+    Ok((package.name.into(), issues))
+}
+
+fn check_unknown_keys<'a>(
+    issues: &mut Vec<MetaIssue>,
+    prefix: &str,
+    unknown_keys: impl IntoIterator<Item = &'a String>,
+    supported_keys: &[&str],
+) {
+    issues.extend(unknown_keys.into_iter().map(|key| {
+        let normalized = key.to_lowercase().replace('_', "-");
+
+        let help = if supported_keys.contains(&normalized.as_str()) {
+            format!("Did you mean `{normalized}`?")
+        } else if PKG_META_KEYS.contains(&key.as_str()) {
+            format!("`{key}` is not supported in overrides.")
+        } else {
+            format!(
+                "Supported keys are: {}.",
+                supported_keys.iter().format(", ")
+            )
+        };
+
+        MetaIssue::UnknownKey {
+            key: format!("{prefix}.{key}"),
+            help,
+        }
+    }));
+}
+
+fn check_templates(
+    issues: &mut Vec<MetaIssue>,
+    prefix: &str,
+    pkg_url: Option<&Templates>,
+    bin_dir: Option<&Templates>,
+    signature_file: Option<&str>,
+) {
+    let pkg_url_key = format!("{prefix}.pkg-url");
+    let bin_dir_key = format!("{prefix}.bin-dir");
+
+    for (key, template) in indexed_templates(&pkg_url_key, pkg_url) {
+        check_template(
+            issues,
+            key,
+            template,
+            PKG_URL_EXAMPLE,
+            &[PKG_URL_PLACEHOLDERS, TARGET_PLACEHOLDERS],
+        );
+    }
+
+    for (key, template) in indexed_templates(&bin_dir_key, bin_dir) {
+        // Search the package for the binaries.
+        if template != crate::bins::AUTO_BIN_DIR {
+            check_template(
+                issues,
+                key,
+                template,
+                BIN_DIR_EXAMPLE,
+                &[BIN_DIR_PLACEHOLDERS, TARGET_PLACEHOLDERS],
+            );
+        }
+    }
+
+    if let Some(template) = signature_file {
+        check_template(
+            issues,
+            format!("{prefix}.signing.file"),
+            template,
+            SIGNATURE_FILE_EXAMPLE,
+            &[&["url"], PKG_URL_PLACEHOLDERS, TARGET_PLACEHOLDERS],
+        );
+    }
+}
+
+/// Return the templates with their keys, suffixed by their index if
+/// `templates` is a list, e.g. `pkg-url[1]`.
+fn indexed_templates<'a>(
+    key: &'a str,
+    templates: Option<&'a Templates>,
+) -> impl Iterator<Item = (String, &'a str)> + 'a {
+    templates.into_iter().flat_map(move |templates| {
+        let is_list = matches!(templates, Templates::Many(_));
+
+        templates.iter().enumerate().map(move |(i, template)| {
+            if is_list {
+                (format!("{key}[{i}]"), template)
+            } else {
+                (key.to_owned(), template)
+            }
+        })
+    })
+}
+
+fn check_template(
+    issues: &mut Vec<MetaIssue>,
+    key: String,
+    template: &str,
+    example: &'static str,
+    placeholders: &[&[&str]],
+) {
+    let template = match Template::parse(template) {
+        Ok(template) => template,
+        Err(err) => {
+            issues.push(MetaIssue::InvalidTemplate { key, example, err });
+            return;
+        }
+    };
+
+    let supported = || placeholders.iter().copied().flatten();
+
+    issues.extend(
+        template
+            .keys()
+            .filter(|placeholder| !supported().any(|supported| supported == placeholder))
+            .unique()
+            .map(|placeholder| MetaIssue::UnknownPlaceholder {
+                key: key.clone(),
+                placeholder: placeholder.to_string(),
+                supported: supported().format(", ").to_string(),
+            }),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::manifests::cargo_toml_binstall::{PkgOverride, PkgSigning, SigningAlgorithm};
+
+    fn issue_keys(meta: &PkgMeta) -> Vec<String> {
+        validate_meta(meta)
+            .into_iter()
+            .map(|issue| match issue {
+                MetaIssue::UnsupportedVersion { key, .. }
+                | MetaIssue::UnknownKey { key, .. }
+                | MetaIssue::InvalidTemplate { key, .. }
+                | MetaIssue::UnknownPlaceholder { key, .. } => key,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_meta() {
+        let mut meta = PkgMeta {
+            pkg_url: Some(Templates::Many(vec![
+                "{ repo }/{ name | lowercase }-{ target | replace(x86_64-pc-windows-msvc, win64) }{ archive-suffix }".into(),
+                "{ repo }/{ name }-{ target".into(),
+                "{ repo }/{ nme | lowercase }".into(),
+            ])),
+            bin_dir: Some("auto".into()),
+            ..Default::default()
+        };
+        meta.unknown_keys.insert("pkg_fmt".into());
+        meta.overrides.insert(
+            "x86_64-pc-windows-msvc".into(),
+            PkgOverride {
+                bin_dir: Some("{ bin }{ binary-extension }".into()),
+                signing: Some(PkgSigning {
+                    algorithm: SigningAlgorithm::Minisign,
+                    pubkey: "key".into(),
+                    file: Some("{ url }.minisig".into()),
+                }),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            issue_keys(&meta),
+            [
+                "package.metadata.binstall.pkg_fmt",
+                "package.metadata.binstall.pkg-url[1]",
+                "package.metadata.binstall.pkg-url[2]",
+                "package.metadata.binstall.overrides.x86_64-pc-windows-msvc.bin-dir",
+            ]
+        );
+
+        meta.meta_version = Some(META_VERSION + 1);
+        assert_eq!(
+            issue_keys(&meta)[0],
+            "package.metadata.binstall.meta-version"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Verify installed binaries against the checksums recorded when
+//! installing them.
+
+use std::{io, path::Path};
+
+use compact_str::CompactString;
+
+use crate::{bins, errors::BinstallError, manifests::crate_info::CrateInfo};
+
+/// State of an installed binary compared to its recorded checksum.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BinStatus {
+    /// The binary is unchanged since installation.
+    Intact,
+    /// The binary has been modified or replaced since installation.
+    Modified { found: CompactString },
+    /// The binary has been removed.
+    Missing,
+    /// No checksum is recorded for the binary, e.g. it is installed by
+    /// an older version of binstall.
+    Unrecorded,
+}
+
+/// Re-hash binaries of `crate_info` installed in `install_path` and
+/// compare them with the recorded checksums.
+///
+/// Return the name of each binary along with its status.
+///
+/// This function uses blocking I/O.
+pub fn verify_bins(
+    crate_info: &CrateInfo,
+    install_path: &Path,
+) -> Result<Vec<(CompactString, BinStatus)>, BinstallError> {
+    crate_info
+        .bins
+        .iter()
+        .map(|bin| {
+            let status = match bins::sha256_file(&install_path.join(bin.as_str())) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => BinStatus::Missing,
+                Err(err) => return Err(err.into()),
+                Ok(found) => match crate_info.bins_sha256.get(bin) {
+                    None => BinStatus::Unrecorded,
+                    Some(expected) if *expected == found => BinStatus::Intact,
+                    Some(_) => BinStatus::Modified { found },
+                },
+            };
+
+            Ok((bin.clone(), status))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use semver::Version;
+
+    use super::*;
+    use crate::manifests::crate_info::CrateSource;
+
+    #[test]
+    fn test_verify_bins() {
+        let install_path = tempfile::tempdir().unwrap();
+        let install_path = install_path.path();
+
+        for bin in ["intact", "modified", "unrecorded"] {
+            fs::write(install_path.join(bin), bin).unwrap();
+        }
+
+        let expected = bins::sha256_file(&install_path.join("intact")).unwrap();
+
+        let crate_info = CrateInfo {
+            name: "a".into(),
+            version_req: "*".into(),
+            current_version: Version::new(0, 1, 0),
+            source: CrateSource::cratesio_registry(),
+            target: "x86_64-unknown-linux-gnu".into(),
+            bins: vec![
+                "intact".into(),
+                "modified".into(),
+                "missing".into(),
+                "unrecorded".into(),
+            ],
+            package_sha256: None,
+            // Only the checksum recorded for "intact" is right.
+            bins_sha256: ["intact", "modified", "missing"]
+                .into_iter()
+                .map(|bin| (bin.into(), expected.clone()))
+                .collect(),
+            package_source: None,
+            install_time: None,
+        };
+
+        let found = bins::sha256_file(&install_path.join("modified")).unwrap();
+
+        assert_eq!(
+            verify_bins(&crate_info, install_path).unwrap(),
+            [
+                ("intact".into(), BinStatus::Intact),
+                ("modified".into(), BinStatus::Modified { found }),
+                ("missing".into(), BinStatus::Missing),
+                ("unrecorded".into(), BinStatus::Unrecorded),
+            ]
+        );
+    }
+}
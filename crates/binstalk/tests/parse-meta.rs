@@ -1,4 +1,4 @@
-use binstalk::ops::resolve::load_manifest_path;
+use binstalk::ops::{resolve::load_manifest_path, validate::validate_meta};
 use cargo_toml_workspace::cargo_toml::{Edition, Product};
 use std::path::PathBuf;
 
@@ -14,8 +14,13 @@ fn parse_meta() {
 
     assert_eq!(&package.name, "cargo-binstall-test");
 
+    assert!(validate_meta(&meta).is_empty());
+
     assert_eq!(
-        meta.pkg_url.as_deref().unwrap(),
+        meta.pkg_url
+            .as_ref()
+            .and_then(|pkg_url| pkg_url.first())
+            .unwrap(),
         "{ repo }/releases/download/v{ version }/{ name }-{ target }.{ archive-format }"
     );
 
@@ -31,6 +31,9 @@ cfg_if! {
 /// If target_os is mac and it is aarch64, then aarch64 is preferred
 /// to x86_64.
 ///
+/// If target_os is linux and the cpu is armv7, then armv7 is preferred
+/// to arm (ARMv6).
+///
 /// Check [this issue](https://github.com/ryankurte/cargo-binstall/issues/155)
 /// for more information.
 pub async fn detect_targets() -> Vec<String> {
@@ -61,6 +64,20 @@ pub async fn detect_targets() -> Vec<String> {
     }
 }
 
+/// Detect the `(major, minor)` version of glibc on the host.
+///
+/// Return `None` if the host is not linux or does not use glibc, or if
+/// the version cannot be detected.
+pub async fn detect_glibc_version() -> Option<(u32, u32)> {
+    cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            linux::detect_glibc_version().await
+        } else {
+            None
+        }
+    }
+}
+
 /// Figure out what the host target is using `rustc`.
 /// If `rustc` is absent, then it would return `None`.
 ///
@@ -76,9 +76,23 @@ pub(super) async fn detect_targets(target: String) -> Vec<String> {
     }
     .into_iter()
     .flatten()
+    .flat_map(|target| {
+        let arm_fallback_target = arm_fallback_target(&target);
+        [Some(target), arm_fallback_target]
+    })
+    .flatten()
     .collect()
 }
 
+/// ARMv7 cpus can also run binaries built for ARMv6, which uses
+/// `arm` as cpu_arch in the target, e.g. `arm-unknown-linux-gnueabihf`
+/// for `armv7-unknown-linux-gnueabihf`.
+fn arm_fallback_target(target: &str) -> Option<String> {
+    target
+        .strip_prefix("armv7-")
+        .map(|rest| format!("arm-{rest}"))
+}
+
 async fn is_gnu_ld(cmd: String) -> bool {
     get_ld_flavor(&cmd).await == Some(Libc::Gnu)
 }
@@ -115,7 +129,23 @@ You are not meant to run this directly.
     if status.success() {
         // Executing glibc ldd or /lib/ld-linux-{cpu_arch}.so.1 will always
         // succeeds.
-        (stdout.contains("GLIBC") || stdout.contains("GNU libc")).then_some(Libc::Gnu)
+        if !(stdout.contains("GLIBC") || stdout.contains("GNU libc")) {
+            return None;
+        }
+
+        match parse_glibc_version(&stdout) {
+            // The gnu artifacts built by rustc requires glibc >= 2.17,
+            // so musl artifacts has to be used on older glibc.
+            Some(version) if version < MIN_GLIBC_VERSION => {
+                #[cfg(feature = "tracing")]
+                debug!(
+                    "`{cmd}` uses glibc {}.{}, which is older than the minimum {}.{} supported",
+                    version.0, version.1, MIN_GLIBC_VERSION.0, MIN_GLIBC_VERSION.1
+                );
+                None
+            }
+            _ => Some(Libc::Gnu),
+        }
     } else if status.code() == Some(1) {
         // On Alpine, executing both the gcompat glibc and the ldd and
         // /lib/ld-musl-{cpu_arch}.so.1 will fail with exit status 1.
@@ -149,6 +179,71 @@ You are not meant to run this directly.
     }
 }
 
+/// Detect the version of glibc on the host by running `ldd --version`,
+/// falling back to `getconf GNU_LIBC_VERSION`.
+pub(super) async fn detect_glibc_version() -> Option<(u32, u32)> {
+    for (cmd, arg) in [("ldd", "--version"), ("getconf", "GNU_LIBC_VERSION")] {
+        let Output { status, stdout, .. } = match Command::new(cmd)
+            .arg(arg)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .await
+        {
+            Ok(output) => output,
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                debug!("Running `{cmd} {arg}`: err={_err:?}");
+                continue;
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&stdout);
+
+        #[cfg(feature = "tracing")]
+        debug!("`{cmd} {arg}`: status={status}, stdout='{stdout}'");
+
+        // musl's ldd also exits with an error on `--version`.
+        if status.success()
+            && (stdout.contains("GLIBC")
+                || stdout.contains("GNU libc")
+                || stdout.starts_with("glibc"))
+        {
+            if let Some(version) = parse_glibc_version(&stdout) {
+                return Some(version);
+            }
+        }
+    }
+
+    None
+}
+
+/// Minimum glibc version required by rust gnu targets.
+///
+/// Artifacts built against a newer glibc have to be checked against
+/// [`detect_glibc_version`] once they are downloaded.
+const MIN_GLIBC_VERSION: (u32, u32) = (2, 17);
+
+/// Parse glibc version from the first line of the output of
+/// `ld.so --version` or `libc.so.6`, e.g.
+/// `ld.so (GNU libc) stable release version 2.17, by Roland McGrath et al.`,
+/// of `ldd --version`, e.g. `ldd (Ubuntu GLIBC 2.35-0ubuntu3.1) 2.35`,
+/// or of `getconf GNU_LIBC_VERSION`, e.g. `glibc 2.35`.
+fn parse_glibc_version(stdout: &str) -> Option<(u32, u32)> {
+    let line = stdout.lines().next()?;
+    let version = match line.split_once("release version ") {
+        Some((_, version)) => version,
+        None => line.rsplit(' ').next()?,
+    };
+
+    let (major, rest) = version.split_once('.')?;
+    let minor_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+
+    Some((major.parse().ok()?, rest[..minor_len].parse().ok()?))
+}
+
 #[derive(Eq, PartialEq)]
 enum Libc {
     Gnu,
@@ -164,3 +259,67 @@ impl<T> Drop for AutoAbortHandle<T> {
         self.0.abort();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_glibc_version() {
+        for (stdout, version) in [
+            // ld.so --version
+            (
+                "ld.so (GNU libc) stable release version 2.17, by Roland McGrath et al.\n\
+                Copyright (C) 2012 Free Software Foundation, Inc.\n",
+                (2, 17),
+            ),
+            (
+                "ld.so (Ubuntu GLIBC 2.35-0ubuntu3.1) stable release version 2.35.\n\
+                Copyright (C) 2022 Free Software Foundation, Inc.\n",
+                (2, 35),
+            ),
+            // libc.so.6
+            (
+                "GNU C Library (Debian GLIBC 2.36-9+deb12u4) stable release version 2.36.\n\
+                Copyright (C) 2022 Free Software Foundation, Inc.\n",
+                (2, 36),
+            ),
+            (
+                "GNU C Library (GNU libc) release release version 2.38.\n",
+                (2, 38),
+            ),
+            // ldd --version
+            (
+                "ldd (GNU libc) 2.17\n\
+                Copyright (C) 2012 Free Software Foundation, Inc.\n",
+                (2, 17),
+            ),
+            (
+                "ldd (Ubuntu GLIBC 2.35-0ubuntu3.1) 2.35\n\
+                Copyright (C) 2022 Free Software Foundation, Inc.\n",
+                (2, 35),
+            ),
+            // getconf GNU_LIBC_VERSION
+            ("glibc 2.35\n", (2, 35)),
+        ] {
+            assert_eq!(parse_glibc_version(stdout), Some(version), "{stdout}");
+        }
+
+        assert_eq!(parse_glibc_version(""), None);
+        assert_eq!(parse_glibc_version("musl libc (x86_64)\n"), None);
+    }
+
+    #[test]
+    fn test_arm_fallback_target() {
+        assert_eq!(
+            arm_fallback_target("armv7-unknown-linux-gnueabihf").as_deref(),
+            Some("arm-unknown-linux-gnueabihf")
+        );
+        assert_eq!(
+            arm_fallback_target("armv7-unknown-linux-musleabihf").as_deref(),
+            Some("arm-unknown-linux-musleabihf")
+        );
+        assert_eq!(arm_fallback_target("arm-unknown-linux-gnueabihf"), None);
+        assert_eq!(arm_fallback_target("x86_64-unknown-linux-gnu"), None);
+    }
+}
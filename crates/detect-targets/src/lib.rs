@@ -65,7 +65,7 @@
 //! ```
 
 mod detect;
-pub use detect::detect_targets;
+pub use detect::{detect_glibc_version, detect_targets};
 
 mod desired_targets;
 pub use desired_targets::{get_desired_targets, DesiredTargets};
@@ -19,6 +19,15 @@ pub fn template(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             Item::Key(key) => quote! {
                 ::leon::Item::Key(#key)
             },
+            Item::If(condition) => quote! {
+                ::leon::Item::If(#condition)
+            },
+            Item::Else => quote! {
+                ::leon::Item::Else
+            },
+            Item::End => quote! {
+                ::leon::Item::End
+            },
         });
 
     quote! {
@@ -18,4 +18,18 @@ fn test() {
         leon_macros::template!("a{ 1 } c"),
         Template::new(&[Item::Text("a"), Item::Key("1"), Item::Text(" c")], None),
     );
+
+    assert_eq!(
+        leon_macros::template!("{ if 1 }a{ else }b{ end }"),
+        Template::new(
+            &[
+                Item::If("1"),
+                Item::Text("a"),
+                Item::Else,
+                Item::Text("b"),
+                Item::End
+            ],
+            None
+        ),
+    );
 }
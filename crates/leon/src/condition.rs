@@ -0,0 +1,131 @@
+use crate::{
+    filter::{split_key, Filter},
+    Values,
+};
+
+/// The condition of a conditional section, e.g.
+/// `target == x86_64-pc-windows-msvc` in
+/// `{ if target == x86_64-pc-windows-msvc }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Condition<'s> {
+    /// The key along with its filters.
+    pub(crate) key: &'s str,
+    pub(crate) op: Op<'s>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op<'s> {
+    /// The value is present and not empty.
+    IsSet,
+    /// The value is equal to the text.
+    Eq(&'s str),
+    /// The value is missing or not equal to the text.
+    Ne(&'s str),
+}
+
+impl<'s> Condition<'s> {
+    /// Parse a condition, return `None` if it is malformed.
+    pub(crate) fn parse(condition: &'s str) -> Option<Self> {
+        let op_index = ["==", "!="]
+            .iter()
+            .filter_map(|op| condition.find(op))
+            .min();
+
+        let (key, op) = match op_index {
+            Some(index) => {
+                let text = condition[index + 2..].trim();
+                let op = if &condition[index..index + 2] == "==" {
+                    Op::Eq(text)
+                } else {
+                    Op::Ne(text)
+                };
+
+                (condition[..index].trim(), op)
+            }
+            None => (condition.trim(), Op::IsSet),
+        };
+
+        let (name, mut filters) = split_key(key);
+        if name.is_empty() || filters.any(|filter| Filter::parse(filter).is_none()) {
+            return None;
+        }
+
+        Some(Self { key, op })
+    }
+
+    /// Evaluate the condition, missing keys are treated as not set.
+    pub(crate) fn eval(&self, values: &dyn Values) -> bool {
+        let (name, filters) = split_key(self.key);
+        let value = filters
+            .filter_map(Filter::parse)
+            .fold(values.get_value(name), |value, filter| filter.apply(value));
+
+        match self.op {
+            Op::IsSet => value.map_or(false, |value| !value.is_empty()),
+            Op::Eq(text) => value.as_deref() == Some(text),
+            Op::Ne(text) => value.as_deref() != Some(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Condition, Op};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Condition::parse(" target "),
+            Some(Condition {
+                key: "target",
+                op: Op::IsSet
+            })
+        );
+        assert_eq!(
+            Condition::parse("target == x86_64-pc-windows-msvc"),
+            Some(Condition {
+                key: "target",
+                op: Op::Eq("x86_64-pc-windows-msvc")
+            })
+        );
+        assert_eq!(
+            Condition::parse("target-libc | default(none) != musl"),
+            Some(Condition {
+                key: "target-libc | default(none)",
+                op: Op::Ne("musl")
+            })
+        );
+        assert_eq!(
+            Condition::parse("binary-ext =="),
+            Some(Condition {
+                key: "binary-ext",
+                op: Op::Eq("")
+            })
+        );
+
+        assert_eq!(Condition::parse(""), None);
+        assert_eq!(Condition::parse("== win64"), None);
+        assert_eq!(Condition::parse("target | capitalize"), None);
+    }
+
+    #[test]
+    fn eval() {
+        let values = [("target", "x86_64-pc-windows-msvc"), ("binary-ext", "")];
+        let eval = |condition| Condition::parse(condition).unwrap().eval(&values);
+
+        assert!(eval("target"));
+        assert!(!eval("binary-ext"));
+        assert!(!eval("target-libc"));
+
+        assert!(eval("target == x86_64-pc-windows-msvc"));
+        assert!(eval(
+            "target | replace(x86_64-pc-windows-msvc, win64) == win64"
+        ));
+        assert!(!eval("target == aarch64-pc-windows-msvc"));
+        assert!(!eval("target-libc == musl"));
+
+        assert!(eval("target != aarch64-pc-windows-msvc"));
+        assert!(eval("target-libc != musl"));
+        assert!(!eval("target | uppercase != X86_64-PC-WINDOWS-MSVC"));
+    }
+}
@@ -59,6 +59,15 @@ enum ErrorKind {
 
     #[error("Escapes are not allowed in keys.")]
     KeyEscape,
+
+    #[error("This key uses an unknown or malformed filter.")]
+    UnknownFilter,
+
+    #[error("This condition is malformed. It should be `key`, `key == text` or `key != text`.")]
+    ConditionMalformed,
+
+    #[error("This does not match a conditional section. Each `if` needs an `end`, with at most one `else` in between.")]
+    ConditionalUnbalanced,
 }
 
 impl ParseError {
@@ -86,4 +95,16 @@ impl ParseError {
     pub(crate) fn key_escape(src: &str, start: usize, end: usize) -> Self {
         Self::new(src, start, end, ErrorKind::KeyEscape)
     }
+
+    pub(crate) fn unknown_filter(src: &str, start: usize, end: usize) -> Self {
+        Self::new(src, start, end, ErrorKind::UnknownFilter)
+    }
+
+    pub(crate) fn condition_malformed(src: &str, start: usize, end: usize) -> Self {
+        Self::new(src, start, end, ErrorKind::ConditionMalformed)
+    }
+
+    pub(crate) fn conditional_unbalanced(src: &str, start: usize, end: usize) -> Self {
+        Self::new(src, start, end, ErrorKind::ConditionalUnbalanced)
+    }
 }
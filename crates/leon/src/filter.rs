@@ -0,0 +1,97 @@
+use std::borrow::Cow;
+
+/// A filter applied to the value of a key, e.g. `lowercase` in
+/// `{ name | lowercase }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Filter<'s> {
+    /// Convert the value to lowercase.
+    Lowercase,
+    /// Convert the value to uppercase.
+    Uppercase,
+    /// Use the argument if the value is missing.
+    Default(&'s str),
+    /// Replace all occurrences of the first argument with the second one.
+    Replace(&'s str, &'s str),
+}
+
+impl<'s> Filter<'s> {
+    /// Parse one filter, return `None` if it is not recognized.
+    pub(crate) fn parse(filter: &'s str) -> Option<Self> {
+        let filter = filter.trim();
+
+        let (name, args) = match filter.split_once('(') {
+            Some((name, args)) => (name.trim_end(), Some(args.strip_suffix(')')?.trim())),
+            None => (filter, None),
+        };
+
+        match (name, args) {
+            ("lowercase", None) => Some(Self::Lowercase),
+            ("uppercase", None) => Some(Self::Uppercase),
+            ("default", Some(default)) => Some(Self::Default(default)),
+            ("replace", Some(args)) => {
+                let (from, to) = args.split_once(',')?;
+                let from = from.trim_end();
+
+                if from.is_empty() {
+                    None
+                } else {
+                    Some(Self::Replace(from, to.trim_start()))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn apply<'a>(self, value: Option<Cow<'a, str>>) -> Option<Cow<'a, str>>
+    where
+        's: 'a,
+    {
+        match self {
+            Self::Lowercase => value.map(|value| Cow::Owned(value.to_lowercase())),
+            Self::Uppercase => value.map(|value| Cow::Owned(value.to_uppercase())),
+            Self::Default(default) => value.or(Some(Cow::Borrowed(default))),
+            Self::Replace(from, to) => value.map(|value| {
+                if value.contains(from) {
+                    Cow::Owned(value.replace(from, to))
+                } else {
+                    value
+                }
+            }),
+        }
+    }
+}
+
+/// Split a key into its name and its filters.
+pub(crate) fn split_key(key: &str) -> (&str, impl Iterator<Item = &str>) {
+    let mut parts = key.split('|');
+    // unwrap: split always returns at least one item
+    let name = parts.next().unwrap().trim();
+
+    (name, parts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Filter;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Filter::parse("lowercase"), Some(Filter::Lowercase));
+        assert_eq!(Filter::parse(" uppercase "), Some(Filter::Uppercase));
+        assert_eq!(Filter::parse("default(zip)"), Some(Filter::Default("zip")));
+        assert_eq!(
+            Filter::parse("replace(x86_64-pc-windows-msvc, win64)"),
+            Some(Filter::Replace("x86_64-pc-windows-msvc", "win64"))
+        );
+        assert_eq!(
+            Filter::parse("replace(-, )"),
+            Some(Filter::Replace("-", ""))
+        );
+
+        assert_eq!(Filter::parse("lower"), None);
+        assert_eq!(Filter::parse("default"), None);
+        assert_eq!(Filter::parse("default(zip"), None);
+        assert_eq!(Filter::parse("replace(a)"), None);
+        assert_eq!(Filter::parse("replace(, a)"), None);
+    }
+}
@@ -2,8 +2,8 @@
 //!
 //! Leon parses a template string into a list of tokens, and then substitutes
 //! provided values in. Unlike other templating engines, it is extremely simple:
-//! it supports no logic, only replaces. It is even simpler than `format!()`,
-//! albeit with a similar syntax.
+//! it supports no logic beyond replaces, a few filters and simple conditional
+//! sections. It is even simpler than `format!()`, albeit with a similar syntax.
 //!
 //! # Syntax
 //!
@@ -15,6 +15,13 @@
 //! A replacement is denoted by `{` and `}`. The contents of the braces, trimmed
 //! of any whitespace, are the key. Any text outside of braces is left as-is.
 //!
+//! Keys can be followed by filters separated by `|`, e.g. `{ name | lowercase }`,
+//! see [`Template::parse`] for the list of filters.
+//!
+//! Parts of the template can be rendered conditionally, e.g.
+//! `{ if target == x86_64-pc-windows-msvc }win64{ else }{ target }{ end }`,
+//! see [`Template::parse`] for the supported conditions.
+//!
 //! To escape a brace, use `\{` or `\}`. To escape a backslash, use `\\`. Keys
 //! cannot contain escapes.
 //!
@@ -133,7 +140,8 @@
 //! # Errors
 //!
 //! Leon will return a [`ParseError`] if the template fails to
-//! parse. This can happen if there are unbalanced braces, or if a key is empty.
+//! parse. This can happen if there are unbalanced braces or conditional
+//! sections, or if a key is empty.
 //!
 //! Leon will return a [`RenderError::MissingKey`] if a key is missing from keyed
 //! values passed to [`Template::render()`], unless a default value is provided
@@ -150,7 +158,9 @@ pub use template::*;
 #[doc(inline)]
 pub use values::*;
 
+mod condition;
 mod error;
+mod filter;
 mod macros;
 mod parser;
 mod template;
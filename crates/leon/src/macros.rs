@@ -2,6 +2,15 @@
 #[macro_export]
 macro_rules! __template_item {
     () => {};
+    ({ if $condition:literal }) => {
+        $crate::Item::If($condition)
+    };
+    ({ else }) => {
+        $crate::Item::Else
+    };
+    ({ end }) => {
+        $crate::Item::End
+    };
     ({ $key:literal }) => {
         $crate::Item::Key($key)
     };
@@ -52,6 +61,14 @@ macro_rules! __template_impl {
 /// );
 /// ```
 ///
+/// With a conditional section:
+///
+/// ```
+/// let template = leon::template!({ if "name" }, "Hello ", { "name" }, { else }, "Hi", { end });
+/// assert_eq!(template.render(&[("name", "Ba Jin")]).unwrap(), "Hello Ba Jin");
+/// assert_eq!(template.render(&[("city", "Chengdu")]).unwrap(), "Hi");
+/// ```
+///
 /// With a default:
 ///
 /// ```
@@ -152,5 +169,20 @@ mod tests {
                 None
             )
         );
+
+        // Conditionals
+        assert_eq!(
+            template!({ if "k1 == 1" }, "1", { else }, { "k1" }, { end }),
+            Template::new(
+                &[
+                    Item::If("k1 == 1"),
+                    Item::Text("1"),
+                    Item::Else,
+                    Item::Key("k1"),
+                    Item::End,
+                ],
+                None
+            )
+        );
     }
 }
@@ -1,9 +1,17 @@
-use crate::{Item, ParseError, Template};
+use crate::{
+    condition::Condition,
+    filter::{split_key, Filter},
+    Item, ParseError, Template,
+};
 
 impl<'s> Template<'s> {
     pub(crate) fn parse_items(source: &'s str) -> Result<Vec<Item<'s>>, ParseError> {
         let mut items = Vec::new();
 
+        // For each conditional section opened, the span of its `if` and
+        // whether it has an `else`.
+        let mut sections: Vec<((usize, usize), bool)> = Vec::new();
+
         let mut start = 0;
         let mut s = source;
 
@@ -22,6 +30,10 @@ impl<'s> Template<'s> {
                     items.push(Item::Text(s));
                 }
 
+                if let Some(((if_start, if_end), _)) = sections.pop() {
+                    return Err(ParseError::conditional_unbalanced(source, if_start, if_end));
+                }
+
                 break Ok(items);
             };
 
@@ -54,10 +66,35 @@ impl<'s> Template<'s> {
                     }
 
                     let k = key.trim();
-                    if k.is_empty() {
-                        return Err(ParseError::key_empty(source, start, start + key.len() + 1));
+                    let end = start + key.len() + 1;
+
+                    if let Some(condition) = k
+                        .strip_prefix("if")
+                        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+                    {
+                        let condition = condition.trim();
+                        if Condition::parse(condition).is_none() {
+                            return Err(ParseError::condition_malformed(source, start, end));
+                        }
+                        sections.push(((start, end), false));
+                        items.push(Item::If(condition));
+                    } else if k == "else" {
+                        match sections.last_mut() {
+                            Some((_, has_else @ false)) => *has_else = true,
+                            _ => {
+                                return Err(ParseError::conditional_unbalanced(source, start, end))
+                            }
+                        }
+                        items.push(Item::Else);
+                    } else if k == "end" {
+                        if sections.pop().is_none() {
+                            return Err(ParseError::conditional_unbalanced(source, start, end));
+                        }
+                        items.push(Item::End);
+                    } else {
+                        Self::check_key(source, k, start, end)?;
+                        items.push(Item::Key(k));
                     }
-                    items.push(Item::Key(k));
 
                     // Move cursor forward
                     //       for the '{'
@@ -73,6 +110,18 @@ impl<'s> Template<'s> {
             }
         }
     }
+
+    fn check_key(source: &str, k: &str, start: usize, end: usize) -> Result<(), ParseError> {
+        let (name, mut filters) = split_key(k);
+        if name.is_empty() {
+            return Err(ParseError::key_empty(source, start, end));
+        }
+        if filters.any(|filter| Filter::parse(filter).is_none()) {
+            return Err(ParseError::unknown_filter(source, start, end));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -157,6 +206,39 @@ mod test_valid {
         assert_eq!(template, template!({ "word" }));
     }
 
+    #[test]
+    fn key_with_filters() {
+        let template = Template::parse("{ name | lowercase | replace(a, b) }").unwrap();
+        assert_eq!(template, template!({ "name | lowercase | replace(a, b)" }));
+    }
+
+    #[test]
+    fn conditionals() {
+        let template = Template::parse(
+            "{ if target == x86_64-pc-windows-msvc }win64{else}{ target }{ end }\
+            { if ext }.{ ext }{ if ext != zip }.gz{ end }{ end }{ iffy }",
+        )
+        .unwrap();
+        assert_eq!(
+            template,
+            template!(
+                { if "target == x86_64-pc-windows-msvc" },
+                "win64",
+                { else },
+                { "target" },
+                { end },
+                { if "ext" },
+                ".",
+                { "ext" },
+                { if "ext != zip" },
+                ".gz",
+                { end },
+                { end },
+                { "iffy" }
+            )
+        );
+    }
+
     #[test]
     fn key_both_whitespace() {
         let template = Template::parse(
@@ -320,6 +402,66 @@ mod test_error {
         assert_eq!(template, ParseError::key_empty(r"nothing: { }", 9, 11));
     }
 
+    #[test]
+    fn key_only_filter() {
+        let template = Template::parse(r"nothing: { | lowercase }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::key_empty(r"nothing: { | lowercase }", 9, 23)
+        );
+    }
+
+    #[test]
+    fn unknown_filter() {
+        let template = Template::parse(r"hello { name | capitalize }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::unknown_filter(r"hello { name | capitalize }", 6, 26)
+        );
+    }
+
+    #[test]
+    fn condition_malformed() {
+        let template = Template::parse(r"{ if }a{ end }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::condition_malformed(r"{ if }a{ end }", 0, 5)
+        );
+
+        let template = Template::parse(r"{ if == a }a{ end }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::condition_malformed(r"{ if == a }a{ end }", 0, 10)
+        );
+    }
+
+    #[test]
+    fn conditional_unbalanced() {
+        let template = Template::parse(r"{ if a }a").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::conditional_unbalanced(r"{ if a }a", 0, 7)
+        );
+
+        let template = Template::parse(r"a{ end }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::conditional_unbalanced(r"a{ end }", 1, 7)
+        );
+
+        let template = Template::parse(r"{ else }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::conditional_unbalanced(r"{ else }", 0, 7)
+        );
+
+        let template = Template::parse(r"{ if a }{ else }{ else }{ end }").unwrap_err();
+        assert_eq!(
+            template,
+            ParseError::conditional_unbalanced(r"{ if a }{ else }{ else }{ end }", 16, 23)
+        );
+    }
+
     #[test]
     fn bad_escape() {
         let template = Template::parse(r"not \a thing").unwrap_err();
@@ -1,6 +1,10 @@
 use std::{borrow::Cow, fmt::Display, io::Write, ops};
 
-use crate::{ParseError, RenderError, Values};
+use crate::{
+    condition::Condition,
+    filter::{split_key, Filter},
+    ParseError, RenderError, Values,
+};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Template<'s> {
@@ -12,6 +16,14 @@ pub struct Template<'s> {
 pub enum Item<'s> {
     Text(&'s str),
     Key(&'s str),
+    /// Start of a conditional section, holding its condition, e.g.
+    /// `target == x86_64-pc-windows-msvc` for
+    /// `{ if target == x86_64-pc-windows-msvc }`.
+    If(&'s str),
+    /// `{ else }` of the innermost conditional section.
+    Else,
+    /// `{ end }` of the innermost conditional section.
+    End,
 }
 
 impl<'s> Template<'s> {
@@ -70,8 +82,33 @@ impl<'s> Template<'s> {
     /// A replacement is denoted by `{` and `}`. The contents of the braces, trimmed
     /// of any whitespace, are the key. Any text outside of braces is left as-is.
     ///
+    /// The key can be followed by filters separated by `|`, which are applied
+    /// to the value in order:
+    ///
+    /// - `lowercase` and `uppercase` change the case of the value.
+    /// - `default(text)` uses `text` if the value is missing.
+    /// - `replace(from, to)` replaces all occurrences of `from` with `to`.
+    ///
+    /// ```plain
+    /// { name | lowercase }-{ target | replace(x86_64-pc-windows-msvc, win64) }
+    /// ```
+    ///
+    /// A conditional section starts with `{ if condition }`, ends with `{ end }`
+    /// and can have an `{ else }` in between. Sections can be nested. The
+    /// condition is one of the following, where the key can have filters:
+    ///
+    /// - `key` is true if the value is present and not empty.
+    /// - `key == text` is true if the value is `text`.
+    /// - `key != text` is true if the value is missing or not `text`.
+    ///
+    /// Keys in sections that are not rendered do not need a value.
+    ///
+    /// ```plain
+    /// { if target == x86_64-pc-windows-msvc }win64{ else }{ target }{ end }
+    /// ```
+    ///
     /// To escape a brace, use `\{` or `\}`. To escape a backslash, use `\\`. Keys
-    /// cannot contain escapes.
+    /// cannot contain escapes, and `if`, `else` and `end` are reserved.
     ///
     /// ```plain
     /// \{ leon \}
@@ -105,16 +142,48 @@ impl<'s> Template<'s> {
         writer: &mut dyn Write,
         values: &dyn Values,
     ) -> Result<(), RenderError> {
+        // For each conditional section entered, whether its parent is
+        // rendered and whether its condition holds.
+        let mut sections: Vec<(bool, bool)> = Vec::new();
+        let is_rendered = |sections: &[(bool, bool)]| {
+            sections
+                .last()
+                .map_or(true, |(parent, condition)| *parent && *condition)
+        };
+
         for token in self.items.as_ref() {
             match token {
+                Item::If(condition) => {
+                    let parent = is_rendered(&sections);
+                    // Conditions are only evaluated if rendered, and
+                    // malformed ones are false.
+                    let condition = parent
+                        && Condition::parse(condition)
+                            .map_or(false, |condition| condition.eval(values));
+                    sections.push((parent, condition));
+                }
+                Item::Else => {
+                    if let Some((_, condition)) = sections.last_mut() {
+                        *condition = !*condition;
+                    }
+                }
+                Item::End => {
+                    sections.pop();
+                }
+                _ if !is_rendered(&sections) => (),
                 Item::Text(text) => writer.write_all(text.as_bytes())?,
                 Item::Key(key) => {
-                    if let Some(value) = values.get_value(key) {
+                    let (name, filters) = split_key(key);
+                    let value = filters
+                        .filter_map(Filter::parse)
+                        .fold(values.get_value(name), |value, filter| filter.apply(value));
+
+                    if let Some(value) = value {
                         writer.write_all(value.as_bytes())?;
                     } else if let Some(default) = &self.default {
                         writer.write_all(default.as_bytes())?;
                     } else {
-                        return Err(RenderError::MissingKey(key.to_string()));
+                        return Err(RenderError::MissingKey(name.to_string()));
                     }
                 }
             }
@@ -127,8 +196,8 @@ impl<'s> Template<'s> {
             self.items
                 .iter()
                 .map(|item| match item {
-                    Item::Key(_) => 0,
                     Item::Text(t) => t.len(),
+                    _ => 0,
                 })
                 .sum(),
         );
@@ -143,18 +212,23 @@ impl<'s> Template<'s> {
         self.has_any_of_keys(&[key])
     }
 
-    /// If the template contains any one of the `keys`.
+    /// If the template contains any one of the `keys`, ignoring their filters.
     pub fn has_any_of_keys(&self, keys: &[&str]) -> bool {
-        self.items.iter().any(|token| match token {
-            Item::Key(k) => keys.contains(k),
-            _ => false,
-        })
+        self.keys().any(|key| keys.contains(&key))
     }
 
-    /// Returns all keys in this template.
-    pub fn keys(&self) -> impl Iterator<Item = &&str> {
+    /// Returns names of all keys in this template, including the ones in
+    /// conditions, without their filters.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.raw_keys().map(|key| split_key(key).0)
+    }
+
+    /// Returns all keys in this template as written, including their filters,
+    /// e.g. `name | lowercase`.
+    pub fn raw_keys(&self) -> impl Iterator<Item = &str> {
         self.items.iter().filter_map(|token| match token {
-            Item::Key(k) => Some(k),
+            Item::Key(k) => Some(*k),
+            Item::If(condition) => Condition::parse(condition).map(|condition| condition.key),
             _ => None,
         })
     }
@@ -255,7 +329,7 @@ impl<'s, 'item: 's> ops::Add<&Item<'item>> for Template<'s> {
 
 #[cfg(test)]
 mod test {
-    use crate::Template;
+    use crate::{RenderError, Template};
 
     #[test]
     fn concat_templates() {
@@ -267,6 +341,93 @@ mod test {
         );
     }
 
+    #[test]
+    fn render_filters() {
+        let template =
+            Template::parse("{ name | uppercase }-{ target | replace(x86_64-pc-windows-msvc, win64) | lowercase }.{ ext | default(zip) }")
+                .unwrap();
+        assert_eq!(
+            template
+                .render(&[("name", "leon"), ("target", "X86_64-pc-windows-msvc")])
+                .unwrap(),
+            "LEON-x86_64-pc-windows-msvc.zip"
+        );
+        assert_eq!(
+            template
+                .render(&[("name", "leon"), ("target", "x86_64-pc-windows-msvc")])
+                .unwrap(),
+            "LEON-win64.zip"
+        );
+        assert!(template.has_key("target"));
+        assert!(template.has_key("ext"));
+        assert_eq!(
+            template.keys().collect::<Vec<_>>(),
+            ["name", "target", "ext"]
+        );
+        assert_eq!(
+            template.raw_keys().collect::<Vec<_>>(),
+            [
+                "name | uppercase",
+                "target | replace(x86_64-pc-windows-msvc, win64) | lowercase",
+                "ext | default(zip)"
+            ]
+        );
+    }
+
+    #[test]
+    fn render_conditionals() {
+        let template = Template::parse(
+            "{ name }-{ if target == x86_64-pc-windows-msvc }win64{ else }{ target }{ end }\
+            { if ext }.{ ext }{ if ext != zip }.gz{ end }{ end }",
+        )
+        .unwrap();
+        assert_eq!(
+            template
+                .render(&[("name", "leon"), ("target", "x86_64-pc-windows-msvc")])
+                .unwrap(),
+            "leon-win64"
+        );
+        assert_eq!(
+            template
+                .render(&[
+                    ("name", "leon"),
+                    ("target", "aarch64-unknown-linux-gnu"),
+                    ("ext", "tar")
+                ])
+                .unwrap(),
+            "leon-aarch64-unknown-linux-gnu.tar.gz"
+        );
+        assert_eq!(
+            template
+                .render(&[
+                    ("name", "leon"),
+                    ("target", "x86_64-pc-windows-msvc"),
+                    ("ext", "zip")
+                ])
+                .unwrap(),
+            "leon-win64.zip"
+        );
+
+        // Keys in the section that is not rendered are not needed.
+        assert_eq!(
+            Template::parse("{ if target }{ target }{ else }none{ end }")
+                .unwrap()
+                .render(&[("name", "leon")])
+                .unwrap(),
+            "none"
+        );
+        assert!(matches!(
+            template.render(&[("name", "leon")]),
+            Err(RenderError::MissingKey(key)) if key == "target"
+        ));
+
+        assert!(template.has_key("ext"));
+        assert_eq!(
+            template.keys().collect::<Vec<_>>(),
+            ["name", "target", "target", "ext", "ext", "ext"]
+        );
+    }
+
     #[test]
     fn test_cast() {
         fn inner<'a>(_: &'a u32, _: Template<'a>) {}